@@ -1,30 +1,178 @@
-fn parse_input(input_str: &str) -> Vec<i32> {
+use aoc_2021_rs::puzzle::{self, Example};
+
+fn parse_measurement(line_idx: usize, line: &str) -> Result<i64, String> {
+    line.trim()
+        .parse()
+        .map_err(|_| format!("Line {}: Could not parse {:?}", line_idx + 1, line))
+}
+
+fn parse_input(input_str: &str) -> Result<Vec<i64>, String> {
     // Split by line, and parse each line into a number
     input_str
         .lines()
-        .map(|line| line.parse().unwrap())
+        .enumerate()
+        .map(|(line_idx, line)| parse_measurement(line_idx, line))
         .collect()
 }
 
-fn part1(depth_measurements: &[i32]) -> usize {
-    // Take the difference of each number and the one before it
-    depth_measurements
-        .windows(2)
-        .filter(|nums| nums[0] < nums[1])
+/// Compares sums of `window` measurements with the sum just before them, without storing
+/// more than `window` measurements at once.
+///
+/// Neighbouring windows share all but their first and last measurements, so the later window
+/// is larger exactly when its last measurement is larger than the earlier window's first.
+struct WindowIncreases<I> {
+    measurements: I,
+    /// The last `window` measurements, as a ring
+    recent: Vec<i64>,
+    window: usize,
+    /// How many measurements have been read so far
+    n_read: usize,
+}
+
+impl<I: Iterator<Item = i64>> Iterator for WindowIncreases<I> {
+    /// The index of the first measurement in a window that is larger than the one before it
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        for measurement in self.measurements.by_ref() {
+            let slot = self.n_read % self.window;
+            self.n_read += 1;
+
+            if self.recent.len() < self.window {
+                self.recent.push(measurement);
+                continue;
+            }
+
+            // This slot holds the measurement from `window` places ago
+            let dropped = std::mem::replace(&mut self.recent[slot], measurement);
+            if measurement > dropped {
+                return Some(self.n_read - self.window);
+            }
+        }
+        None
+    }
+}
+
+/// Every window of `window` measurements that is larger than the one before it
+fn increases<I: Iterator<Item = i64>>(
+    measurements: I,
+    window: usize,
+) -> Result<WindowIncreases<I>, String> {
+    if window == 0 {
+        return Err("The window must have at least one measurement in it".to_string());
+    }
+
+    Ok(WindowIncreases {
+        measurements,
+        recent: Vec::with_capacity(window),
+        window,
+        n_read: 0,
+    })
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Increases {
+    count: usize,
+    indices: Vec<usize>,
+}
+
+/// Count the increases, and keep the index of each one
+fn sweep<I: Iterator<Item = i64>>(measurements: I, window: usize) -> Result<Increases, String> {
+    let indices: Vec<usize> = increases(measurements, window)?.collect();
+    Ok(Increases {
+        count: indices.len(),
+        indices,
+    })
+}
+
+fn part1(depth_measurements: &[i64]) -> usize {
+    increases(depth_measurements.iter().copied(), 1)
+        .unwrap()
         .count()
 }
 
-fn part2(depth_measurements: &[i32]) -> usize {
-    // Sum up each window of length 3
-    let length_3_window_sums: Vec<i32> = depth_measurements
-        .windows(3)
-        .map(|nums| nums.iter().sum())
-        .collect();
+fn part2(depth_measurements: &[i64]) -> usize {
+    increases(depth_measurements.iter().copied(), 3)
+        .unwrap()
+        .count()
+}
+
+/// The examples from the puzzle description
+const EXAMPLES: &[Example] = &[Example {
+    input: "199
+200
+208
+210
+200
+207
+240
+269
+260
+263",
+    part1: Some("7"),
+    part2: Some("5"),
+}];
+
+/// Each part, solved straight from the input
+const PARTS: [Option<puzzle::Part>; 2] = [Some(solve_part1), Some(solve_part2)];
+
+fn solve_part1(input: &str) -> Result<String, String> {
+    Ok(part1(&parse_input(input)?).to_string())
+}
+
+fn solve_part2(input: &str) -> Result<String, String> {
+    Ok(part2(&parse_input(input)?).to_string())
+}
 
-    part1(&length_3_window_sums)
+/// Print how many windows of `window` increased, or the error that stopped the count
+fn report_windows(window: usize, result: Result<usize, String>) {
+    match result {
+        Ok(count) => println!("Windows of {} that increased: {}", window, count),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
 }
 
 fn main() {
+    // Pass `--example` to check the solutions against the puzzle's examples instead
+    if std::env::args().any(|arg| arg == "--example") {
+        std::process::exit(puzzle::run_examples(EXAMPLES, PARTS));
+    }
+
+    // Pass `--window=K` to list every window of K measurements that increases, then the count.
+    // With `--stdin` as well, the measurements are read from stdin as they arrive, instead of
+    // solving the input file
+    let window = std::env::args().find_map(|arg| {
+        arg.strip_prefix("--window=")
+            .map(|k| k.parse::<usize>().expect("Could not parse the window size"))
+    });
+    if let Some(window) = window.filter(|_| std::env::args().any(|arg| arg == "--stdin")) {
+        use std::io::BufRead;
+        let measurements = std::io::stdin()
+            .lock()
+            .lines()
+            .map_while(Result::ok)
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(
+                |(line_idx, line)| match parse_measurement(line_idx, &line) {
+                    Ok(measurement) => measurement,
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        std::process::exit(1);
+                    }
+                },
+            );
+
+        // Print each one as it is found, rather than keeping them
+        let result = increases(measurements, window)
+            .map(|window_increases| window_increases.inspect(|idx| println!("{}", idx)).count());
+        report_windows(window, result);
+        return;
+    }
+
     let setup_time = std::time::Instant::now();
 
     // Read the input file for day 1
@@ -32,7 +180,13 @@ fn main() {
         std::fs::read_to_string("input/day01.txt").expect("Failed to read day 1 input file");
 
     // Parse the input into a vector of numbers
-    let input = parse_input(&input_str);
+    let input = match parse_input(&input_str) {
+        Ok(input) => input,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
 
     println!("Setup took {:.6} µs", setup_time.elapsed().as_micros());
 
@@ -49,6 +203,17 @@ fn main() {
     println!();
     println!("Part 1 result: {}", part1_result);
     println!("Part 2 result: {}", part2_result);
+
+    if let Some(window) = window {
+        println!();
+        let result = sweep(input.iter().copied(), window).map(|found| {
+            for idx in &found.indices {
+                println!("{}", idx);
+            }
+            found.count
+        });
+        report_windows(window, result);
+    }
 }
 
 // Test functions
@@ -66,14 +231,14 @@ fn test_parse_input() {
 260
 263";
 
-    let expected: Vec<i32> = vec![199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
+    let expected: Vec<i64> = vec![199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
     let got = parse_input(input_str);
-    assert_eq!(expected, got);
+    assert_eq!(Ok(expected), got);
 }
 
 #[test]
 fn test_part1() {
-    let input: Vec<i32> = vec![199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
+    let input: Vec<i64> = vec![199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
     assert_eq!(7, part1(&input));
 }
 
@@ -81,13 +246,13 @@ fn test_part1() {
 fn test_part1_acutal() {
     let input_str =
         std::fs::read_to_string("input/day01.txt").expect("Failed to read day 1 input file");
-    let input = parse_input(&input_str);
+    let input = parse_input(&input_str).unwrap();
     assert_eq!(1564, part1(&input));
 }
 
 #[test]
 fn test_part2() {
-    let input: Vec<i32> = vec![199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
+    let input: Vec<i64> = vec![199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
     assert_eq!(5, part2(&input));
 }
 
@@ -95,6 +260,66 @@ fn test_part2() {
 fn test_part2_acutal() {
     let input_str =
         std::fs::read_to_string("input/day01.txt").expect("Failed to read day 1 input file");
-    let input = parse_input(&input_str);
+    let input = parse_input(&input_str).unwrap();
     assert_eq!(1611, part2(&input));
 }
+
+#[test]
+fn test_parse_input_error() {
+    assert_eq!(
+        Err("Line 2: Could not parse \"20O\"".to_string()),
+        parse_input("199\n20O\n")
+    );
+}
+
+#[test]
+fn test_sweep() {
+    let input: Vec<i64> = vec![199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
+
+    assert_eq!(
+        Ok(Increases {
+            count: 7,
+            indices: vec![1, 2, 3, 5, 6, 7, 9],
+        }),
+        sweep(input.iter().copied(), 1)
+    );
+    assert_eq!(
+        Ok(Increases {
+            count: 5,
+            indices: vec![1, 4, 5, 6, 7],
+        }),
+        sweep(input.iter().copied(), 3)
+    );
+    // A window as long as everything has nothing to compare with
+    assert_eq!(0, sweep(input.iter().copied(), 10).unwrap().count);
+    assert!(sweep(input.iter().copied(), 0).is_err());
+}
+
+#[test]
+fn test_increases_matches_window_sums() {
+    let input_str =
+        std::fs::read_to_string("input/day01.txt").expect("Failed to read day 1 input file");
+    let input = parse_input(&input_str).unwrap();
+
+    for window in 1..=6 {
+        let sums: Vec<i64> = input.windows(window).map(|w| w.iter().sum()).collect();
+        let expected: Vec<usize> = (1..sums.len()).filter(|&i| sums[i] > sums[i - 1]).collect();
+        let got: Vec<usize> = increases(input.iter().copied(), window).unwrap().collect();
+        assert_eq!(expected, got);
+    }
+}
+
+#[test]
+fn test_increases_unbounded() {
+    // Works from an endless stream, as long as only some of it is taken
+    let rising = (0..).map(|n: i64| n % 4);
+    let got: Vec<usize> = increases(rising, 2).unwrap().take(4).collect();
+    assert_eq!(vec![1, 2, 5, 6], got);
+}
+
+#[test]
+fn test_examples() {
+    for check in puzzle::check_examples(EXAMPLES, PARTS) {
+        assert!(check.is_ok(), "{}", check);
+    }
+}
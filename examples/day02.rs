@@ -1,80 +1,168 @@
-#[derive(Debug, PartialEq)]
+use aoc_2021_rs::puzzle::{self, Example};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
 enum Direction {
     Forward,
+    Back,
     Up,
     Down,
 }
 
 impl Direction {
-    fn new(s: &str) -> Self {
+    fn new(s: &str) -> Result<Self, String> {
         match s {
-            "forward" => Direction::Forward,
-            "down" => Direction::Down,
-            "up" => Direction::Up,
-            _ => panic!("Input string was not recognized direction"),
+            "forward" => Ok(Direction::Forward),
+            "back" => Ok(Direction::Back),
+            "down" => Ok(Direction::Down),
+            "up" => Ok(Direction::Up),
+            _ => Err(format!("Unknown command {:?}", s)),
         }
     }
 }
 
-fn parse_input(input_str: &str) -> Vec<(Direction, i64)> {
+fn parse_input(input_str: &str) -> Result<Vec<(Direction, i64)>, String> {
     // For each line in the input
     input_str
         .lines()
-        // Split around the space
-        .map(str::split_whitespace)
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
         // Convert the first item of each line to a Direction, and the second to a i64
-        .map(|mut line_parts| {
-            (
-                Direction::new(
-                    line_parts
-                        .next()
-                        .expect("Could not get anything from line after splitting whitespace"),
-                ),
-                line_parts
-                    .next()
-                    .expect("Could not get a second part of the line")
-                    .parse::<i64>()
-                    .expect("Could not parse &str to int"),
-            )
+        .map(|(line_idx, line)| {
+            let with_line = |e: String| format!("Line {}: {}", line_idx + 1, e);
+            let (command, value) =
+                line.trim().split_once(char::is_whitespace).ok_or_else(|| {
+                    with_line(format!("Expected a command and a value in {:?}", line))
+                })?;
+            let value = value
+                .trim()
+                .parse::<i64>()
+                .map_err(|_| with_line(format!("Could not parse the value {:?}", value)))?;
+
+            Ok((Direction::new(command).map_err(with_line)?, value))
         })
-        .collect::<Vec<_>>()
+        .collect()
 }
 
-fn part1(instructions: &[(Direction, i64)]) -> i64 {
-    let mut depth: i64 = 0;
-    let mut distance: i64 = 0;
-
-    for (dir, value) in instructions {
-        match dir {
-            Direction::Forward => distance += value,
-            Direction::Up => depth -= value,
-            Direction::Down => depth += value,
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+struct Position {
+    distance: i64,
+    depth: i64,
+}
+
+/// A way of working out where the submarine goes for each command
+trait Submarine {
+    fn position(&self) -> Position;
+
+    fn apply(&mut self, direction: Direction, value: i64);
+}
+
+/// Up and down change the depth directly
+#[derive(Debug, Default)]
+struct SimpleSubmarine {
+    position: Position,
+}
+
+impl Submarine for SimpleSubmarine {
+    fn position(&self) -> Position {
+        self.position
+    }
+
+    fn apply(&mut self, direction: Direction, value: i64) {
+        match direction {
+            Direction::Forward => self.position.distance += value,
+            Direction::Back => self.position.distance -= value,
+            Direction::Up => self.position.depth -= value,
+            Direction::Down => self.position.depth += value,
         }
     }
+}
 
-    depth * distance
+/// Up and down change the aim, and moving changes the depth by the aim for every step taken
+#[derive(Debug, Default)]
+struct AimSubmarine {
+    position: Position,
+    aim: i64,
 }
 
-fn part2(instructions: &[(Direction, i64)]) -> i64 {
-    let mut depth: i64 = 0;
-    let mut distance: i64 = 0;
-    let mut aim: i64 = 0;
-
-    for (dir, value) in instructions {
-        match dir {
-            Direction::Forward => {
-                depth += aim * value;
-                distance += value;
+impl Submarine for AimSubmarine {
+    fn position(&self) -> Position {
+        self.position
+    }
+
+    fn apply(&mut self, direction: Direction, value: i64) {
+        // Going back is going forward by the opposite amount
+        let forward = match direction {
+            Direction::Forward => value,
+            Direction::Back => -value,
+            Direction::Up => {
+                self.aim -= value;
+                return;
             }
-            Direction::Up => aim -= value,
-            Direction::Down => aim += value,
-        }
+            Direction::Down => {
+                self.aim += value;
+                return;
+            }
+        };
+        self.position.depth += self.aim * forward;
+        self.position.distance += forward;
     }
+}
+
+/// Follow all the instructions, giving the position before the first one and after each one
+fn navigate(submarine: &mut dyn Submarine, instructions: &[(Direction, i64)]) -> Vec<Position> {
+    let mut history = Vec::with_capacity(instructions.len() + 1);
+    history.push(submarine.position());
+
+    for &(direction, value) in instructions {
+        submarine.apply(direction, value);
+        history.push(submarine.position());
+    }
+
+    history
+}
+
+fn final_product(submarine: &mut dyn Submarine, instructions: &[(Direction, i64)]) -> i64 {
+    let end = *navigate(submarine, instructions).last().unwrap();
+    end.depth * end.distance
+}
+
+fn part1(instructions: &[(Direction, i64)]) -> i64 {
+    final_product(&mut SimpleSubmarine::default(), instructions)
+}
+
+fn part2(instructions: &[(Direction, i64)]) -> i64 {
+    final_product(&mut AimSubmarine::default(), instructions)
+}
+
+/// The examples from the puzzle description
+const EXAMPLES: &[Example] = &[Example {
+    input: "forward 5
+down 5
+forward 8
+up 3
+down 8
+forward 2",
+    part1: Some("150"),
+    part2: Some("900"),
+}];
+
+/// Each part, solved straight from the input
+const PARTS: [Option<puzzle::Part>; 2] = [Some(solve_part1), Some(solve_part2)];
 
-    depth * distance
+fn solve_part1(input: &str) -> Result<String, String> {
+    Ok(part1(&parse_input(input)?).to_string())
+}
+
+fn solve_part2(input: &str) -> Result<String, String> {
+    Ok(part2(&parse_input(input)?).to_string())
 }
 
 fn main() {
+    // Pass `--example` to check the solutions against the puzzle's examples instead
+    if std::env::args().any(|arg| arg == "--example") {
+        std::process::exit(puzzle::run_examples(EXAMPLES, PARTS));
+    }
+
     let setup_time = std::time::Instant::now();
 
     // Read the input file for day 2
@@ -82,7 +170,13 @@ fn main() {
         std::fs::read_to_string("input/day02.txt").expect("Failed to read day 2 input file");
 
     // Parse the input into a vector
-    let instructions = parse_input(&input_str);
+    let instructions = match parse_input(&input_str) {
+        Ok(instructions) => instructions,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
 
     println!("Setup took {:.6} µs", setup_time.elapsed().as_micros());
 
@@ -99,6 +193,29 @@ fn main() {
     println!();
     println!("Part 1 result: {}", part1_result);
     println!("Part 2 result: {}", part2_result);
+
+    // Pass `--history=simple` or `--history=aim` to list every position along the way
+    if let Some(model) = std::env::args().find_map(|arg| {
+        arg.strip_prefix("--history=")
+            .map(std::string::ToString::to_string)
+    }) {
+        let mut submarine: Box<dyn Submarine> = match model.as_str() {
+            "simple" => Box::new(SimpleSubmarine::default()),
+            "aim" => Box::new(AimSubmarine::default()),
+            _ => {
+                eprintln!("Unknown navigation model {:?}", model);
+                std::process::exit(1);
+            }
+        };
+        println!();
+        println!("step,distance,depth");
+        for (step, position) in navigate(submarine.as_mut(), &instructions)
+            .iter()
+            .enumerate()
+        {
+            println!("{},{},{}", step, position.distance, position.depth);
+        }
+    }
 }
 
 #[test]
@@ -109,7 +226,7 @@ forward 8
 up 3
 down 8
 forward 2";
-    let got = parse_input(&test_str);
+    let got = parse_input(test_str).unwrap();
     let expected: Vec<(Direction, i64)> = vec![
         (Direction::Forward, 5),
         (Direction::Down, 5),
@@ -140,7 +257,7 @@ fn test_part1() {
 fn test_part1_actual() {
     let input_str =
         std::fs::read_to_string("input/day02.txt").expect("Failed to read day 2 input file");
-    let instructions = parse_input(&input_str);
+    let instructions = parse_input(&input_str).unwrap();
     assert_eq!(1636725, part1(&instructions));
 }
 
@@ -163,6 +280,94 @@ fn test_part2() {
 fn test_part2_actual() {
     let input_str =
         std::fs::read_to_string("input/day02.txt").expect("Failed to read day 2 input file");
-    let instructions = parse_input(&input_str);
+    let instructions = parse_input(&input_str).unwrap();
     assert_eq!(1872757425, part2(&instructions));
 }
+
+#[test]
+fn test_parse_input_errors() {
+    assert_eq!(
+        Err("Line 2: Unknown command \"sideways\"".to_string()),
+        parse_input("forward 5\nsideways 3\n")
+    );
+    assert_eq!(
+        Err("Line 1: Could not parse the value \"five\"".to_string()),
+        parse_input("forward five\n")
+    );
+    assert_eq!(
+        Err("Line 3: Expected a command and a value in \"up\"".to_string()),
+        parse_input("back 2\ndown -4\nup\n")
+    );
+    assert_eq!(
+        Ok(vec![(Direction::Back, 2), (Direction::Down, -4)]),
+        parse_input("back 2\ndown -4\n")
+    );
+}
+
+#[test]
+fn test_navigate_history() {
+    let instructions = vec![
+        (Direction::Forward, 5),
+        (Direction::Down, 5),
+        (Direction::Forward, 8),
+        (Direction::Back, 3),
+        (Direction::Up, -2),
+    ];
+    let position = |distance, depth| Position { distance, depth };
+
+    assert_eq!(
+        vec![
+            position(0, 0),
+            position(5, 0),
+            position(5, 5),
+            position(13, 5),
+            position(10, 5),
+            position(10, 7),
+        ],
+        navigate(&mut SimpleSubmarine::default(), &instructions)
+    );
+    assert_eq!(
+        vec![
+            position(0, 0),
+            position(5, 0),
+            position(5, 0),
+            position(13, 40),
+            position(10, 25),
+            position(10, 25),
+        ],
+        navigate(&mut AimSubmarine::default(), &instructions)
+    );
+}
+
+#[test]
+fn test_custom_submarine() {
+    /// Only ever goes forward, however it is told to move
+    struct Stubborn(Position);
+
+    impl Submarine for Stubborn {
+        fn position(&self) -> Position {
+            self.0
+        }
+
+        fn apply(&mut self, _: Direction, value: i64) {
+            self.0.distance += value.abs();
+        }
+    }
+
+    let instructions = vec![(Direction::Back, 3), (Direction::Down, 4)];
+    let history = navigate(&mut Stubborn(Position::default()), &instructions);
+    assert_eq!(
+        Position {
+            distance: 7,
+            depth: 0
+        },
+        history[2]
+    );
+}
+
+#[test]
+fn test_examples() {
+    for check in puzzle::check_examples(EXAMPLES, PARTS) {
+        assert!(check.is_ok(), "{}", check);
+    }
+}
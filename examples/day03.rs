@@ -1,164 +1,183 @@
-use std::collections::HashSet;
+use aoc_2021_rs::puzzle::{self, Example};
 
-fn parse_input(input: &str) -> Vec<Vec<char>> {
-    // For each row and column, we store the character at that position.
-    input.lines().map(|line| line.chars().collect()).collect()
+/// The diagnostic report, with each reading packed into the low `width` bits of a word
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct Report {
+    readings: Vec<u64>,
+    width: u32,
 }
 
-fn most_common_char(input: &[char]) -> char {
-    let mut counts: [u32; 2] = [0, 0];
-    for c in input {
-        if *c == '0' {
-            counts[0] += 1;
-        } else {
-            counts[1] += 1;
-        }
+impl Report {
+    /// All of the bits that a reading can use
+    fn mask(&self) -> u64 {
+        u64::MAX >> (64 - self.width)
     }
-    if counts[0] > counts[1] {
-        '0'
-    } else {
-        '1'
+
+    /// How many readings have a 1 in each bit, indexed by bit position (0 is least significant)
+    fn ones_in_each_bit(&self) -> Vec<usize> {
+        (0..self.width)
+            .map(|bit| {
+                self.readings
+                    .iter()
+                    .filter(|&&reading| reading & (1 << bit) != 0)
+                    .count()
+            })
+            .collect()
     }
 }
 
-fn flip_bits(input: &[char]) -> Vec<char> {
-    input
-        .iter()
-        .map(|c| if *c == '0' { '1' } else { '0' })
-        .collect()
-}
+fn parse_input(input: &str) -> Result<Report, String> {
+    let mut readings: Vec<u64> = Vec::new();
+    let mut width: Option<usize> = None;
 
-fn part1(input: &[Vec<char>]) -> usize {
-    // Collect the items in each column
-    let mut cols: Vec<Vec<char>> = vec![Vec::new(); input[0].len()];
-    for row in input {
-        for (i, c) in row.iter().enumerate() {
-            cols[i].push(*c);
+    for (line_idx, line) in input.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
         }
-    }
-
-    // For each column, get the most common character.
-    let most_common_char_in_each_col: Vec<char> =
-        cols.iter().map(|col| most_common_char(col)).collect();
-
-    // gamma is the number created from the bits
-    let gamma = usize::from_str_radix(
-        &most_common_char_in_each_col
-            .iter()
-            .map(std::string::ToString::to_string)
-            .collect::<String>(),
-        2,
-    )
-    .expect("Could not parse gamma");
-
-    // Flip all the bits in most_common_char_in_each_col
-    let least_common_char_in_each_col = flip_bits(&most_common_char_in_each_col);
-
-    // epsilon is the number created from the least common bits
-    let epsilon = usize::from_str_radix(
-        &least_common_char_in_each_col
-            .iter()
-            .map(std::string::ToString::to_string)
-            .collect::<String>(),
-        2,
-    )
-    .expect("Could not parse epsilon");
-
-    gamma * epsilon
-}
 
-fn bit_criteria(input: &[char], is_oxygen: bool) -> char {
-    let mut counts: [u32; 2] = [0, 0];
-    for c in input {
-        if *c == '0' {
-            counts[0] += 1;
-        } else {
-            counts[1] += 1;
+        let expected_width = *width.get_or_insert(line.len());
+        if line.len() != expected_width {
+            return Err(format!(
+                "Line {} has {} bits, but the first reading has {}",
+                line_idx + 1,
+                line.len(),
+                expected_width
+            ));
         }
+        if expected_width > 64 {
+            return Err(format!(
+                "Readings have {} bits, but only up to 64 are supported",
+                expected_width
+            ));
+        }
+
+        let reading = u64::from_str_radix(line, 2)
+            .map_err(|_| format!("Line {} is not a binary number: {:?}", line_idx + 1, line))?;
+        readings.push(reading);
     }
 
-    match (counts[0], counts[1], is_oxygen) {
-        (n_zeros, n_ones, oxy) if (n_zeros > n_ones) && oxy => '0',
-        (n_zeros, n_ones, oxy) if (n_zeros > n_ones) && !oxy => '1',
-        (n_zeros, n_ones, oxy) if (n_zeros < n_ones) && oxy => '1',
-        (n_zeros, n_ones, oxy) if (n_zeros < n_ones) && !oxy => '0',
-        (_, _, oxy) if oxy => '1',
-        (_, _, oxy) if !oxy => '0',
-        _ => unreachable!(),
+    match width {
+        Some(width) => Ok(Report {
+            readings,
+            width: width as u32,
+        }),
+        None => Err("The report has no readings".to_string()),
     }
 }
 
-fn get_rows<T: Clone>(v: &[Vec<T>], rows: &HashSet<usize>) -> Vec<Vec<T>> {
-    // Return the rows in the set
-    v.iter()
+fn part1(report: &Report) -> u128 {
+    // gamma has a 1 in every bit where 1 is the most common, or where they are tied
+    let n_readings = report.readings.len();
+    let gamma = report
+        .ones_in_each_bit()
+        .iter()
         .enumerate()
-        .filter(|(idx, _)| rows.contains(idx))
-        .map(|(i, _)| v[i].clone())
-        .collect()
-}
+        .filter(|(_, &n_ones)| 2 * n_ones >= n_readings)
+        .fold(0u64, |gamma, (bit, _)| gamma | (1 << bit));
 
-fn get_col<T: Copy>(v: &[Vec<T>], wanted_col_idx: usize) -> Vec<T> {
-    // Return only the one column of `v` that is wanted_col_idx
-    let mut result = Vec::new();
-    for row in v.iter() {
-        for (col_idx, &item) in row.iter().enumerate() {
-            if col_idx == wanted_col_idx {
-                result.push(item);
-            }
-        }
-    }
-    result
-}
+    // epsilon uses the least common bits, which are all of the others
+    let epsilon = !gamma & report.mask();
 
-fn get_number_for_sensor(input: &Vec<Vec<char>>, is_oxygen: bool) -> usize {
-    let mut to_alter = input.to_owned();
-    for col_idx in 0..input[0].len() {
-        // Get this column
-        let this_col = get_col(&to_alter, col_idx);
-
-        // What's the one to keep according to bit_criteria?
-        let desired_char = bit_criteria(&this_col, is_oxygen);
+    gamma as u128 * epsilon as u128
+}
 
-        // Gather a HashSet of the row indices desired to keep
-        let rows_to_keep: HashSet<usize> = this_col
-            .iter()
-            .enumerate()
-            .filter(|(_, &val)| val == desired_char)
-            .map(|(idx, _)| idx)
-            .collect();
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Rating {
+    /// Keep the most common bit, or 1 if they are tied
+    Oxygen,
+    /// Keep the least common bit, or 0 if they are tied
+    Co2,
+}
 
-        // Get the new to_alter
-        to_alter = get_rows(&to_alter, &rows_to_keep);
+/// Find the rating from readings that are already sorted.
+///
+/// Each time a bit is considered, all of the remaining readings have the same bits above it, so
+/// the ones with a 0 in that bit are all before the ones with a 1, and the split between them
+/// can be found with a binary search.
+fn rating(sorted_readings: &[u64], width: u32, rating: Rating) -> u64 {
+    let mut remaining = sorted_readings;
 
-        // If only one row left, break out
-        if to_alter.len() == 1 {
+    for bit in (0..width).rev() {
+        if remaining.len() <= 1 {
             break;
         }
+
+        let split = remaining.partition_point(|&reading| reading & (1 << bit) == 0);
+        let (zeros, ones) = remaining.split_at(split);
+
+        // If every reading has the same bit there is no choice to make
+        remaining = if zeros.is_empty() || ones.is_empty() {
+            remaining
+        } else {
+            match rating {
+                Rating::Oxygen if ones.len() >= zeros.len() => ones,
+                Rating::Oxygen => zeros,
+                Rating::Co2 if zeros.len() <= ones.len() => zeros,
+                Rating::Co2 => ones,
+            }
+        };
     }
 
-    usize::from_str_radix(
-        &to_alter[0]
-            .iter()
-            .map(std::string::ToString::to_string)
-            .collect::<String>(),
-        2,
-    )
-    .expect("Could not parse number")
+    // Anything left over must be identical
+    remaining[0]
 }
 
-fn part2(input: &Vec<Vec<char>>) -> usize {
-    let oxygen_number = get_number_for_sensor(input, true);
+fn part2(report: &Report) -> u128 {
+    let mut sorted_readings = report.readings.clone();
+    sorted_readings.sort_unstable();
 
-    let co2_number = get_number_for_sensor(input, false);
+    let oxygen_number = rating(&sorted_readings, report.width, Rating::Oxygen);
+    let co2_number = rating(&sorted_readings, report.width, Rating::Co2);
 
-    oxygen_number * co2_number
+    oxygen_number as u128 * co2_number as u128
+}
+
+/// The examples from the puzzle description
+const EXAMPLES: &[Example] = &[Example {
+    input: "00100
+11110
+10110
+10111
+10101
+01111
+00111
+11100
+10000
+11001
+00010
+01010",
+    part1: Some("198"),
+    part2: Some("230"),
+}];
+
+/// Each part, solved straight from the input
+const PARTS: [Option<puzzle::Part>; 2] = [Some(solve_part1), Some(solve_part2)];
+
+fn solve_part1(input: &str) -> Result<String, String> {
+    Ok(part1(&parse_input(input)?).to_string())
+}
+
+fn solve_part2(input: &str) -> Result<String, String> {
+    Ok(part2(&parse_input(input)?).to_string())
 }
 
 fn main() {
+    // Pass `--example` to check the solutions against the puzzle's examples instead
+    if std::env::args().any(|arg| arg == "--example") {
+        std::process::exit(puzzle::run_examples(EXAMPLES, PARTS));
+    }
+
     let setup_time = std::time::Instant::now();
 
     let input_str = std::fs::read_to_string("input/day03.txt").expect("Could not read day 3 input");
-    let input = parse_input(&input_str);
+    let input = match parse_input(&input_str) {
+        Ok(input) => input,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
     println!("Setup took {:.6} µs", setup_time.elapsed().as_micros());
     // Part 1
     let part1_time = std::time::Instant::now();
@@ -177,134 +196,88 @@ fn main() {
 
 #[test]
 fn test_parse_input() {
-    let input = "00100
-11110
-10110
-10111
-10101
-01111
-00111
-11100
-10000
-11001
-00010
-01010";
-    let expected: Vec<Vec<char>> = vec![
-        vec!['0', '0', '1', '0', '0'],
-        vec!['1', '1', '1', '1', '0'],
-        vec!['1', '0', '1', '1', '0'],
-        vec!['1', '0', '1', '1', '1'],
-        vec!['1', '0', '1', '0', '1'],
-        vec!['0', '1', '1', '1', '1'],
-        vec!['0', '0', '1', '1', '1'],
-        vec!['1', '1', '1', '0', '0'],
-        vec!['1', '0', '0', '0', '0'],
-        vec!['1', '1', '0', '0', '1'],
-        vec!['0', '0', '0', '1', '0'],
-        vec!['0', '1', '0', '1', '0'],
-    ];
-    assert_eq!(parse_input(&input), expected);
+    let expected = Report {
+        readings: vec![
+            0b00100, 0b11110, 0b10110, 0b10111, 0b10101, 0b01111, 0b00111, 0b11100, 0b10000,
+            0b11001, 0b00010, 0b01010,
+        ],
+        width: 5,
+    };
+    assert_eq!(parse_input(EXAMPLES[0].input), Ok(expected));
 }
 
 #[test]
-fn test_part1() {
-    let input: Vec<Vec<char>> = vec![
-        vec!['0', '0', '1', '0', '0'],
-        vec!['1', '1', '1', '1', '0'],
-        vec!['1', '0', '1', '1', '0'],
-        vec!['1', '0', '1', '1', '1'],
-        vec!['1', '0', '1', '0', '1'],
-        vec!['0', '1', '1', '1', '1'],
-        vec!['0', '0', '1', '1', '1'],
-        vec!['1', '1', '1', '0', '0'],
-        vec!['1', '0', '0', '0', '0'],
-        vec!['1', '1', '0', '0', '1'],
-        vec!['0', '0', '0', '1', '0'],
-        vec!['0', '1', '0', '1', '0'],
-    ];
+fn test_parse_input_errors() {
+    assert_eq!(
+        parse_input("0101\n011\n"),
+        Err("Line 2 has 3 bits, but the first reading has 4".to_string())
+    );
+    assert_eq!(
+        parse_input("0101\n0121\n"),
+        Err("Line 2 is not a binary number: \"0121\"".to_string())
+    );
+    assert!(parse_input(&"1".repeat(65)).is_err());
+    assert_eq!(
+        parse_input("\n"),
+        Err("The report has no readings".to_string())
+    );
+}
+
+#[test]
+fn test_ones_in_each_bit() {
+    let report = parse_input(EXAMPLES[0].input).unwrap();
+    assert_eq!(vec![5, 7, 8, 5, 7], report.ones_in_each_bit());
+}
 
+#[test]
+fn test_part1() {
+    let input = parse_input(EXAMPLES[0].input).unwrap();
     assert_eq!(part1(&input), 198);
 }
 
 #[test]
 fn test_part1_actual() {
     let input_str = std::fs::read_to_string("input/day03.txt").expect("Could not read day 3 input");
-    let input = parse_input(&input_str);
+    let input = parse_input(&input_str).unwrap();
     assert_eq!(3320834, part1(&input));
 }
 
 #[test]
-fn test_get_rows() {
-    let input: Vec<Vec<char>> = vec![
-        vec!['0', '0', '1', '0', '0'],
-        vec!['1', '1', '1', '1', '0'],
-        vec!['1', '0', '1', '1', '0'],
-        vec!['1', '0', '1', '1', '1'],
-        vec!['1', '0', '1', '0', '1'],
-        vec!['0', '1', '1', '1', '1'],
-        vec!['0', '0', '1', '1', '1'],
-        vec!['1', '1', '1', '0', '0'],
-        vec!['1', '0', '0', '0', '0'],
-        vec!['1', '1', '0', '0', '1'],
-        vec!['0', '0', '0', '1', '0'],
-        vec!['0', '1', '0', '1', '0'],
-    ];
-
-    let rows: HashSet<usize> = vec![0, 3, 8].into_iter().collect();
-    let expected: Vec<Vec<char>> = vec![
-        vec!['0', '0', '1', '0', '0'],
-        vec!['1', '0', '1', '1', '1'],
-        vec!['1', '0', '0', '0', '0'],
-    ];
-    let got = get_rows(&input, &rows);
-    assert_eq!(got, expected);
+fn test_rating() {
+    let report = parse_input(EXAMPLES[0].input).unwrap();
+    let mut sorted_readings = report.readings.clone();
+    sorted_readings.sort_unstable();
+    assert_eq!(23, rating(&sorted_readings, report.width, Rating::Oxygen));
+    assert_eq!(10, rating(&sorted_readings, report.width, Rating::Co2));
 }
 
 #[test]
-fn test_get_col() {
-    let input: Vec<Vec<char>> = vec![
-        vec!['0', '0', '1', '0', '0'],
-        vec!['1', '1', '1', '1', '0'],
-        vec!['1', '0', '1', '1', '0'],
-        vec!['1', '0', '1', '1', '1'],
-        vec!['1', '0', '1', '0', '1'],
-        vec!['0', '1', '1', '1', '1'],
-        vec!['0', '0', '1', '1', '1'],
-        vec!['1', '1', '1', '0', '0'],
-        vec!['1', '0', '0', '0', '0'],
-        vec!['1', '1', '0', '0', '1'],
-        vec!['0', '0', '0', '1', '0'],
-        vec!['0', '1', '0', '1', '0'],
-    ];
-    let wanted_col_idx: usize = 2;
-    let expected: Vec<char> = vec!['1', '1', '1', '1', '1', '1', '1', '1', '0', '0', '0', '0'];
-    let got = get_col(&input, wanted_col_idx);
-    assert_eq!(expected, got)
+fn test_wide_readings() {
+    // Full 64 bit readings, where the top half is most common in the top 32 bits
+    let (high, low) = (0xFFFF_FFFF_0000_0000u64, 0x0000_0000_FFFF_FFFFu64);
+    let input_str = format!("{:064b}\n{:064b}\n{:064b}\n", high, high, low);
+    let input = parse_input(&input_str).unwrap();
+    assert_eq!(64, input.width);
+    assert_eq!(high as u128 * low as u128, part1(&input));
+    assert_eq!(high as u128 * low as u128, part2(&input));
 }
 
 #[test]
 fn test_part2() {
-    let input: Vec<Vec<char>> = vec![
-        vec!['0', '0', '1', '0', '0'],
-        vec!['1', '1', '1', '1', '0'],
-        vec!['1', '0', '1', '1', '0'],
-        vec!['1', '0', '1', '1', '1'],
-        vec!['1', '0', '1', '0', '1'],
-        vec!['0', '1', '1', '1', '1'],
-        vec!['0', '0', '1', '1', '1'],
-        vec!['1', '1', '1', '0', '0'],
-        vec!['1', '0', '0', '0', '0'],
-        vec!['1', '1', '0', '0', '1'],
-        vec!['0', '0', '0', '1', '0'],
-        vec!['0', '1', '0', '1', '0'],
-    ];
-
+    let input = parse_input(EXAMPLES[0].input).unwrap();
     assert_eq!(part2(&input), 230);
 }
 
 #[test]
 fn test_part2_actual() {
     let input_str = std::fs::read_to_string("input/day03.txt").expect("Could not read day 3 input");
-    let input = parse_input(&input_str);
+    let input = parse_input(&input_str).unwrap();
     assert_eq!(4481199, part2(&input));
 }
+
+#[test]
+fn test_examples() {
+    for check in puzzle::check_examples(EXAMPLES, PARTS) {
+        assert!(check.is_ok(), "{}", check);
+    }
+}
@@ -1,41 +1,173 @@
-use ndarray::{arr2, Array2, ArrayView2};
+use std::collections::HashMap;
+
+use aoc_2021_rs::parsing;
+use aoc_2021_rs::puzzle::{self, Example};
+
+#[cfg(test)]
+use ndarray::arr2;
+use ndarray::{Array2, ArrayView2};
 
 fn parse_input(input: &str) -> (Vec<u16>, Vec<Array2<u16>>) {
-    // Split on double newlines
-    let mut lines = input.split("\n\n");
+    let sections = parsing::sections(input);
+    let (first, boards) = sections.split_first().expect("The input is empty");
 
     // The first line is the bingo numbers called
-    let bingo_numbers: Vec<u16> = lines
-        .next()
-        .unwrap()
-        .split(',')
-        .map(|s| s.parse().expect("Could not parse bingo number"))
-        .collect();
+    let bingo_numbers: Vec<u16> = parsing::numbers(first[0]).expect("Could not parse bingo number");
 
-    // Each following split item is a bingo board
-    let bingo_boards: Vec<Array2<u16>> = lines
+    // Each following section is a bingo board, of whatever size the rows and columns give
+    let bingo_boards: Vec<Array2<u16>> = boards
+        .iter()
         .map(|board| {
-            // Split on newlines
-            let rows = board.split('\n');
-
-            // Create an array of zeros
-            let mut board = Array2::<u16>::zeros((5, 5));
+            let rows: Vec<Vec<u16>> = board
+                .iter()
+                .map(|row| parsing::numbers(row).expect("Could not parse board number"))
+                .collect();
 
-            // Fill the array with the numbers
-            for (row_idx, row) in rows.enumerate() {
-                for (col_index, num) in row.split_whitespace().enumerate() {
-                    board[(row_idx, col_index)] =
-                        num.parse().expect("Could not parse board number");
-                }
-            }
+            let ncols = rows[0].len();
+            assert!(
+                rows.iter().all(|row| row.len() == ncols),
+                "Board rows are not all the same length"
+            );
 
-            board
+            Array2::from_shape_vec((rows.len(), ncols), rows.concat()).unwrap()
         })
         .collect();
 
     (bingo_numbers, bingo_boards)
 }
 
+/// For each number, which board and cell (row, column) it is in
+type NumberIndex = HashMap<u16, Vec<(usize, (usize, usize))>>;
+
+/// A board winning
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+struct Win {
+    /// Index of the board that won
+    board: usize,
+    /// Index into the numbers drawn of the draw that it won on
+    draw: usize,
+    /// The number drawn
+    number: u16,
+    unmarked_sum: usize,
+    score: usize,
+}
+
+/// How far through the game a single board is
+#[derive(Debug, Clone)]
+struct BoardState {
+    marked: Array2<bool>,
+    marked_in_row: Vec<usize>,
+    marked_in_col: Vec<usize>,
+    /// Leading diagonal, then the anti-diagonal
+    marked_in_diagonal: [usize; 2],
+    unmarked_sum: usize,
+    won: bool,
+}
+
+/// A game of bingo, with every number on every board indexed up front, so that drawing a
+/// number only touches the cells it is on
+#[derive(Debug, Clone)]
+struct BingoGame {
+    boards: Vec<Array2<u16>>,
+    index: NumberIndex,
+    /// Whether a full diagonal wins. Only square boards have diagonals
+    diagonals: bool,
+}
+
+impl BingoGame {
+    fn new(boards: &[ArrayView2<u16>], diagonals: bool) -> BingoGame {
+        let mut index = NumberIndex::new();
+        for (board_idx, board) in boards.iter().enumerate() {
+            for (cell, &number) in board.indexed_iter() {
+                index.entry(number).or_default().push((board_idx, cell));
+            }
+        }
+
+        BingoGame {
+            boards: boards.iter().map(|board| board.to_owned()).collect(),
+            index,
+            diagonals,
+        }
+    }
+
+    fn initial_state(&self, board: &Array2<u16>) -> BoardState {
+        let (nrows, ncols) = board.dim();
+        BoardState {
+            marked: Array2::from_elem((nrows, ncols), false),
+            marked_in_row: vec![0; nrows],
+            marked_in_col: vec![0; ncols],
+            marked_in_diagonal: [0; 2],
+            unmarked_sum: board.iter().map(|&n| n as usize).sum(),
+            won: false,
+        }
+    }
+
+    /// Play through all of `numbers`, returning every board that wins in the order that they
+    /// won. Boards that win on the same draw are in board order. Boards that never win are left
+    /// out.
+    fn play(&self, numbers: &[u16]) -> Vec<Win> {
+        let mut states: Vec<BoardState> = self
+            .boards
+            .iter()
+            .map(|board| self.initial_state(board))
+            .collect();
+        let mut wins: Vec<Win> = Vec::new();
+
+        for (draw, &number) in numbers.iter().enumerate() {
+            let cells = match self.index.get(&number) {
+                Some(cells) => cells,
+                None => continue,
+            };
+
+            let wins_before = wins.len();
+            for &(board_idx, (row, col)) in cells {
+                let state = &mut states[board_idx];
+                if state.won || state.marked[(row, col)] {
+                    continue;
+                }
+                state.marked[(row, col)] = true;
+                state.unmarked_sum -= number as usize;
+                state.marked_in_row[row] += 1;
+                state.marked_in_col[col] += 1;
+
+                let (nrows, ncols) = state.marked.dim();
+                let mut complete =
+                    state.marked_in_row[row] == ncols || state.marked_in_col[col] == nrows;
+
+                if self.diagonals && nrows == ncols {
+                    if row == col {
+                        state.marked_in_diagonal[0] += 1;
+                    }
+                    if row + col + 1 == ncols {
+                        state.marked_in_diagonal[1] += 1;
+                    }
+                    complete |= state.marked_in_diagonal.contains(&nrows);
+                }
+
+                if complete {
+                    state.won = true;
+                    wins.push(Win {
+                        board: board_idx,
+                        draw,
+                        number,
+                        unmarked_sum: state.unmarked_sum,
+                        score: state.unmarked_sum * number as usize,
+                    });
+                }
+            }
+            wins[wins_before..].sort_unstable_by_key(|win| win.board);
+
+            if wins.len() == self.boards.len() {
+                break;
+            }
+        }
+
+        wins
+    }
+}
+
+/// Scan the board for `number`, and mark it. Kept as a simple check on `BingoGame`.
+#[cfg(test)]
 fn mark_number(number: u16, board: ArrayView2<u16>, board_markers: &mut Array2<bool>) {
     // Find the index of an occurrence of the number in the board
     let indices = board
@@ -50,141 +182,115 @@ fn mark_number(number: u16, board: ArrayView2<u16>, board_markers: &mut Array2<b
     }
 }
 
+#[cfg(test)]
 fn board_won(board_markers: ArrayView2<bool>) -> bool {
-    if board_markers
+    board_markers
         .columns()
         .into_iter()
         .any(|col| col.iter().all(|&item| item))
-    {
-        return true;
-    } else if board_markers
-        .rows()
-        .into_iter()
-        .any(|row| row.iter().all(|&item| item))
-    {
-        return true;
-    }
-
-    false
+        || board_markers
+            .rows()
+            .into_iter()
+            .any(|row| row.iter().all(|&item| item))
 }
 
-fn part1(bingo_numbers: &[u16], bingo_boards: &[ArrayView2<u16>]) -> usize {
-    // Create the board markers, one for each board in bingo_boards, boolean arrays of whether or not a number has been marked
-    let board_marker_example = arr2(&[[false; 5]; 5]);
-
-    let mut board_markers: Vec<Array2<bool>> = Vec::new();
-    for _ in bingo_boards {
-        board_markers.push(board_marker_example.clone());
-    }
-
-    // For each bingo number
-    for &number in bingo_numbers {
-        // Mark it on all boards
-        for (&board, marker) in bingo_boards.iter().zip(board_markers.iter_mut()) {
-            mark_number(number, board, marker);
-        }
-
-        // Check if any board has won
-        let possible_winner = board_markers
-            .iter()
-            .enumerate()
-            .find(|(_, marker)| board_won(marker.view()));
-
-        if let Some((winners_idx, winning_board)) = possible_winner {
-            // Get the sum of all unmarked numbers on the winning board
-            let sum_of_unmared_numbers: usize = bingo_boards[winners_idx]
-                .iter()
-                // Run through it with the marker board
-                .zip(winning_board.iter())
-                // Remove any items where the marker is marked
-                .filter(|(_, &item)| !item)
-                .map(|(&board_num, _)| board_num as usize)
-                .sum();
-
-            return sum_of_unmared_numbers * number as usize;
-        }
-    }
+fn part1(wins: &[Win]) -> usize {
+    wins.first()
+        .expect("Could not find any winning boards")
+        .score
+}
 
-    panic!("Could not find any winning boards");
+fn part2(wins: &[Win]) -> usize {
+    wins.last()
+        .expect("Could not find any winning boards")
+        .score
 }
 
-fn part2(bingo_numbers: &[u16], bingo_boards: &[ArrayView2<u16>]) -> usize {
-    // Create the board markers, one for each board in bingo_boards, boolean arrays of whether or not a number has been marked
-    let board_marker_example = arr2(&[[false; 5]; 5]);
+/// The examples from the puzzle description
+const EXAMPLES: &[Example] = &[Example {
+    input: "7,4,9,5,11,17,23,2,0,14,21,24,10,16,13,6,15,25,12,22,18,20,8,19,3,26,1
 
-    let mut board_markers: Vec<Array2<bool>> = Vec::new();
-    for _ in bingo_boards {
-        board_markers.push(board_marker_example.clone());
-    }
+22 13 17 11  0
+ 8  2 23  4 24
+21  9 14 16  7
+ 6 10  3 18  5
+ 1 12 20 15 19
 
-    let mut winning_number: i32 = -1;
-    let mut winning_boards_in_order: Vec<usize> = Vec::new();
+ 3 15  0  2 22
+ 9 18 13 17  5
+19  8  7 25 23
+20 11 10 24  4
+14 21 16 12  6
 
-    // For each bingo number
-    for &number in bingo_numbers {
-        // Mark it on all boards
-        for (&board, marker) in bingo_boards.iter().zip(board_markers.iter_mut()) {
-            mark_number(number, board, marker);
-        }
+14 21 17 24  4
+10 16 15  9 19
+18  8 23 26 20
+22 11 13  6  5
+ 2  0 12  3  7",
+    part1: Some("4512"),
+    part2: Some("1924"),
+}];
 
-        // Find any winners, and update `winning_boards_in_order`
-        let winners_indices: Vec<usize> = board_markers
-            .iter()
-            .enumerate()
-            .filter(|(_, marker)| board_won(marker.view()))
-            .map(|(idx, _)| idx)
-            .collect();
+/// Each part, solved straight from the input
+const PARTS: [Option<puzzle::Part>; 2] = [Some(solve_part1), Some(solve_part2)];
 
-        // For each winners_indices check if it already exists in winning_boards_in_order, if not add it
-        for &winner_idx in &winners_indices {
-            if !winning_boards_in_order.contains(&winner_idx) {
-                winning_boards_in_order.push(winner_idx);
-            }
-        }
+fn play_input(input: &str) -> Vec<Win> {
+    let (numbers, boards) = parse_input(input);
+    let board_views: Vec<ArrayView2<u16>> = boards.iter().map(ndarray::ArrayBase::view).collect();
+    BingoGame::new(&board_views, false).play(&numbers)
+}
 
-        winning_number = i32::from(number);
-        // If all have finished, then exit the loop
-        if winners_indices.len() == bingo_boards.len() {
-            break;
-        }
-    }
-    // What is the winner's index
-    let winner_idx = winning_boards_in_order.last().unwrap();
+fn solve_part1(input: &str) -> Result<String, String> {
+    Ok(part1(&play_input(input)).to_string())
+}
 
-    // Take the `winner_idx`, and get the sum of all unmarked numbers on the winning board
-    let sum_of_unmared_numbers: usize = bingo_boards[*winner_idx]
-        .iter()
-        // Run through it with the marker board
-        .zip(board_markers[*winner_idx].iter())
-        // Remove any items where the marker is marked
-        .filter(|(_, &item)| !item)
-        .map(|(&board_num, _)| board_num as usize)
-        .sum();
-
-    sum_of_unmared_numbers * (winning_number as usize)
+fn solve_part2(input: &str) -> Result<String, String> {
+    Ok(part2(&play_input(input)).to_string())
 }
 
 fn main() {
+    // Pass `--example` to check the solutions against the puzzle's examples instead
+    if std::env::args().any(|arg| arg == "--example") {
+        std::process::exit(puzzle::run_examples(EXAMPLES, PARTS));
+    }
+
     let setup_time = std::time::Instant::now();
 
     let input_str = std::fs::read_to_string("input/day04.txt").expect("Failed to read day 4 input");
     let (board_numbers, boards) = parse_input(&input_str);
     let board_views: Vec<ArrayView2<u16>> = boards.iter().map(ndarray::ArrayBase::view).collect();
+
+    // Pass `--diagonals` to let full diagonals win as well
+    let diagonals = std::env::args().any(|arg| arg == "--diagonals");
+    let game = BingoGame::new(&board_views, diagonals);
     println!("Setup took {:.6} µs", setup_time.elapsed().as_micros());
 
     // Part 1
     let part1_time = std::time::Instant::now();
-    let part1_result = part1(&board_numbers, &board_views);
+    let wins = game.play(&board_numbers);
+    let part1_result = part1(&wins);
     println!("Part 1 took {:.6} µs", part1_time.elapsed().as_micros());
 
     // Part 2
     let part2_time = std::time::Instant::now();
-    let part2_result = part2(&board_numbers, &board_views);
+    let part2_result = part2(&wins);
     println!("Part 2 took {:.6} µs", part2_time.elapsed().as_micros());
 
     println!();
     println!("Part 1 result: {}", part1_result);
     println!("Part 2 result: {}", part2_result);
+
+    // Pass `--order` to list every board in the order that they won
+    if std::env::args().any(|arg| arg == "--order") {
+        println!();
+        println!("board,draw,number,unmarked_sum,score");
+        for win in &wins {
+            println!(
+                "{},{},{},{},{}",
+                win.board, win.draw, win.number, win.unmarked_sum, win.score
+            );
+        }
+    }
 }
 
 #[test]
@@ -209,7 +315,7 @@ fn test_parse_input() {
 22 11 13  6  5
  2  0 12  3  7";
 
-    let (numbers_drawn, boards) = parse_input(&input_str);
+    let (numbers_drawn, boards) = parse_input(input_str);
 
     let expected_numbers_drawn: Vec<u16> = vec![
         7, 4, 9, 5, 11, 17, 23, 2, 0, 14, 21, 24, 10, 16, 13, 6, 15, 25, 12, 22, 18, 20, 8, 19, 3,
@@ -281,7 +387,7 @@ fn test_board_won_1() {
         [false, false, false, false, false],
     ]);
     let got = board_won(test_board1.view());
-    assert_eq!(true, got);
+    assert!(got);
 }
 
 #[test]
@@ -294,7 +400,7 @@ fn test_board_won_2() {
         [false, true, false, false, false],
     ]);
     let got = board_won(test_board1.view());
-    assert_eq!(true, got);
+    assert!(got);
 }
 
 #[test]
@@ -307,7 +413,7 @@ fn test_board_won_3() {
         [false, true, false, false, false],
     ]);
     let got = board_won(test_board1.view());
-    assert_eq!(false, got);
+    assert!(!got);
 }
 
 #[test]
@@ -341,7 +447,8 @@ fn test_part1() {
     ];
     let board_views: Vec<ArrayView2<u16>> = boards.iter().map(|b| b.view()).collect();
 
-    let got = part1(&numbers_drawn, &board_views);
+    let wins = BingoGame::new(&board_views, false).play(&numbers_drawn);
+    let got = part1(&wins);
     assert_eq!(got, 4512);
 }
 
@@ -350,7 +457,8 @@ fn test_part1_actual() {
     let input_str = std::fs::read_to_string("input/day04.txt").expect("Failed to read day 4 input");
     let (board_numbers, boards) = parse_input(&input_str);
     let board_views: Vec<ArrayView2<u16>> = boards.iter().map(|b| b.view()).collect();
-    let got = part1(&board_numbers, &board_views);
+    let wins = BingoGame::new(&board_views, false).play(&board_numbers);
+    let got = part1(&wins);
     assert_eq!(49860, got);
 }
 
@@ -385,7 +493,8 @@ fn test_part2() {
     ];
     let board_views: Vec<ArrayView2<u16>> = boards.iter().map(|b| b.view()).collect();
 
-    let got = part2(&numbers_drawn, &board_views);
+    let wins = BingoGame::new(&board_views, false).play(&numbers_drawn);
+    let got = part2(&wins);
     assert_eq!(got, 1924);
 }
 
@@ -394,6 +503,116 @@ fn test_part2_actual() {
     let input_str = std::fs::read_to_string("input/day04.txt").expect("Failed to read day 4 input");
     let (board_numbers, boards) = parse_input(&input_str);
     let board_views: Vec<ArrayView2<u16>> = boards.iter().map(|b| b.view()).collect();
-    let got = part2(&board_numbers, &board_views);
+    let wins = BingoGame::new(&board_views, false).play(&board_numbers);
+    let got = part2(&wins);
     assert_eq!(24628, got);
 }
+
+#[test]
+fn test_play_winning_order() {
+    let input_str = std::fs::read_to_string("input/day04.txt").expect("Failed to read day 4 input");
+    let (numbers, boards) = parse_input(&input_str);
+    let board_views: Vec<ArrayView2<u16>> = boards.iter().map(|b| b.view()).collect();
+    let wins = BingoGame::new(&board_views, false).play(&numbers);
+
+    // Replay the game by scanning every board for every number, and check that the same boards
+    // win on the same draws
+    let mut markers: Vec<Array2<bool>> = boards
+        .iter()
+        .map(|b| Array2::from_elem(b.dim(), false))
+        .collect();
+    let mut expected: Vec<(usize, usize)> = Vec::new();
+    for (draw, &number) in numbers.iter().enumerate() {
+        for (board_idx, (board, marker)) in board_views.iter().zip(markers.iter_mut()).enumerate() {
+            if expected.iter().any(|&(idx, _)| idx == board_idx) {
+                continue;
+            }
+            mark_number(number, *board, marker);
+            if board_won(marker.view()) {
+                expected.push((board_idx, draw));
+            }
+        }
+    }
+
+    let got: Vec<(usize, usize)> = wins.iter().map(|win| (win.board, win.draw)).collect();
+    assert_eq!(expected, got);
+    assert!(wins
+        .iter()
+        .all(|win| win.score == win.unmarked_sum * win.number as usize));
+}
+
+#[test]
+fn test_play_sizes_and_diagonals() {
+    let input_str = "5,1,9,4,7,2
+
+1 2 3
+4 5 6
+7 8 9
+
+ 9  8
+ 7  6
+ 1 10";
+    let (numbers, boards) = parse_input(input_str);
+    assert_eq!((3, 3), boards[0].dim());
+    assert_eq!((3, 2), boards[1].dim());
+    let board_views: Vec<ArrayView2<u16>> = boards.iter().map(|b| b.view()).collect();
+
+    // Without diagonals both boards complete their first column with the 7
+    let wins = BingoGame::new(&board_views, false).play(&numbers);
+    assert_eq!(
+        vec![
+            Win {
+                board: 0,
+                draw: 4,
+                number: 7,
+                unmarked_sum: 19,
+                score: 133,
+            },
+            Win {
+                board: 1,
+                draw: 4,
+                number: 7,
+                unmarked_sum: 24,
+                score: 168,
+            },
+        ],
+        wins
+    );
+
+    // With diagonals it wins on the 9, and the non-square board is unaffected
+    let wins = BingoGame::new(&board_views, true).play(&numbers);
+    assert_eq!(
+        vec![
+            Win {
+                board: 0,
+                draw: 2,
+                number: 9,
+                unmarked_sum: 30,
+                score: 270,
+            },
+            Win {
+                board: 1,
+                draw: 4,
+                number: 7,
+                unmarked_sum: 24,
+                score: 168,
+            },
+        ],
+        wins
+    );
+}
+
+#[test]
+fn test_parse_input_crlf() {
+    let input_str = "7,4,9\n\n22 13\n 8  2\n\n3 15\n 9 18\n";
+    let windows_str = "7,4,9  \r\n\r\n\r\n22 13\r\n 8  2\r\n \r\n3 15\r\n 9 18\r\n\r\n";
+    assert_eq!(parse_input(input_str), parse_input(windows_str));
+    assert_eq!(2, parse_input(windows_str).1.len());
+}
+
+#[test]
+fn test_examples() {
+    for check in puzzle::check_examples(EXAMPLES, PARTS) {
+        assert!(check.is_ok(), "{}", check);
+    }
+}
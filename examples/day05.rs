@@ -1,3 +1,8 @@
+use std::collections::HashMap;
+
+use aoc_2021_rs::puzzle::{self, Example};
+
+#[cfg(test)]
 use ndarray::{arr2, Array2};
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
@@ -30,6 +35,7 @@ impl LineEnds {
         (rise / d, run / d)
     }
 
+    #[cfg(test)]
     fn points_on_segment(&self) -> Vec<Point> {
         let mut result: Vec<Point> = Vec::new();
         let mut xpoint = self.p1.x;
@@ -51,6 +57,156 @@ impl LineEnds {
 
         result
     }
+
+    fn min_x(&self) -> i64 {
+        self.p1.x.min(self.p2.x)
+    }
+
+    fn max_x(&self) -> i64 {
+        self.p1.x.max(self.p2.x)
+    }
+
+    /// The line this segment is on. A segment that is a single point is treated as part of
+    /// the horizontal line through it.
+    fn line_key(&self) -> LineKey {
+        let step = if self.p1 == self.p2 {
+            Point { x: 1, y: 0 }
+        } else {
+            let (rise, run) = self.rise_and_run();
+            // Point the step rightwards, or up if the segment is vertical, so that both ways
+            // along a line give the same key
+            if run < 0 || (run == 0 && rise < 0) {
+                Point { x: -run, y: -rise }
+            } else {
+                Point { x: run, y: rise }
+            }
+        };
+        LineKey {
+            step,
+            offset: step.cross(self.p1),
+        }
+    }
+
+    /// The steps along its line that this segment covers, smallest first
+    fn step_range(&self, key: &LineKey) -> (i128, i128) {
+        let (k1, k2) = (key.steps_to(self.p1), key.steps_to(self.p2));
+        (k1.min(k2), k1.max(k2))
+    }
+
+    /// Is `p` somewhere on this segment
+    fn contains(&self, p: Point) -> bool {
+        let r = self.p2.minus(self.p1);
+        r.cross(p.minus(self.p1)) == 0
+            && p.x >= self.min_x()
+            && p.x <= self.max_x()
+            && p.y >= self.p1.y.min(self.p2.y)
+            && p.y <= self.p1.y.max(self.p2.y)
+    }
+
+    /// Every integer point that is on both this segment and `other`
+    fn overlap_points(&self, other: &LineEnds) -> Vec<Point> {
+        // A segment that is a single point has no direction
+        if self.p1 == self.p2 {
+            return [self.p1]
+                .into_iter()
+                .filter(|&p| other.contains(p))
+                .collect();
+        }
+        if other.p1 == other.p2 {
+            return [other.p1]
+                .into_iter()
+                .filter(|&p| self.contains(p))
+                .collect();
+        }
+
+        let r = self.p2.minus(self.p1);
+        let s = other.p2.minus(other.p1);
+        let q_minus_p = other.p1.minus(self.p1);
+        let denom = r.cross(s);
+
+        if denom == 0 {
+            if r.cross(q_minus_p) != 0 {
+                // Parallel, but not on the same line
+                return Vec::new();
+            }
+
+            // On the same line. Every integer point on the line is a whole number of steps
+            // along from `p1`, so find the range of steps that both segments cover
+            let (rise, run) = self.rise_and_run();
+            let step = Point { x: run, y: rise };
+            let steps_to = |p: Point| (p.minus(self.p1).dot(step) / step.dot(step)) as i64;
+            let (k1, k2) = (steps_to(other.p1), steps_to(other.p2));
+            let first = k1.min(k2).max(0);
+            let last = k1.max(k2).min(steps_to(self.p2));
+
+            return (first..=last)
+                .map(|k| Point {
+                    x: self.p1.x + k * run,
+                    y: self.p1.y + k * rise,
+                })
+                .collect();
+        }
+
+        // They cross at `p1 + r * t_num / denom`, as long as that is within both segments,
+        // and lands on an integer point
+        let (t_num, u_num) = (q_minus_p.cross(s), q_minus_p.cross(r));
+        let within = |num: i128| {
+            if denom > 0 {
+                num >= 0 && num <= denom
+            } else {
+                num <= 0 && num >= denom
+            }
+        };
+        if !within(t_num) || !within(u_num) {
+            return Vec::new();
+        }
+
+        let x_num = r.x as i128 * t_num;
+        let y_num = r.y as i128 * t_num;
+        if x_num % denom != 0 || y_num % denom != 0 {
+            return Vec::new();
+        }
+
+        vec![Point {
+            x: self.p1.x + (x_num / denom) as i64,
+            y: self.p1.y + (y_num / denom) as i64,
+        }]
+    }
+}
+
+/// A line through integer points, as the smallest step between them and which of the lines
+/// with that step it is
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+struct LineKey {
+    step: Point,
+    offset: i128,
+}
+
+impl LineKey {
+    /// How many steps `p`, which must be on this line, is from the line's closest integer point
+    /// to the origin. Every integer point on the line has the same remainder, so rounding down
+    /// gives a whole number of steps.
+    fn steps_to(&self, p: Point) -> i128 {
+        p.dot(self.step).div_euclid(self.step.dot(self.step))
+    }
+}
+
+impl Point {
+    fn minus(self, other: Point) -> Point {
+        Point {
+            x: self.x - other.x,
+            y: self.y - other.y,
+        }
+    }
+
+    // Products of coordinates are done in i128, so that large coordinates can't overflow
+    fn cross(self, other: Point) -> i128 {
+        self.x as i128 * other.y as i128 - self.y as i128 * other.x as i128
+    }
+
+    fn dot(self, other: Point) -> i128 {
+        self.x as i128 * other.x as i128 + self.y as i128 * other.y as i128
+    }
 }
 
 fn parse_input(input: &str) -> Vec<LineEnds> {
@@ -86,14 +242,17 @@ fn gcd(a_in: i64, b_in: i64) -> i64 {
     a
 }
 
+/// Count how many lines pass through every point, using a dense grid covering all of the
+/// lines. Kept to check `overlap_points` against.
+#[cfg(test)]
 fn count_line_points(input: &[LineEnds]) -> Array2<usize> {
     let points_visited: Vec<_> = input
         .iter()
         .flat_map(|&line| line.points_on_segment())
         .collect();
 
-    let max_x = &points_visited.iter().map(|p| p.x).max().unwrap() + 1;
-    let max_y = &points_visited.iter().map(|p| p.y).max().unwrap() + 1;
+    let max_x = points_visited.iter().map(|p| p.x).max().unwrap() + 1;
+    let max_y = points_visited.iter().map(|p| p.y).max().unwrap() + 1;
     let mut array = Array2::<usize>::zeros((max_y as usize, max_x as usize));
 
     // Fill up the array, adding 1 to each point that is visited
@@ -105,6 +264,155 @@ fn count_line_points(input: &[LineEnds]) -> Array2<usize> {
     array
 }
 
+/// The stretches of a line that at least two lines cover, sorted and not touching, along
+/// with a point on the line and how many steps along it that point is
+type Stretches = (Point, i128, Vec<(i128, i128)>);
+
+/// Where the lines overlap. Lines on the same line overlap along whole stretches, which are
+/// kept as ranges of steps along it, and lines that cross overlap at single points.
+struct Overlaps {
+    /// The stretches of each line that at least two lines cover
+    stretches: HashMap<LineKey, Stretches>,
+    /// The points where lines on different lines cross, with how many of the stretches each
+    /// is in
+    crossings: Vec<(Point, usize)>,
+}
+
+impl Overlaps {
+    fn find(input: &[LineEnds]) -> Overlaps {
+        // Group the lines by the line they are on, and merge each group's ranges of steps
+        let mut groups: HashMap<LineKey, Vec<&LineEnds>> = HashMap::new();
+        for line in input {
+            groups.entry(line.line_key()).or_default().push(line);
+        }
+        let stretches: HashMap<LineKey, Stretches> = groups
+            .into_iter()
+            .filter(|(_, lines)| lines.len() > 1)
+            .map(|(key, lines)| {
+                let ranges: Vec<(i128, i128)> =
+                    lines.iter().map(|line| line.step_range(&key)).collect();
+                let anchor = lines[0].p1;
+                (key, (anchor, key.steps_to(anchor), covered_twice(&ranges)))
+            })
+            .collect();
+        let in_stretch = |key: &LineKey, p: Point| {
+            stretches.get(key).is_some_and(|(_, _, ranges)| {
+                let k = key.steps_to(p);
+                let idx = ranges.partition_point(|&(_, last)| last < k);
+                ranges.get(idx).is_some_and(|&(first, _)| first <= k)
+            })
+        };
+
+        // Sweep across the lines from left to right, only comparing lines whose x ranges
+        // overlap, to find where lines on different lines cross. In the worst case every pair
+        // is still compared, but each pair only meets at one point.
+        let mut lines: Vec<(LineKey, &LineEnds)> =
+            input.iter().map(|line| (line.line_key(), line)).collect();
+        lines.sort_unstable_by_key(|(_, line)| line.min_x());
+        let mut crossings: HashMap<Point, Vec<LineKey>> = HashMap::new();
+        for (idx, (key, line)) in lines.iter().enumerate() {
+            // Any line that starts after this one ends can't overlap it, and nor can any after it
+            for (other_key, other) in lines[idx + 1..]
+                .iter()
+                .take_while(|(_, other)| other.min_x() <= line.max_x())
+            {
+                if key == other_key {
+                    continue;
+                }
+                for p in line.overlap_points(other) {
+                    let stretch_keys = crossings.entry(p).or_default();
+                    for k in [key, other_key] {
+                        if in_stretch(k, p) && !stretch_keys.contains(k) {
+                            stretch_keys.push(*k);
+                        }
+                    }
+                }
+            }
+        }
+
+        Overlaps {
+            stretches,
+            crossings: crossings
+                .into_iter()
+                .map(|(p, stretch_keys)| (p, stretch_keys.len()))
+                .collect(),
+        }
+    }
+
+    /// How many points at least two lines overlap at, without listing them
+    fn count(&self) -> usize {
+        let in_stretches: i128 = self
+            .stretches
+            .values()
+            .flat_map(|(_, _, ranges)| ranges)
+            .map(|(first, last)| last - first + 1)
+            .sum();
+        // Each crossing should be counted once, but was already counted once for each
+        // stretch it is in
+        let crossings: i128 = self
+            .crossings
+            .iter()
+            .map(|&(_, n_stretches)| 1 - n_stretches as i128)
+            .sum();
+        (in_stretches + crossings) as usize
+    }
+
+    /// Every point at least two lines overlap at, sorted by x and then y
+    fn points(&self) -> Vec<Point> {
+        let mut points: Vec<Point> = self
+            .crossings
+            .iter()
+            .filter(|&&(_, n_stretches)| n_stretches == 0)
+            .map(|&(p, _)| p)
+            .collect();
+        for (key, (anchor, anchor_k, ranges)) in &self.stretches {
+            for &(first, last) in ranges {
+                points.extend((first..=last).map(|k| Point {
+                    x: anchor.x + ((k - anchor_k) * key.step.x as i128) as i64,
+                    y: anchor.y + ((k - anchor_k) * key.step.y as i128) as i64,
+                }));
+            }
+        }
+        points.sort_unstable_by_key(|p| (p.x, p.y));
+        // Stretches on different lines can cross
+        points.dedup();
+        points
+    }
+}
+
+/// The parts of the inclusive `ranges` that at least two of them cover, sorted and merged
+fn covered_twice(ranges: &[(i128, i128)]) -> Vec<(i128, i128)> {
+    // Each range adds one from its first step, and takes it away again after its last
+    let mut changes: Vec<(i128, i32)> = ranges
+        .iter()
+        .flat_map(|&(first, last)| [(first, 1), (last + 1, -1)])
+        .collect();
+    changes.sort_unstable();
+
+    let mut result: Vec<(i128, i128)> = Vec::new();
+    let mut depth = 0;
+    for (k, change) in changes {
+        let was_covered = depth >= 2;
+        depth += change;
+        // A stretch starts when the second range starts, and ends when only one is left
+        match (was_covered, depth >= 2) {
+            (false, true) => match result.last_mut() {
+                // Carry on a stretch that only stopped for a moment
+                Some((_, last)) if *last == k - 1 => *last = k,
+                _ => result.push((k, k)),
+            },
+            (true, false) => result.last_mut().expect("A stretch was started").1 = k - 1,
+            _ => {}
+        }
+    }
+    result
+}
+
+/// Every point where at least two lines overlap, sorted by x and then y
+fn overlap_points(input: &[LineEnds]) -> Vec<Point> {
+    Overlaps::find(input).points()
+}
+
 fn part1(lines: &[LineEnds]) -> usize {
     // Filter out any that are not horizontal or vertical
     let new_lines: Vec<LineEnds> = lines
@@ -113,17 +421,46 @@ fn part1(lines: &[LineEnds]) -> usize {
         .copied()
         .collect::<Vec<_>>();
 
-    count_line_points(&new_lines)
-        .iter()
-        .filter(|&n| *n >= 2)
-        .count()
+    Overlaps::find(&new_lines).count()
 }
 
 fn part2(lines: &[LineEnds]) -> usize {
-    count_line_points(lines).iter().filter(|&n| *n >= 2).count()
+    Overlaps::find(lines).count()
+}
+
+/// The examples from the puzzle description
+const EXAMPLES: &[Example] = &[Example {
+    input: "0,9 -> 5,9
+8,0 -> 0,8
+9,4 -> 3,4
+2,2 -> 2,1
+7,0 -> 7,4
+6,4 -> 2,0
+0,9 -> 2,9
+3,4 -> 1,4
+0,0 -> 8,8
+5,5 -> 8,2",
+    part1: Some("5"),
+    part2: Some("12"),
+}];
+
+/// Each part, solved straight from the input
+const PARTS: [Option<puzzle::Part>; 2] = [Some(solve_part1), Some(solve_part2)];
+
+fn solve_part1(input: &str) -> Result<String, String> {
+    Ok(part1(&parse_input(input)).to_string())
+}
+
+fn solve_part2(input: &str) -> Result<String, String> {
+    Ok(part2(&parse_input(input)).to_string())
 }
 
 fn main() {
+    // Pass `--example` to check the solutions against the puzzle's examples instead
+    if std::env::args().any(|arg| arg == "--example") {
+        std::process::exit(puzzle::run_examples(EXAMPLES, PARTS));
+    }
+
     let setup_time = std::time::Instant::now();
 
     let input_str = std::fs::read_to_string("input/day05.txt").expect("Failed to read day 5 input");
@@ -143,6 +480,14 @@ fn main() {
     println!();
     println!("Part 1 result: {}", part1_result);
     println!("Part 2 result: {}", part2_result);
+
+    // Pass `--points` to list every point where the lines overlap
+    if std::env::args().any(|arg| arg == "--points") {
+        println!();
+        for point in overlap_points(&lines) {
+            println!("{},{}", point.x, point.y);
+        }
+    }
 }
 
 #[test]
@@ -200,7 +545,7 @@ fn test_parse_input() {
             p2: Point { x: 8, y: 2 },
         },
     ];
-    let got = parse_input(&input_str);
+    let got = parse_input(input_str);
     assert_eq!(expected_input, got);
 }
 
@@ -251,7 +596,7 @@ fn test_count_line_points_horizontal_vertical() {
     let input: Vec<LineEnds> = input
         .iter()
         .filter(|line| line.is_vertical() || line.is_horizontal())
-        .map(|&l| l)
+        .copied()
         .collect();
 
     let got = count_line_points(&input);
@@ -484,3 +829,144 @@ fn test_part2_acutal() {
     let got = part2(&lines);
     assert_eq!(18423, got);
 }
+
+#[test]
+fn test_overlap_points() {
+    let input_str = "0,9 -> 5,9
+8,0 -> 0,8
+9,4 -> 3,4
+2,2 -> 2,1
+7,0 -> 7,4
+6,4 -> 2,0
+0,9 -> 2,9
+3,4 -> 1,4
+0,0 -> 8,8
+5,5 -> 8,2";
+    let lines = parse_input(input_str);
+
+    // Should be the same as every point with a count of 2 or more from the dense grid
+    let counts = count_line_points(&lines);
+    let mut expected: Vec<Point> = counts
+        .indexed_iter()
+        .filter(|(_, &n)| n >= 2)
+        .map(|((y, x), _)| Point {
+            x: x as i64,
+            y: y as i64,
+        })
+        .collect();
+    expected.sort_unstable_by_key(|p| (p.x, p.y));
+
+    assert_eq!(expected, overlap_points(&lines));
+}
+
+#[test]
+fn test_overlap_points_actual() {
+    let input_str = std::fs::read_to_string("input/day05.txt").expect("Failed to read day 5 input");
+    let lines = parse_input(&input_str);
+    let n_dense = count_line_points(&lines)
+        .iter()
+        .filter(|&n| *n >= 2)
+        .count();
+    assert_eq!(n_dense, overlap_points(&lines).len());
+}
+
+#[test]
+fn test_segment_overlap_points() {
+    let line = |x1, y1, x2, y2| LineEnds {
+        p1: Point { x: x1, y: y1 },
+        p2: Point { x: x2, y: y2 },
+    };
+
+    // Diagonals that cross between integer points don't overlap
+    assert_eq!(
+        Vec::<Point>::new(),
+        line(0, 0, 1, 1).overlap_points(&line(0, 1, 1, 0))
+    );
+    // But ones that cross on an integer point do
+    assert_eq!(
+        vec![Point { x: 1, y: 1 }],
+        line(0, 0, 2, 2).overlap_points(&line(0, 2, 2, 0))
+    );
+    // Steep slopes only hit a few integer points
+    assert_eq!(
+        vec![Point { x: 2, y: 6 }],
+        line(0, 0, 4, 12).overlap_points(&line(0, 6, 10, 6))
+    );
+    assert_eq!(
+        Vec::<Point>::new(),
+        line(0, 0, 4, 12).overlap_points(&line(0, 5, 10, 5))
+    );
+    // Parallel lines that are on the same line share a stretch
+    assert_eq!(
+        vec![Point { x: 2, y: 1 }, Point { x: 4, y: 2 }],
+        line(0, 0, 4, 2).overlap_points(&line(6, 3, 2, 1))
+    );
+    // Parallel lines that aren't on the same line never meet
+    assert_eq!(
+        Vec::<Point>::new(),
+        line(0, 0, 4, 2).overlap_points(&line(0, 1, 4, 3))
+    );
+    // Single points
+    assert_eq!(
+        vec![Point { x: 3, y: 3 }],
+        line(3, 3, 3, 3).overlap_points(&line(0, 0, 5, 5))
+    );
+    // Coordinates far too large for a grid
+    let big = 1_000_000_000_000;
+    assert_eq!(
+        vec![Point { x: big, y: big }],
+        line(0, 0, 2 * big, 2 * big).overlap_points(&line(0, 2 * big, 2 * big, 0))
+    );
+}
+
+#[test]
+fn test_long_collinear_overlaps() {
+    // Two long stretches on top of each other, which cross a short vertical stretch, and a
+    // line that crosses where only one line is. Listing the points would take far too long.
+    let input = "0,0 -> 50000000,0
+10,0 -> 49999999,0
+20,-1 -> 20,1
+20,0 -> 20,3
+5,-5 -> 5,5";
+    let lines = parse_input(input);
+    assert_eq!(49_999_990 + 2 - 1 + 1, part1(&lines));
+    assert_eq!(49_999_990 + 2 - 1 + 1, part2(&lines));
+
+    // The same shape, small enough for the dense grid, moved away from negative coordinates
+    let input = "0,5 -> 50,5
+10,5 -> 49,5
+20,4 -> 20,6
+20,5 -> 20,8
+5,0 -> 5,10
+49,5 -> 45,9
+45,9 -> 47,7";
+    let lines = parse_input(input);
+    let expected = count_line_points(&lines)
+        .iter()
+        .filter(|&n| *n >= 2)
+        .count();
+    assert_eq!(expected, part2(&lines));
+    assert_eq!(expected, overlap_points(&lines).len());
+}
+
+#[test]
+fn test_covered_twice() {
+    assert_eq!(Vec::<(i128, i128)>::new(), covered_twice(&[(0, 5), (6, 9)]));
+    assert_eq!(vec![(3, 5)], covered_twice(&[(0, 5), (3, 9)]));
+    // One stretch ends just as another starts, so they join up
+    assert_eq!(
+        vec![(2, 9)],
+        covered_twice(&[(0, 5), (2, 5), (6, 9), (6, 12)])
+    );
+    assert_eq!(
+        vec![(1, 1), (4, 4)],
+        covered_twice(&[(1, 4), (1, 1), (4, 4)])
+    );
+}
+
+#[test]
+fn test_examples() {
+    for check in puzzle::check_examples(EXAMPLES, PARTS) {
+        assert!(check.is_ok(), "{}", check);
+    }
+}
@@ -1,3 +1,8 @@
+use aoc_2021_rs::crosscheck::{self, Crosscheck};
+use aoc_2021_rs::matrix::checked_matrix_power;
+use aoc_2021_rs::puzzle::{self, Example};
+use ndarray::{Array1, Array2};
+
 fn parse_input(input: &str) -> [usize; 9] {
     let mut v: [usize; 9] = [0; 9];
     input
@@ -22,7 +27,174 @@ fn solve(input: &[usize; 9], n_days: usize) -> usize {
     population.iter().sum()
 }
 
+/// Follow every fish on its own, as the puzzle describes it. This is far too slow for part 2,
+/// but simple enough to check the quicker ways against.
+fn simulate(input: &str, n_days: usize) -> usize {
+    let mut timers: Vec<u8> = input
+        .trim()
+        .split(',')
+        .map(|n| n.parse().expect("Could not read number"))
+        .collect();
+    for _ in 0..n_days {
+        let n_newborn = timers.iter().filter(|&&timer| timer == 0).count();
+        for timer in timers.iter_mut() {
+            *timer = if *timer == 0 { 6 } else { *timer - 1 };
+        }
+        timers.resize(timers.len() + n_newborn, 8);
+    }
+
+    timers.len()
+}
+
+/// How the lanternfish reproduce. When a fish's timer would go below 0, it creates a new fish
+/// with a timer of `newborn_timer`, and its own timer goes back to `reset_timer`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Lifecycle {
+    reset_timer: usize,
+    newborn_timer: usize,
+}
+
+impl Lifecycle {
+    const LANTERNFISH: Lifecycle = Lifecycle {
+        reset_timer: 6,
+        newborn_timer: 8,
+    };
+
+    /// The number of different timer values a fish can have, given that the starting fish
+    /// can have timers of up to 8
+    fn n_states(&self) -> usize {
+        self.reset_timer.max(self.newborn_timer).max(8) + 1
+    }
+
+    /// `transition[[to, from]]` is how many fish with timer `to` a single fish with timer
+    /// `from` becomes in one day
+    fn transition(&self) -> Array2<u128> {
+        let n = self.n_states();
+        let mut transition = Array2::zeros((n, n));
+        for from in 1..n {
+            transition[[from - 1, from]] = 1;
+        }
+        transition[[self.reset_timer, 0]] += 1;
+        transition[[self.newborn_timer, 0]] += 1;
+        transition
+    }
+}
+
+fn overflow_error() -> String {
+    String::from("The population is too large to fit in a u128")
+}
+
+/// How many fish have each timer value, padded out to every state in `lifecycle`
+fn initial_counts(input: &[usize; 9], lifecycle: &Lifecycle) -> Vec<u128> {
+    let mut counts = vec![0; lifecycle.n_states()];
+    for (timer, &count) in input.iter().enumerate() {
+        counts[timer] = count as u128;
+    }
+    counts
+}
+
+/// Move every fish on to the next day
+fn next_day(mut counts: Vec<u128>, lifecycle: &Lifecycle) -> Result<Vec<u128>, String> {
+    let spawning = counts[0];
+    counts.rotate_left(1);
+    let last = counts.len() - 1;
+    counts[last] = 0;
+    for timer in [lifecycle.reset_timer, lifecycle.newborn_timer] {
+        counts[timer] = counts[timer]
+            .checked_add(spawning)
+            .ok_or_else(overflow_error)?;
+    }
+    Ok(counts)
+}
+
+/// An endless iterator over the total population on each day, starting with the day the fish
+/// are counted. If the population overflows, the error is yielded, and the iterator stops.
+fn populations(
+    input: &[usize; 9],
+    lifecycle: Lifecycle,
+) -> impl Iterator<Item = Result<u128, String>> {
+    let mut next = Some(Ok(initial_counts(input, &lifecycle)));
+    std::iter::from_fn(move || {
+        let counts = match next.take()? {
+            Ok(counts) => counts,
+            Err(e) => return Some(Err(e)),
+        };
+        let total = counts
+            .iter()
+            .try_fold(0_u128, |total, &count| total.checked_add(count))
+            .ok_or_else(overflow_error);
+        if total.is_ok() {
+            next = Some(next_day(counts, &lifecycle));
+        }
+        Some(total)
+    })
+}
+
+/// The population after `n_days`, found by raising the transition matrix to the power
+/// `n_days`. This takes O(log(n_days)) matrix multiplications instead of `n_days` steps.
+fn project(input: &[usize; 9], lifecycle: &Lifecycle, n_days: usize) -> Result<u128, String> {
+    let power =
+        checked_matrix_power(lifecycle.transition().view(), n_days).ok_or_else(overflow_error)?;
+    let counts = Array1::from(initial_counts(input, lifecycle));
+
+    let mut total: u128 = 0;
+    for ((_, from), &n) in power.indexed_iter() {
+        let fish = n.checked_mul(counts[from]).ok_or_else(overflow_error)?;
+        total = total.checked_add(fish).ok_or_else(overflow_error)?;
+    }
+    Ok(total)
+}
+
+/// The examples from the puzzle description
+const EXAMPLES: &[Example] = &[Example {
+    input: "3,4,3,1,2",
+    part1: Some("5934"),
+    part2: Some("26984457539"),
+}];
+
+/// Each part, solved straight from the input
+const PARTS: [Option<puzzle::Part>; 2] = [Some(solve_part1), Some(solve_part2)];
+
+fn solve_part1(input: &str) -> Result<String, String> {
+    Ok(solve(&parse_input(input), 80).to_string())
+}
+
+fn solve_part2(input: &str) -> Result<String, String> {
+    Ok(solve(&parse_input(input), 256).to_string())
+}
+
+/// The quick ways of counting the fish, each checked against following every fish
+const CROSSCHECKS: &[Crosscheck] = &[
+    Crosscheck {
+        name: "Part 1 by rotating counts",
+        reference: simulate_part1,
+        optimised: solve_part1,
+    },
+    Crosscheck {
+        name: "Part 1 by matrix power",
+        reference: simulate_part1,
+        optimised: project_part1,
+    },
+];
+
+fn simulate_part1(input: &str) -> Result<String, String> {
+    Ok(simulate(input, 80).to_string())
+}
+
+fn project_part1(input: &str) -> Result<String, String> {
+    project(&parse_input(input), &Lifecycle::LANTERNFISH, 80).map(|n| n.to_string())
+}
+
 fn main() {
+    // Pass `--example` to check the solutions against the puzzle's examples instead
+    if std::env::args().any(|arg| arg == "--example") {
+        std::process::exit(puzzle::run_examples(EXAMPLES, PARTS));
+    }
+    // Pass `--crosscheck` to check the quick solutions against simple ones on random inputs
+    if std::env::args().any(|arg| arg == "--crosscheck") {
+        std::process::exit(crosscheck::run(6, CROSSCHECKS));
+    }
+
     let setup_time = std::time::Instant::now();
 
     let input_str = std::fs::read_to_string("input/day06.txt").expect("Failed to read day 6 input");
@@ -42,6 +214,31 @@ fn main() {
     println!();
     println!("Part 1 result: {}", part1_result);
     println!("Part 2 result: {}", part2_result);
+
+    // Pass `--days=N` to project the population N days ahead, or `--growth=N` to get the
+    // population on each of the first N days as CSV
+    for arg in std::env::args() {
+        if let Some(n_days) = arg.strip_prefix("--days=") {
+            let n_days = n_days.parse().expect("Could not parse number of days");
+            match project(&numbers, &Lifecycle::LANTERNFISH, n_days) {
+                Ok(population) => println!("After {} days: {}", n_days, population),
+                Err(e) => println!("After {} days: {}", n_days, e),
+            }
+        }
+        if let Some(n_days) = arg.strip_prefix("--growth=") {
+            let n_days: usize = n_days.parse().expect("Could not parse number of days");
+            println!("day,population");
+            for (day, population) in populations(&numbers, Lifecycle::LANTERNFISH)
+                .take(n_days + 1)
+                .enumerate()
+            {
+                match population {
+                    Ok(population) => println!("{},{}", day, population),
+                    Err(e) => println!("{},{}", day, e),
+                }
+            }
+        }
+    }
 }
 
 #[test]
@@ -91,3 +288,99 @@ fn test_part2_actual() {
 
     assert_eq!(1702631502303, got);
 }
+
+#[test]
+fn test_project() {
+    let input: [usize; 9] = [0, 1, 1, 2, 1, 0, 0, 0, 0];
+    assert_eq!(Ok(26), project(&input, &Lifecycle::LANTERNFISH, 18));
+    assert_eq!(Ok(5934), project(&input, &Lifecycle::LANTERNFISH, 80));
+    assert_eq!(
+        Ok(26984457539),
+        project(&input, &Lifecycle::LANTERNFISH, 256)
+    );
+
+    for n_days in [0, 1, 7, 9, 100, 300] {
+        assert_eq!(
+            Ok(solve(&input, n_days) as u128),
+            project(&input, &Lifecycle::LANTERNFISH, n_days)
+        );
+    }
+}
+
+#[test]
+fn test_project_overflow() {
+    let input: [usize; 9] = [0, 1, 1, 2, 1, 0, 0, 0, 0];
+    assert_eq!(
+        Err(overflow_error()),
+        project(&input, &Lifecycle::LANTERNFISH, 10_000)
+    );
+}
+
+#[test]
+fn test_populations() {
+    let input: [usize; 9] = [0, 1, 1, 2, 1, 0, 0, 0, 0];
+    let got: Vec<u128> = populations(&input, Lifecycle::LANTERNFISH)
+        .take(19)
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(vec![5, 5, 6, 7, 9, 10], got[..6]);
+    assert_eq!(26, got[18]);
+
+    // The populations should agree with the projection until they overflow
+    for (n_days, population) in populations(&input, Lifecycle::LANTERNFISH).enumerate() {
+        assert_eq!(project(&input, &Lifecycle::LANTERNFISH, n_days), population);
+        if population.is_err() {
+            assert!(n_days > 256);
+            break;
+        }
+    }
+}
+
+#[test]
+fn test_lifecycle() {
+    // If every fish has a baby every day, the population doubles every day
+    let lifecycle = Lifecycle {
+        reset_timer: 0,
+        newborn_timer: 0,
+    };
+    let input: [usize; 9] = [1, 0, 0, 0, 0, 0, 0, 0, 0];
+    assert_eq!(Ok(1 << 100), project(&input, &lifecycle, 100));
+    let got: Vec<u128> = populations(&input, lifecycle)
+        .take(5)
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(vec![1, 2, 4, 8, 16], got);
+
+    // Longer timers than the lanternfish need more states
+    let lifecycle = Lifecycle {
+        reset_timer: 10,
+        newborn_timer: 12,
+    };
+    assert_eq!(13, lifecycle.n_states());
+    let got: Vec<u128> = populations(&input, lifecycle)
+        .take(14)
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(vec![1, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 3, 3], got);
+    assert_eq!(Ok(3), project(&input, &lifecycle, 13));
+}
+
+#[test]
+fn test_examples() {
+    for check in puzzle::check_examples(EXAMPLES, PARTS) {
+        assert!(check.is_ok(), "{}", check);
+    }
+}
+
+#[test]
+fn test_simulate() {
+    assert_eq!(26, simulate("3,4,3,1,2", 18));
+    assert_eq!(5934, simulate("3,4,3,1,2", 80));
+}
+
+#[test]
+fn test_crosschecks() {
+    for check in CROSSCHECKS {
+        assert_eq!(Ok(None), check.find_disagreement(6, 5, Some(50)));
+    }
+}
@@ -1,3 +1,6 @@
+use aoc_2021_rs::crosscheck::{self, Crosscheck};
+use aoc_2021_rs::puzzle::{self, Example};
+
 fn parse_input(input: &str) -> Vec<usize> {
     let mut nums: Vec<usize> = input
         .trim()
@@ -10,56 +13,168 @@ fn parse_input(input: &str) -> Vec<usize> {
     nums
 }
 
-fn unsigned_diff(u1: usize, u2: usize) -> usize {
-    if u1 > u2 {
-        u1 - u2
-    } else {
-        u2 - u1
-    }
+/// How much fuel a crab uses to move a given distance
+enum CostModel {
+    /// Each step costs 1 fuel
+    Linear,
+    /// Each step costs 1 more than the last, so `n` steps cost `1 + 2 + ... + n`
+    Triangular,
+    /// `n` steps cost `n * n`
+    Quadratic,
+    /// Any other cost of moving a distance. Nothing is known about its shape, so every
+    /// position has to be tried.
+    Custom(Box<dyn Fn(usize) -> usize>),
 }
 
-fn part1(input: &[usize]) -> usize {
-    // `input` must be sorted
-
-    // Get the position of the middle item
-    let middle_idx = (input.len() / 2) + (input.len() % 2);
-
-    // Get the median
-    let median = input[middle_idx];
+impl CostModel {
+    fn cost(&self, distance: usize) -> usize {
+        match self {
+            CostModel::Linear => distance,
+            CostModel::Triangular => sum_to_n(distance),
+            CostModel::Quadratic => distance * distance,
+            CostModel::Custom(f) => f(distance),
+        }
+    }
+}
 
-    // Get the differences with respect to the median
-    input.iter().map(|&n| unsigned_diff(n, median)).sum()
+/// The best place for the crabs to line up, and how much fuel it takes them to get there
+#[derive(Debug, PartialEq, Eq)]
+struct Alignment {
+    position: usize,
+    fuel: usize,
 }
 
 fn sum_to_n(n: usize) -> usize {
     ((n + 1) * n) / 2
 }
 
-fn cost_at_point(crab_positions: &[usize], central_position: usize) -> usize {
+fn cost_at_point(crab_positions: &[usize], central_position: usize, model: &CostModel) -> usize {
     crab_positions
         .iter()
         // Get the distance to each crab position from the central_position
-        .map(|&n| unsigned_diff(n, central_position))
+        .map(|&n| n.abs_diff(central_position))
         // Get the cost to travel that distance
-        .map(sum_to_n)
+        .map(|distance| model.cost(distance))
         // Sum it up
         .sum()
 }
 
-fn part2(input: &[usize]) -> usize {
-    // What are the points we need to test
-    let minimum = input.iter().min().expect("Could not find the minimum");
-    let maximum = input.iter().max().expect("Could not find the maximum");
-    (*minimum..=*maximum)
-        .into_iter()
-        // Test each
-        .map(|test_position| cost_at_point(input, test_position))
-        // Select the minimum fuel cost
+/// The cheapest of `candidates`, picking the lowest position if there is a tie
+fn cheapest(
+    crab_positions: &[usize],
+    candidates: impl Iterator<Item = usize>,
+    model: &CostModel,
+) -> Alignment {
+    candidates
+        .map(|position| Alignment {
+            position,
+            fuel: cost_at_point(crab_positions, position, model),
+        })
+        .min_by_key(|alignment| (alignment.fuel, alignment.position))
+        .expect("There were no positions to try")
+}
+
+/// Find the position where lining up the crabs takes the least fuel
+fn align(crab_positions: &[usize], model: &CostModel) -> Result<Alignment, String> {
+    let n_crabs = crab_positions.len();
+    if n_crabs == 0 {
+        return Err("There are no crabs to line up".to_string());
+    }
+    let mean = crab_positions.iter().sum::<usize>() / n_crabs;
+
+    Ok(match model {
+        // Any median is the best position. `crab_positions` must be sorted.
+        CostModel::Linear => cheapest(
+            crab_positions,
+            std::iter::once(crab_positions[(n_crabs - 1) / 2]),
+            model,
+        ),
+        // The mean is the best position, so it's either side of the rounded down mean
+        CostModel::Quadratic => cheapest(crab_positions, mean..=mean + 1, model),
+        // The best position is within half a step of the mean
+        CostModel::Triangular => cheapest(crab_positions, mean.saturating_sub(1)..=mean + 1, model),
+        // Even a cost that never goes down with distance can give a total with more than one
+        // dip, e.g. crabs at 0, 10 and 10 when any move costs 1, so search everywhere
+        CostModel::Custom(_) => brute_force(crab_positions, model),
+    })
+}
+
+/// Try every position between the first and last crab
+fn brute_force(crab_positions: &[usize], model: &CostModel) -> Alignment {
+    let minimum = *crab_positions
+        .iter()
         .min()
-        .expect("Could not find a minimum cost")
+        .expect("Could not find the minimum");
+    let maximum = *crab_positions
+        .iter()
+        .max()
+        .expect("Could not find the maximum");
+    cheapest(crab_positions, minimum..=maximum, model)
+}
+
+fn part1(input: &[usize]) -> Result<usize, String> {
+    // `input` must be sorted
+    Ok(align(input, &CostModel::Linear)?.fuel)
+}
+
+fn part2(input: &[usize]) -> Result<usize, String> {
+    Ok(align(input, &CostModel::Triangular)?.fuel)
+}
+
+/// The examples from the puzzle description
+const EXAMPLES: &[Example] = &[Example {
+    input: "16,1,2,0,4,2,7,1,2,14",
+    part1: Some("37"),
+    part2: Some("168"),
+}];
+
+/// Each part, solved straight from the input
+const PARTS: [Option<puzzle::Part>; 2] = [Some(solve_part1), Some(solve_part2)];
+
+fn solve_part1(input: &str) -> Result<String, String> {
+    Ok(part1(&parse_input(input))?.to_string())
+}
+
+fn solve_part2(input: &str) -> Result<String, String> {
+    Ok(part2(&parse_input(input))?.to_string())
+}
+
+/// Lining up at the median or mean, each checked against trying every position
+const CROSSCHECKS: &[Crosscheck] = &[
+    Crosscheck {
+        name: "Part 1 by median",
+        reference: brute_force_part1,
+        optimised: solve_part1,
+    },
+    Crosscheck {
+        name: "Part 2 by mean",
+        reference: brute_force_part2,
+        optimised: solve_part2,
+    },
+];
+
+fn brute_force_part1(input: &str) -> Result<String, String> {
+    Ok(brute_force(&parse_input(input), &CostModel::Linear)
+        .fuel
+        .to_string())
+}
+
+fn brute_force_part2(input: &str) -> Result<String, String> {
+    Ok(brute_force(&parse_input(input), &CostModel::Triangular)
+        .fuel
+        .to_string())
 }
 
 fn main() {
+    // Pass `--example` to check the solutions against the puzzle's examples instead
+    if std::env::args().any(|arg| arg == "--example") {
+        std::process::exit(puzzle::run_examples(EXAMPLES, PARTS));
+    }
+    // Pass `--crosscheck` to check the quick solutions against simple ones on random inputs
+    if std::env::args().any(|arg| arg == "--crosscheck") {
+        std::process::exit(crosscheck::run(7, CROSSCHECKS));
+    }
+
     let setup_time = std::time::Instant::now();
 
     let input_str = std::fs::read_to_string("input/day07.txt").expect("Failed to read day 7 input");
@@ -68,17 +183,41 @@ fn main() {
 
     // Part 1
     let part1_time = std::time::Instant::now();
-    let part1_result = part1(&numbers);
+    let part1_result = part1(&numbers).expect("Failed to solve part 1");
     println!("Part 1 took {:.6} µs", part1_time.elapsed().as_micros());
 
     // Part 2
     let part2_time = std::time::Instant::now();
-    let part2_result = part2(&numbers);
+    let part2_result = part2(&numbers).expect("Failed to solve part 2");
     println!("Part 2 took {:.6} µs", part2_time.elapsed().as_micros());
 
     println!();
     println!("Part 1 result: {}", part1_result);
     println!("Part 2 result: {}", part2_result);
+
+    // Pass `--cost=linear`, `--cost=triangular`, `--cost=quadratic` or `--cost=power:K` to see
+    // where the crabs line up with that cost model
+    for arg in std::env::args() {
+        if let Some(name) = arg.strip_prefix("--cost=") {
+            let model = match name {
+                "linear" => CostModel::Linear,
+                "triangular" => CostModel::Triangular,
+                "quadratic" => CostModel::Quadratic,
+                _ => {
+                    let k: u32 = name
+                        .strip_prefix("power:")
+                        .and_then(|k| k.parse().ok())
+                        .expect("Unknown cost model");
+                    CostModel::Custom(Box::new(move |n| n.pow(k)))
+                }
+            };
+            let alignment = align(&numbers, &model).expect("Failed to line up the crabs");
+            println!(
+                "With {} cost: position {}, fuel {}",
+                name, alignment.position, alignment.fuel
+            );
+        }
+    }
 }
 
 #[test]
@@ -93,7 +232,7 @@ fn test_parse_input() {
 fn test_part1() {
     let nums: Vec<usize> = vec![0, 1, 1, 2, 2, 2, 4, 7, 14, 16];
     let got = part1(&nums);
-    assert_eq!(37, got);
+    assert_eq!(Ok(37), got);
 }
 
 #[test]
@@ -101,7 +240,7 @@ fn test_part1_actual() {
     let input_str = std::fs::read_to_string("input/day07.txt").expect("Failed to read day 7 input");
     let numbers = parse_input(&input_str);
     let got = part1(&numbers);
-    assert_eq!(336701, got);
+    assert_eq!(Ok(336701), got);
 }
 
 #[test]
@@ -119,14 +258,14 @@ fn test_sum_to_n2() {
 #[test]
 fn test_cost_at_point1() {
     let nums: Vec<usize> = vec![0, 1, 1, 2, 2, 2, 4, 7, 14, 16];
-    let got = cost_at_point(&nums, 5);
+    let got = cost_at_point(&nums, 5, &CostModel::Triangular);
     assert_eq!(168, got);
 }
 
 #[test]
 fn test_cost_at_point2() {
     let nums: Vec<usize> = vec![0, 1, 1, 2, 2, 2, 4, 7, 14, 16];
-    let got = cost_at_point(&nums, 2);
+    let got = cost_at_point(&nums, 2, &CostModel::Triangular);
     assert_eq!(206, got);
 }
 
@@ -134,7 +273,7 @@ fn test_cost_at_point2() {
 fn test_part2() {
     let nums: Vec<usize> = vec![0, 1, 1, 2, 2, 2, 4, 7, 14, 16];
     let got = part2(&nums);
-    assert_eq!(168, got);
+    assert_eq!(Ok(168), got);
 }
 
 #[test]
@@ -142,5 +281,107 @@ fn test_part2_actual() {
     let input_str = std::fs::read_to_string("input/day07.txt").expect("Failed to read day 7 input");
     let numbers = parse_input(&input_str);
     let got = part2(&numbers);
-    assert_eq!(95167302, got);
+    assert_eq!(Ok(95167302), got);
+}
+
+#[test]
+fn test_median_odd_length() {
+    // The median of 5 crabs is the 3rd one
+    let nums: Vec<usize> = vec![0, 1, 2, 10, 100];
+    assert_eq!(
+        Alignment {
+            position: 2,
+            fuel: 2 + 1 + 8 + 98
+        },
+        align(&nums, &CostModel::Linear).unwrap()
+    );
+}
+
+#[test]
+fn test_align() {
+    let nums: Vec<usize> = vec![0, 1, 1, 2, 2, 2, 4, 7, 14, 16];
+    assert_eq!(
+        Alignment {
+            position: 2,
+            fuel: 37
+        },
+        align(&nums, &CostModel::Linear).unwrap()
+    );
+    assert_eq!(
+        Alignment {
+            position: 5,
+            fuel: 168
+        },
+        align(&nums, &CostModel::Triangular).unwrap()
+    );
+    assert_eq!(
+        Alignment {
+            position: 5,
+            fuel: 291
+        },
+        align(&nums, &CostModel::Quadratic).unwrap()
+    );
+}
+
+#[test]
+fn test_align_matches_brute_force() {
+    let input_str = std::fs::read_to_string("input/day07.txt").expect("Failed to read day 7 input");
+    let numbers = parse_input(&input_str);
+
+    let models = [
+        CostModel::Linear,
+        CostModel::Triangular,
+        CostModel::Quadratic,
+        CostModel::Custom(Box::new(|n| n * n * n)),
+        // Flat for short distances, so there are ties
+        CostModel::Custom(Box::new(|n| n.saturating_sub(500))),
+    ];
+    for nums in [
+        numbers,
+        vec![0, 1, 1, 2, 2, 2, 4, 7, 14, 16],
+        vec![3],
+        vec![0, 1, 2, 10, 100],
+    ] {
+        for model in &models {
+            let expected = brute_force(&nums, model);
+            let got = align(&nums, model).unwrap();
+            assert_eq!(expected.fuel, got.fuel);
+            assert_eq!(got.fuel, cost_at_point(&nums, got.position, model));
+        }
+    }
+}
+
+#[test]
+fn test_align_custom_not_convex() {
+    // Moving at all costs 1, so the total is 2 at 0, 3 between, and 1 at 10. Stopping at the
+    // first position where a step doesn't help would give 0.
+    let nums: Vec<usize> = vec![0, 10, 10];
+    let model = CostModel::Custom(Box::new(|n| usize::from(n >= 1)));
+    assert_eq!(
+        Ok(Alignment {
+            position: 10,
+            fuel: 1
+        }),
+        align(&nums, &model)
+    );
+}
+
+#[test]
+fn test_align_no_crabs() {
+    assert!(align(&[], &CostModel::Linear).is_err());
+    assert!(part2(&[]).is_err());
+}
+
+#[test]
+fn test_examples() {
+    for check in puzzle::check_examples(EXAMPLES, PARTS) {
+        assert!(check.is_ok(), "{}", check);
+    }
+}
+
+#[test]
+fn test_crosschecks() {
+    for check in CROSSCHECKS {
+        assert_eq!(Ok(None), check.find_disagreement(7, 20, Some(50)));
+    }
 }
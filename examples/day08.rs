@@ -1,6 +1,6 @@
-use std::collections::{HashMap, HashSet};
+use aoc_2021_rs::puzzle::{self, Example};
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 enum Signal {
     A,
     B,
@@ -11,17 +11,19 @@ enum Signal {
     G,
 }
 
-impl From<char> for Signal {
-    fn from(c: char) -> Self {
+impl TryFrom<char> for Signal {
+    type Error = String;
+
+    fn try_from(c: char) -> Result<Self, Self::Error> {
         match c {
-            'a' => Signal::A,
-            'b' => Signal::B,
-            'c' => Signal::C,
-            'd' => Signal::D,
-            'e' => Signal::E,
-            'f' => Signal::F,
-            'g' => Signal::G,
-            _ => panic!("Input signal was not in a-g"),
+            'a' => Ok(Signal::A),
+            'b' => Ok(Signal::B),
+            'c' => Ok(Signal::C),
+            'd' => Ok(Signal::D),
+            'e' => Ok(Signal::E),
+            'f' => Ok(Signal::F),
+            'g' => Ok(Signal::G),
+            _ => Err(format!("Input signal {:?} was not in a-g", c)),
         }
     }
 }
@@ -35,7 +37,7 @@ fn parse_input_line(input_line: &str) -> (Vec<&str>, Vec<&str>) {
 }
 
 fn gather_patterns(input: &str) -> Vec<&str> {
-    input.trim().split_whitespace().collect()
+    input.split_whitespace().collect()
 }
 
 fn part1(input: &[(Vec<&str>, Vec<&str>)]) -> usize {
@@ -51,115 +53,210 @@ fn part1(input: &[(Vec<&str>, Vec<&str>)]) -> usize {
         .count()
 }
 
-fn get_output_numers(input: &(Vec<&str>, Vec<&str>)) -> usize {
-    // First get just the input signals
-    let in_signal = &input.0;
-    let out_signal = &input.1;
-
-    // Convert from Vec<&str> to Vec<HashSet<char>>
-    let char_sets: Vec<HashSet<char>> = in_signal.iter().map(|&s| s.chars().collect()).collect();
-    let out_char_set: Vec<HashSet<char>> =
-        out_signal.iter().map(|&s| s.chars().collect()).collect();
+/// The segments lit up for each digit, with segment `a` as bit 0 up to `g` as bit 6
+const DIGITS: [u8; 10] = [
+    0b1110111, // 0: abcefg
+    0b0100100, // 1: cf
+    0b1011101, // 2: acdeg
+    0b1101101, // 3: acdfg
+    0b0101110, // 4: bcdf
+    0b1101011, // 5: abdfg
+    0b1111011, // 6: abdefg
+    0b0100101, // 7: acf
+    0b1111111, // 8: abcdefg
+    0b1101111, // 9: abcdfg
+];
+
+const ALL_SEGMENTS: u8 = 0b1111111;
+
+/// Convert a pattern like "cfbegad" to a mask of its wires
+fn pattern_mask(pattern: &str) -> Result<u8, String> {
+    pattern.chars().try_fold(0, |mask, c| {
+        let signal = Signal::try_from(c)?;
+        Ok(mask | 1 << signal as u8)
+    })
+}
 
-    // Create a HashMap to put everything in. Use the index of the HashSet as the key
-    let mut map: HashMap<usize, char> = HashMap::new();
+/// Which segment each wire (`a` to `g`) is connected to
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+struct Wiring([u8; 7]);
 
-    // Populate the HashMap
-    // Get 1
-    let one_idx = char_sets
-        .iter()
-        .enumerate()
-        .find(|(_, s)| s.len() == 2)
-        .expect("Could not find the set representing 1")
-        .0;
-    map.insert(one_idx, '1');
+impl Wiring {
+    /// Convert a mask of wires to a mask of the segments they light up
+    fn segments(&self, wires: u8) -> u8 {
+        (0..7)
+            .filter(|wire| wires & (1 << wire) != 0)
+            .fold(0, |segments, wire| segments | 1 << self.0[wire])
+    }
 
-    // Get the 4
-    let four_idx = char_sets
-        .iter()
-        .enumerate()
-        .find(|(_, s)| s.len() == 4)
-        .expect("Could not find the set representing 4")
-        .0;
-    map.insert(four_idx, '4');
+    fn digit(&self, wires: u8) -> Option<usize> {
+        let segments = self.segments(wires);
+        DIGITS.iter().position(|&digit| digit == segments)
+    }
+}
 
-    // Get the 7
-    let seven_idx = char_sets
-        .iter()
-        .enumerate()
-        .find(|(_, s)| s.len() == 3)
-        .expect("Could not find the set representing 7")
-        .0;
-    map.insert(seven_idx, '7');
+impl std::fmt::Display for Wiring {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let letter = |idx: u8| (b'a' + idx) as char;
+        let pairs: Vec<String> = (0..7)
+            .map(|wire| format!("{}->{}", letter(wire), letter(self.0[wire as usize])))
+            .collect();
+        write!(f, "{}", pairs.join(" "))
+    }
+}
 
-    // Get the 8
-    let eight_idx = char_sets
-        .iter()
-        .enumerate()
-        .find(|(_, s)| s.len() == 7)
-        .expect("Could not find the set representing 8")
-        .0;
-    map.insert(eight_idx, '8');
-
-    // What are the HashSets for 1 and 4?
-    let one_char_set = &char_sets[one_idx];
-    let four_char_set = &char_sets[four_idx];
-
-    // Now match on the remaining items to add them to the HashMap
-    char_sets.iter().enumerate().for_each(|(idx, s)| {
-        let one_intersection_size = s.intersection(one_char_set).count();
-        let four_intersection_size = s.intersection(four_char_set).count();
-        if s.len() == 5 {
-            if one_intersection_size == 2 {
-                map.insert(idx, '3');
-            } else if four_intersection_size == 3 {
-                map.insert(idx, '5');
-            } else {
-                map.insert(idx, '2');
-            }
-        } else if s.len() == 6 {
-            if one_intersection_size == 1 {
-                map.insert(idx, '6');
-            } else if four_intersection_size == 4 {
-                map.insert(idx, '9');
+/// Find the only wiring that turns every one of `patterns` into a digit.
+///
+/// Each wire starts out able to go to any segment. Each pattern then narrows that down: a wire
+/// in a pattern with n wires must go to a segment in one of the digits with n segments, and a
+/// wire that is not in it must go to a segment missing from one of those digits. The wirings
+/// left are then searched for ones that turn every pattern into a digit.
+fn solve_wiring(patterns: &[u8]) -> Result<Wiring, String> {
+    let mut candidates = [ALL_SEGMENTS; 7];
+    for &pattern in patterns {
+        let same_size = DIGITS
+            .iter()
+            .filter(|digit| digit.count_ones() == pattern.count_ones());
+        let lit = same_size.clone().fold(0, |lit, digit| lit | digit);
+        let unlit = same_size.fold(0, |unlit, digit| unlit | (!digit & ALL_SEGMENTS));
+
+        for (wire, candidate) in candidates.iter_mut().enumerate() {
+            *candidate &= if pattern & (1 << wire) != 0 {
+                lit
             } else {
-                map.insert(idx, '0');
-            }
+                unlit
+            };
         }
-    });
+    }
+
+    let mut solutions: Vec<Wiring> = Vec::new();
+    let mut wiring = Wiring([0; 7]);
+    search_wirings(patterns, &candidates, 0, 0, &mut wiring, &mut solutions);
+
+    match solutions.len() {
+        0 => Err("No wiring turns every pattern into a digit".to_string()),
+        1 => Ok(solutions[0]),
+        _ => Err("The patterns fit more than one wiring".to_string()),
+    }
+}
 
-    // Use it to get the output number
-    let number_as_chars: String = out_char_set
+/// Try every way of connecting the wires from `wire` onwards, adding any wiring that works to
+/// `solutions`. Stops once there are two, as that is enough to know it is ambiguous.
+fn search_wirings(
+    patterns: &[u8],
+    candidates: &[u8; 7],
+    wire: usize,
+    used: u8,
+    wiring: &mut Wiring,
+    solutions: &mut Vec<Wiring>,
+) {
+    if solutions.len() > 1 {
+        return;
+    }
+    if wire == 7 {
+        if patterns.iter().all(|&p| wiring.digit(p).is_some()) {
+            solutions.push(*wiring);
+        }
+        return;
+    }
+
+    for segment in 0..7 {
+        let bit = 1 << segment;
+        if candidates[wire] & bit != 0 && used & bit == 0 {
+            wiring.0[wire] = segment;
+            search_wirings(
+                patterns,
+                candidates,
+                wire + 1,
+                used | bit,
+                wiring,
+                solutions,
+            );
+        }
+    }
+}
+
+/// Work out the wiring for a line, and use it to read the output number
+fn decode_line(input: &(Vec<&str>, Vec<&str>)) -> Result<(Wiring, usize), String> {
+    let signals = input
+        .0
         .iter()
-        // Find the index of this char set in `char_sets`
-        .map(|searching_for| {
-            char_sets
-                .iter()
-                .enumerate()
-                .find(|(_, item)| searching_for.clone() == (*item).clone())
-                .expect("Could not find a matching char set")
-        })
-        // Get the char for that index
-        .map(|(idx, _)| map.get(&idx).expect("Could not get number from map"))
-        .map(|c| *c)
-        .collect();
+        .map(|s| pattern_mask(s))
+        .collect::<Result<Vec<u8>, String>>()?;
+    let outputs = input
+        .1
+        .iter()
+        .map(|s| pattern_mask(s))
+        .collect::<Result<Vec<u8>, String>>()?;
 
-    number_as_chars
-        .parse()
-        .expect("Could not convert final string to a number")
+    let all_patterns: Vec<u8> = signals.iter().chain(outputs.iter()).copied().collect();
+    let wiring = solve_wiring(&all_patterns)?;
+
+    let number = outputs.iter().fold(0, |number, &output| {
+        // Every pattern was checked to be a digit when the wiring was found
+        number * 10 + wiring.digit(output).unwrap()
+    });
+
+    Ok((wiring, number))
 }
 
-fn part2(input: &[(Vec<&str>, Vec<&str>)]) -> usize {
-    // For each line of the input
+fn part2(input: &[(Vec<&str>, Vec<&str>)]) -> Result<usize, String> {
+    // For each line of the input, get the output numbers, and sum them up
     input
         .iter()
-        // Get the output numbers
-        .map(get_output_numers)
-        // Sum them up
+        .enumerate()
+        .map(|(line_idx, line)| {
+            decode_line(line)
+                .map(|(_, number)| number)
+                .map_err(|e| format!("Line {}: {}", line_idx + 1, e))
+        })
         .sum()
 }
 
+/// The examples from the puzzle description
+const EXAMPLES: &[Example] = &[
+    Example {
+        input:
+            "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdbaf",
+        part1: None,
+        part2: Some("5353"),
+    },
+    Example {
+        input:
+            "be cfbegad cbdgef fgaecd cgeb fdcge agebfd fecdb fabcd edb | fdgacbe cefdb cefbgd gcbe
+edbfga begcd cbg gc gcadebf fbgde acbgfd abcde gfcbed gfec | fcgedb cgb dgebacf gc
+fgaebd cg bdaec gdafb agbcfd gdcbef bgcad gfac gcb cdgabef | cg cg fdcagb cbg
+fbegcd cbd adcefb dageb afcb bc aefdc ecdab fgdeca fcdbega | efabcd cedba gadfec cb
+aecbfdg fbg gf bafeg dbefa fcge gcbea fcaegb dgceab fcbdga | gecf egdcabf bgf bfgea
+fgeab ca afcebg bdacfeg cfaedg gcfdb baec bfadeg bafgc acf | gebdcfa ecba ca fadegcb
+dbcfg fgd bdegcaf fgec aegbdf ecdfab fbedc dacgb gdcebf gf | cefg dcbef fcge gbcadfe
+bdfegc cbegaf gecbf dfcage bdacg ed bedf ced adcbefg gebcd | ed bcgafe cdgba cbgef
+egadfb cdbfeg cegd fecab cgb gbdefca cg fgcdab egfdb bfceg | gbdfcae bgc cg cgb
+gcafb gcf dcaebfg ecagb gf abcdeg gaef cafbge fdbac fegbdc | fgae cfgab fg bagce",
+        part1: Some("26"),
+        part2: Some("61229"),
+    },
+];
+
+/// Each part, solved straight from the input
+const PARTS: [Option<puzzle::Part>; 2] = [Some(solve_part1), Some(solve_part2)];
+
+fn solve_part1(input: &str) -> Result<String, String> {
+    let input: Vec<(Vec<&str>, Vec<&str>)> = input.lines().map(parse_input_line).collect();
+    Ok(part1(&input).to_string())
+}
+
+fn solve_part2(input: &str) -> Result<String, String> {
+    let input: Vec<(Vec<&str>, Vec<&str>)> = input.lines().map(parse_input_line).collect();
+    part2(&input).map(|sum| sum.to_string())
+}
+
 fn main() {
+    // Pass `--example` to check the solutions against the puzzle's examples instead
+    if std::env::args().any(|arg| arg == "--example") {
+        std::process::exit(puzzle::run_examples(EXAMPLES, PARTS));
+    }
+
     let setup_time = std::time::Instant::now();
 
     let input_str = std::fs::read_to_string("input/day08.txt").expect("Failed to read day 8 input");
@@ -178,7 +275,21 @@ fn main() {
 
     println!();
     println!("Part 1 result: {}", part1_result);
-    println!("Part 2 result: {}", part2_result);
+    match part2_result {
+        Ok(result) => println!("Part 2 result: {}", result),
+        Err(e) => println!("Part 2 failed: {}", e),
+    }
+
+    // Pass `--wiring` to show the wiring found for each line
+    if std::env::args().any(|arg| arg == "--wiring") {
+        println!();
+        for (line_idx, line) in numbers.iter().enumerate() {
+            match decode_line(line) {
+                Ok((wiring, number)) => println!("{}: {} = {}", line_idx + 1, wiring, number),
+                Err(e) => println!("{}: {}", line_idx + 1, e),
+            }
+        }
+    }
 }
 
 #[test]
@@ -233,7 +344,7 @@ fn test_part2_1() {
     let input: Vec<(Vec<&str>, Vec<&str>)> = input_str.lines().map(parse_input_line).collect();
 
     let got = part2(&input);
-    assert_eq!(5353, got);
+    assert_eq!(Ok(5353), got);
 }
 
 #[test]
@@ -252,7 +363,7 @@ gcafb gcf dcaebfg ecagb gf abcdeg gaef cafbge fdbac fegbdc | fgae cfgab fg bagce
     let input: Vec<(Vec<&str>, Vec<&str>)> = input_str.lines().map(parse_input_line).collect();
 
     let got = part2(&input);
-    assert_eq!(61229, got);
+    assert_eq!(Ok(61229), got);
 }
 
 #[test]
@@ -262,7 +373,7 @@ fn test_part2_2() {
     let input: Vec<(Vec<&str>, Vec<&str>)> = input_str.lines().map(parse_input_line).collect();
 
     let got = part2(&input);
-    assert_eq!(8394, got);
+    assert_eq!(Ok(8394), got);
 }
 
 #[test]
@@ -272,7 +383,7 @@ fn test_part2_3() {
     let input: Vec<(Vec<&str>, Vec<&str>)> = input_str.lines().map(parse_input_line).collect();
 
     let got = part2(&input);
-    assert_eq!(9781, got);
+    assert_eq!(Ok(9781), got);
 }
 
 #[test]
@@ -281,7 +392,7 @@ fn test_part2_4() {
     let input: Vec<(Vec<&str>, Vec<&str>)> = input_str.lines().map(parse_input_line).collect();
 
     let got = part2(&input);
-    assert_eq!(1197, got);
+    assert_eq!(Ok(1197), got);
 }
 
 #[test]
@@ -291,7 +402,7 @@ fn test_part2_5() {
     let input: Vec<(Vec<&str>, Vec<&str>)> = input_str.lines().map(parse_input_line).collect();
 
     let got = part2(&input);
-    assert_eq!(9361, got);
+    assert_eq!(Ok(9361), got);
 }
 
 #[test]
@@ -301,7 +412,7 @@ fn test_part2_6() {
     let input: Vec<(Vec<&str>, Vec<&str>)> = input_str.lines().map(parse_input_line).collect();
 
     let got = part2(&input);
-    assert_eq!(4873, got);
+    assert_eq!(Ok(4873), got);
 }
 
 #[test]
@@ -311,7 +422,7 @@ fn test_part2_7() {
     let input: Vec<(Vec<&str>, Vec<&str>)> = input_str.lines().map(parse_input_line).collect();
 
     let got = part2(&input);
-    assert_eq!(8418, got);
+    assert_eq!(Ok(8418), got);
 }
 
 #[test]
@@ -321,7 +432,7 @@ fn test_part2_8() {
     let input: Vec<(Vec<&str>, Vec<&str>)> = input_str.lines().map(parse_input_line).collect();
 
     let got = part2(&input);
-    assert_eq!(4548, got);
+    assert_eq!(Ok(4548), got);
 }
 
 #[test]
@@ -331,7 +442,7 @@ fn test_part2_9() {
     let input: Vec<(Vec<&str>, Vec<&str>)> = input_str.lines().map(parse_input_line).collect();
 
     let got = part2(&input);
-    assert_eq!(1625, got);
+    assert_eq!(Ok(1625), got);
 }
 
 #[test]
@@ -341,7 +452,7 @@ fn test_part2_10() {
     let input: Vec<(Vec<&str>, Vec<&str>)> = input_str.lines().map(parse_input_line).collect();
 
     let got = part2(&input);
-    assert_eq!(8717, got);
+    assert_eq!(Ok(8717), got);
 }
 
 #[test]
@@ -351,7 +462,7 @@ fn test_part2_11() {
     let input: Vec<(Vec<&str>, Vec<&str>)> = input_str.lines().map(parse_input_line).collect();
 
     let got = part2(&input);
-    assert_eq!(4315, got);
+    assert_eq!(Ok(4315), got);
 }
 
 #[test]
@@ -359,5 +470,50 @@ fn test_part2_actual() {
     let input_str = std::fs::read_to_string("input/day08.txt").expect("Failed to read day 8 input");
     let input: Vec<(Vec<&str>, Vec<&str>)> = input_str.lines().map(parse_input_line).collect();
     let got = part2(&input);
-    assert_eq!(983030, got);
+    assert_eq!(Ok(983030), got);
+}
+
+#[test]
+fn test_decode_line_wiring() {
+    let input_str =
+        "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdbaf";
+    let (wiring, number) = decode_line(&parse_input_line(input_str)).unwrap();
+
+    // The wiring from the puzzle description, where wire d goes to segment a and so on
+    assert_eq!("a->c b->f c->g d->a e->b f->d g->e", wiring.to_string());
+    assert_eq!(5353, number);
+}
+
+#[test]
+fn test_decode_line_errors() {
+    // Two different patterns can't both be a 7
+    assert_eq!(
+        Err("No wiring turns every pattern into a digit".to_string()),
+        decode_line(&parse_input_line("abc abd | abc"))
+    );
+    // Knowing where the 1 is doesn't say which way round it goes
+    assert_eq!(
+        Err("The patterns fit more than one wiring".to_string()),
+        decode_line(&parse_input_line("ab abcdefg | ab"))
+    );
+    assert_eq!(
+        Err("Input signal 'h' was not in a-g".to_string()),
+        decode_line(&parse_input_line("abh | ab"))
+    );
+
+    let input: Vec<(Vec<&str>, Vec<&str>)> = vec![
+        parse_input_line("be cfbegad cbdgef fgaecd cgeb fdcge agebfd fecdb fabcd edb | fdgacbe cefdb cefbgd gcbe"),
+        parse_input_line("abc abd | abc"),
+    ];
+    assert_eq!(
+        Err("Line 2: No wiring turns every pattern into a digit".to_string()),
+        part2(&input)
+    );
+}
+
+#[test]
+fn test_examples() {
+    for check in puzzle::check_examples(EXAMPLES, PARTS) {
+        assert!(check.is_ok(), "{}", check);
+    }
 }
@@ -1,4 +1,8 @@
-use ndarray::{arr2, Array2, ArrayView2, ArrayViewMut2};
+use aoc_2021_rs::puzzle::{self, Example};
+#[cfg(test)]
+use ndarray::arr2;
+use ndarray::{Array2, ArrayView2};
+use std::collections::{HashSet, VecDeque};
 
 fn parse_input(input: &str) -> Array2<u8> {
     let nrows = input.lines().count();
@@ -61,47 +65,163 @@ fn part1(arr: ArrayView2<u8>) -> usize {
         .sum()
 }
 
-fn get_basin_size(arr: &mut ArrayViewMut2<u8>, row_idx: usize, col_idx: usize) -> usize {
-    // Get the height at the current location
-    let height = arr[[row_idx, col_idx]];
-    // Set this position as visited
-    arr[[row_idx, col_idx]] = u8::MAX;
+/// A basin, with its bounding box given by the (row, column) of its top left and bottom
+/// right cells
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Basin {
+    low_point: (usize, usize),
+    size: usize,
+    bounding_box: ((usize, usize), (usize, usize)),
+}
 
-    // Visit adjacent points
-    get_neighbors(arr.view(), row_idx, col_idx)
-        .iter()
-        .filter_map(|(new_row_idx, new_col_idx)| {
-            let adjacent_height = arr[[*new_row_idx, *new_col_idx]];
-            if (adjacent_height > height) && adjacent_height < 9 {
-                Some(get_basin_size(arr, *new_row_idx, *new_col_idx))
-            } else {
-                None
+/// Find the root of `idx`, pointing everything on the way at its grandparent
+fn find_root(parents: &mut [usize], mut idx: usize) -> usize {
+    while parents[idx] != idx {
+        parents[idx] = parents[parents[idx]];
+        idx = parents[idx];
+    }
+    idx
+}
+
+fn union(parents: &mut [usize], a: usize, b: usize) {
+    let (root_a, root_b) = (find_root(parents, a), find_root(parents, b));
+    parents[root_a.max(root_b)] = root_a.min(root_b);
+}
+
+/// Label every cell that isn't a 9 with the basin it flows down into.
+///
+/// Cells of the same height that touch make a plateau, and a single cell is a plateau of its
+/// own. If no cell of a plateau has a lower neighbour, the plateau is the bottom of a basin,
+/// and all of it is in that basin. Otherwise the cells with a lower neighbour are its exits,
+/// and each flows to its lowest neighbour, taking the first of any that are tied. Every other
+/// cell of the plateau flows across it to the nearest exit, so that basins that touch without
+/// a 9 between them stay apart. Basins are numbered in the order their first cell is found,
+/// scanning along the rows.
+fn label_basins(arr: ArrayView2<u8>) -> (Array2<Option<usize>>, Vec<Basin>) {
+    let ncols = arr.ncols();
+    let flat_idx = |(row_idx, col_idx): (usize, usize)| row_idx * ncols + col_idx;
+    let mut parents: Vec<usize> = (0..arr.len()).collect();
+    let mut visited = Array2::from_elem(arr.dim(), false);
+
+    for ((row_idx, col_idx), &height) in arr.indexed_iter() {
+        if height >= 9 || visited[(row_idx, col_idx)] {
+            continue;
+        }
+
+        // Find the whole plateau, and which of its cells are exits
+        let mut plateau = vec![(row_idx, col_idx)];
+        visited[(row_idx, col_idx)] = true;
+        let mut exits: VecDeque<(usize, usize)> = VecDeque::new();
+        let mut next = 0;
+        while let Some(&cell) = plateau.get(next) {
+            next += 1;
+            let neighbors = get_neighbors(arr, cell.0, cell.1);
+            let lowest = neighbors
+                .iter()
+                .copied()
+                .min_by_key(|&nbr| arr[nbr])
+                .filter(|&nbr| arr[nbr] < height);
+            if let Some(nbr) = lowest {
+                union(&mut parents, flat_idx(cell), flat_idx(nbr));
+                exits.push_back(cell);
             }
-        })
-        .sum::<usize>()
-        + 1
+            for nbr in neighbors {
+                if arr[nbr] == height && !visited[nbr] {
+                    visited[nbr] = true;
+                    plateau.push(nbr);
+                }
+            }
+        }
+
+        if exits.is_empty() {
+            for &cell in &plateau[1..] {
+                union(&mut parents, flat_idx(plateau[0]), flat_idx(cell));
+            }
+            continue;
+        }
+
+        // Spread out from the exits together, so each cell follows whichever reaches it first
+        let mut reached: HashSet<(usize, usize)> = exits.iter().copied().collect();
+        while let Some(cell) = exits.pop_front() {
+            for nbr in get_neighbors(arr, cell.0, cell.1) {
+                if arr[nbr] == height && reached.insert(nbr) {
+                    union(&mut parents, flat_idx(cell), flat_idx(nbr));
+                    exits.push_back(nbr);
+                }
+            }
+        }
+    }
+
+    let mut labels = Array2::<Option<usize>>::from_elem(arr.dim(), None);
+    let mut root_labels: Vec<Option<usize>> = vec![None; arr.len()];
+    let mut basins: Vec<Basin> = Vec::new();
+
+    for ((row_idx, col_idx), &height) in arr.indexed_iter() {
+        if height >= 9 {
+            continue;
+        }
+        let root = find_root(&mut parents, flat_idx((row_idx, col_idx)));
+        let label = *root_labels[root].get_or_insert_with(|| {
+            basins.push(Basin {
+                low_point: (row_idx, col_idx),
+                size: 0,
+                bounding_box: ((row_idx, col_idx), (row_idx, col_idx)),
+            });
+            basins.len() - 1
+        });
+        labels[(row_idx, col_idx)] = Some(label);
+
+        let basin = &mut basins[label];
+        basin.size += 1;
+        if height < arr[basin.low_point] {
+            basin.low_point = (row_idx, col_idx);
+        }
+        let ((min_row, min_col), (max_row, max_col)) = &mut basin.bounding_box;
+        *min_row = (*min_row).min(row_idx);
+        *min_col = (*min_col).min(col_idx);
+        *max_row = (*max_row).max(row_idx);
+        *max_col = (*max_col).max(col_idx);
+    }
+
+    (labels, basins)
 }
 
 fn part2(arr: ArrayView2<u8>) -> usize {
-    // Create a mutable copy of the array
-    let mut basin_array = arr.to_owned();
+    let (_, basins) = label_basins(arr);
+    let mut sizes: Vec<usize> = basins.iter().map(|basin| basin.size).collect();
 
-    // For each location
-    let mut basins: Vec<usize> = arr
-        .indexed_iter()
-        // Find the lowest points
-        .filter(|((row_idx, col_idx), _)| is_lowest_of_neighbors(arr, *row_idx, *col_idx))
-        // Get the basin size
-        .map(|((row_idx, col_idx), _)| {
-            get_basin_size(&mut basin_array.view_mut(), row_idx, col_idx)
-        })
-        .collect();
+    sizes.sort_unstable();
+    sizes.iter().rev().take(3).product()
+}
 
-    basins.sort_unstable();
-    basins.iter().rev().take(3).product()
+/// The examples from the puzzle description
+const EXAMPLES: &[Example] = &[Example {
+    input: "2199943210
+3987894921
+9856789892
+8767896789
+9899965678",
+    part1: Some("15"),
+    part2: Some("1134"),
+}];
+
+/// Each part, solved straight from the input
+const PARTS: [Option<puzzle::Part>; 2] = [Some(solve_part1), Some(solve_part2)];
+
+fn solve_part1(input: &str) -> Result<String, String> {
+    Ok(part1(parse_input(input).view()).to_string())
+}
+
+fn solve_part2(input: &str) -> Result<String, String> {
+    Ok(part2(parse_input(input).view()).to_string())
 }
 
 fn main() {
+    // Pass `--example` to check the solutions against the puzzle's examples instead
+    if std::env::args().any(|arg| arg == "--example") {
+        std::process::exit(puzzle::run_examples(EXAMPLES, PARTS));
+    }
+
     let setup_time = std::time::Instant::now();
 
     let input_str = std::fs::read_to_string("input/day09.txt").expect("Failed to read day 9 input");
@@ -121,6 +241,26 @@ fn main() {
     println!();
     println!("Part 1 result: {}", part1_result);
     println!("Part 2 result: {}", part2_result);
+
+    // Pass `--basins` to list every basin
+    if std::env::args().any(|arg| arg == "--basins") {
+        println!();
+        println!("low_row,low_col,size,min_row,min_col,max_row,max_col");
+        let (_, basins) = label_basins(arr.view());
+        for basin in basins {
+            let ((min_row, min_col), (max_row, max_col)) = basin.bounding_box;
+            println!(
+                "{},{},{},{},{},{},{}",
+                basin.low_point.0,
+                basin.low_point.1,
+                basin.size,
+                min_row,
+                min_col,
+                max_row,
+                max_col
+            );
+        }
+    }
 }
 
 #[test]
@@ -138,7 +278,7 @@ fn test_parse_input() {
         [9, 8, 9, 9, 9, 6, 5, 6, 7, 8],
     ]);
 
-    let got = parse_input(&input_str);
+    let got = parse_input(input_str);
     assert_eq!(expected, got);
 }
 
@@ -201,3 +341,100 @@ fn test_part2_actual() {
     let got = part2(arr.view());
     assert_eq!(1023660, got);
 }
+
+#[test]
+fn test_label_basins() {
+    let arr: Array2<u8> = arr2(&[
+        [2, 1, 9, 9, 9, 4, 3, 2, 1, 0],
+        [3, 9, 8, 7, 8, 9, 4, 9, 2, 1],
+        [9, 8, 5, 6, 7, 8, 9, 8, 9, 2],
+        [8, 7, 6, 7, 8, 9, 6, 7, 8, 9],
+        [9, 8, 9, 9, 9, 6, 5, 6, 7, 8],
+    ]);
+    let (labels, basins) = label_basins(arr.view());
+
+    let expected = vec![
+        Basin {
+            low_point: (0, 1),
+            size: 3,
+            bounding_box: ((0, 0), (1, 1)),
+        },
+        Basin {
+            low_point: (0, 9),
+            size: 9,
+            bounding_box: ((0, 5), (2, 9)),
+        },
+        Basin {
+            low_point: (2, 2),
+            size: 14,
+            bounding_box: ((1, 0), (4, 5)),
+        },
+        Basin {
+            low_point: (4, 6),
+            size: 9,
+            bounding_box: ((2, 5), (4, 9)),
+        },
+    ];
+    assert_eq!(expected, basins);
+
+    // Only the 9s are left out
+    assert_eq!(
+        arr.iter().filter(|&&h| h == 9).count(),
+        labels.iter().filter(|l| l.is_none()).count()
+    );
+    assert_eq!(Some(0), labels[(1, 0)]);
+    assert_eq!(Some(2), labels[(4, 1)]);
+    assert_eq!(Some(1), labels[(1, 6)]);
+    assert_eq!(Some(3), labels[(3, 6)]);
+}
+
+#[test]
+fn test_label_basins_touching() {
+    // The two basins meet at the 2, which flows into the first one, and the flat bottom of
+    // the last one is all a single basin
+    let arr: Array2<u8> = arr2(&[[0, 1, 2, 1, 0, 9, 3, 1, 1, 3]]);
+    let (labels, basins) = label_basins(arr.view());
+
+    assert_eq!(
+        arr2(&[[
+            Some(0),
+            Some(0),
+            Some(0),
+            Some(1),
+            Some(1),
+            None,
+            Some(2),
+            Some(2),
+            Some(2),
+            Some(2)
+        ]]),
+        labels
+    );
+    let sizes: Vec<usize> = basins.iter().map(|basin| basin.size).collect();
+    assert_eq!(vec![3, 2, 4], sizes);
+    assert_eq!((0, 7), basins[2].low_point);
+}
+
+#[test]
+fn test_label_basins_ridge() {
+    // The ridge of 2s drains both ways, so it mustn't join the two low points. The middle 2 is
+    // as close to either exit, and goes to the first.
+    let arr: Array2<u8> = arr2(&[[0, 2, 2, 2, 0]]);
+    let (labels, basins) = label_basins(arr.view());
+
+    assert_eq!(
+        arr2(&[[Some(0), Some(0), Some(0), Some(1), Some(1)]]),
+        labels
+    );
+    let low_points: Vec<(usize, usize)> = basins.iter().map(|basin| basin.low_point).collect();
+    assert_eq!(vec![(0, 0), (0, 4)], low_points);
+    let sizes: Vec<usize> = basins.iter().map(|basin| basin.size).collect();
+    assert_eq!(vec![3, 2], sizes);
+}
+
+#[test]
+fn test_examples() {
+    for check in puzzle::check_examples(EXAMPLES, PARTS) {
+        assert!(check.is_ok(), "{}", check);
+    }
+}
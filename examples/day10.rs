@@ -1,26 +1,112 @@
+use aoc_2021_rs::puzzle::{self, Example};
+
+/// A pair of brackets, and the scores for their closing bracket
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct BracketPair {
+    open: char,
+    close: char,
+    /// Score when the closer is found where a different one was expected
+    corrupted_score: usize,
+    /// Score for each time the closer is needed to complete a line
+    completion_score: usize,
+}
+
+/// Which brackets can be used, and how lines using them are scored
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Syntax {
+    pairs: Vec<BracketPair>,
+    /// Completion scores are multiplied by this for every closer after the first
+    completion_base: usize,
+}
+
+impl Syntax {
+    fn new(pairs: Vec<BracketPair>, completion_base: usize) -> Result<Syntax, String> {
+        let mut seen: Vec<char> = Vec::new();
+        for c in pairs.iter().flat_map(|pair| [pair.open, pair.close]) {
+            if seen.contains(&c) {
+                return Err(format!("{:?} is used in more than one bracket", c));
+            }
+            seen.push(c);
+        }
+
+        Ok(Syntax {
+            pairs,
+            completion_base,
+        })
+    }
+
+    /// `()[]{}<>`, scored as in the puzzle
+    fn standard() -> Syntax {
+        let pair = |open, close, corrupted_score, completion_score| BracketPair {
+            open,
+            close,
+            corrupted_score,
+            completion_score,
+        };
+        Syntax::new(
+            vec![
+                pair('(', ')', 3, 1),
+                pair('[', ']', 57, 2),
+                pair('{', '}', 1197, 3),
+                pair('<', '>', 25137, 4),
+            ],
+            5,
+        )
+        .unwrap()
+    }
+
+    fn opened_by(&self, c: char) -> Option<&BracketPair> {
+        self.pairs.iter().find(|pair| pair.open == c)
+    }
+
+    fn closed_by(&self, c: char) -> Option<&BracketPair> {
+        self.pairs.iter().find(|pair| pair.close == c)
+    }
+}
+
 #[derive(Debug, PartialEq)]
 enum ParseResult<'a> {
-    Corrupted(char),
+    /// A closer that doesn't match the last opener. Columns start from 1
+    Corrupted {
+        column: usize,
+        found: char,
+        expected: char,
+    },
+    /// A closer with nothing left open for it to close
+    UnexpectedClose {
+        column: usize,
+        found: char,
+    },
+    /// The closers needed to finish the line, in order
     Incomplete(Vec<char>),
     Ok(&'a str),
 }
 
-fn parse_line(s: &str) -> ParseResult {
+/// Check the brackets in a line. Anything that isn't a bracket is skipped over
+fn parse_line<'a>(s: &'a str, syntax: &Syntax) -> ParseResult<'a> {
     let mut stack: Vec<char> = Vec::with_capacity(20);
 
+    // Columns are counted from the start of the line, before it is trimmed
+    let indent = s.chars().take_while(|c| c.is_whitespace()).count();
+
     // Iterate over all the inputs
-    for c in s.trim().chars() {
-        match c {
-            '(' => stack.push(')'),
-            '[' => stack.push(']'),
-            '{' => stack.push('}'),
-            '<' => stack.push('>'),
-            ')' if stack.pop().unwrap() != ')' => return ParseResult::Corrupted(')'),
-            ']' if stack.pop().unwrap() != ']' => return ParseResult::Corrupted(']'),
-            '}' if stack.pop().unwrap() != '}' => return ParseResult::Corrupted('}'),
-            '>' if stack.pop().unwrap() != '>' => return ParseResult::Corrupted('>'),
-            // This represents a good closing bracket
-            _ => (),
+    for (idx, c) in s.trim().chars().enumerate() {
+        let column = indent + idx + 1;
+        if let Some(pair) = syntax.opened_by(c) {
+            stack.push(pair.close);
+        } else if syntax.closed_by(c).is_some() {
+            match stack.pop() {
+                // This represents a good closing bracket
+                Some(expected) if expected == c => (),
+                Some(expected) => {
+                    return ParseResult::Corrupted {
+                        column,
+                        found: c,
+                        expected,
+                    }
+                }
+                None => return ParseResult::UnexpectedClose { column, found: c },
+            }
         }
     }
     if stack.is_empty() {
@@ -31,26 +117,30 @@ fn parse_line(s: &str) -> ParseResult {
     ParseResult::Incomplete(stack)
 }
 
-fn part1(input: &[ParseResult]) -> usize {
+/// Complete a line by adding the closers it is missing. Lines that can't be completed give
+/// back what was wrong with them
+fn repair<'a>(s: &'a str, syntax: &Syntax) -> Result<String, ParseResult<'a>> {
+    match parse_line(s, syntax) {
+        ParseResult::Ok(_) => Ok(s.trim().to_string()),
+        ParseResult::Incomplete(closers) => Ok(s.trim().chars().chain(closers).collect::<String>()),
+        error => Err(error),
+    }
+}
+
+fn part1(input: &[ParseResult], syntax: &Syntax) -> usize {
     input
         .iter()
         // Get only the corrupted input characters
         .filter_map(|r| match r {
-            ParseResult::Corrupted(c) => Some(c),
+            ParseResult::Corrupted { found, .. } => Some(*found),
             _ => None,
         })
         // Get the score for each character
-        .map(|c| match c {
-            ')' => 3,
-            ']' => 57,
-            '}' => 1197,
-            '>' => 25137,
-            _ => unreachable!(),
-        })
+        .map(|c| syntax.closed_by(c).unwrap().corrupted_score)
         .sum()
 }
 
-fn part2(input: &[ParseResult]) -> usize {
+fn part2(input: &[ParseResult], syntax: &Syntax) -> Result<usize, String> {
     let mut completion_scores: Vec<usize> = input
         // Get just the incomplete lines
         .iter()
@@ -60,12 +150,8 @@ fn part2(input: &[ParseResult]) -> usize {
         })
         // For each set of characters, calculate the score
         .map(|v| {
-            v.iter().fold(0_usize, |acc, c| match c {
-                ')' => 1 + (acc * 5),
-                ']' => 2 + (acc * 5),
-                '}' => 3 + (acc * 5),
-                '>' => 4 + (acc * 5),
-                _ => unreachable!(),
+            v.iter().fold(0_usize, |acc, &c| {
+                syntax.closed_by(c).unwrap().completion_score + (acc * syntax.completion_base)
             })
         })
         .collect();
@@ -74,107 +160,201 @@ fn part2(input: &[ParseResult]) -> usize {
     completion_scores.sort_unstable();
 
     // Return the middle value
+    if completion_scores.is_empty() {
+        return Err(String::from("No incomplete lines"));
+    }
     let mid_idx = (completion_scores.len() - 1) / 2;
-    completion_scores[mid_idx]
+    Ok(completion_scores[mid_idx])
+}
+
+/// The examples from the puzzle description
+const EXAMPLES: &[Example] = &[Example {
+    input: "[({(<(())[]>[[{[]{<()<>>
+[(()[<>])]({[<{<<[]>>(
+{([(<{}[<>[]}>{[]{[(<()>
+(((({<>}<{<{<>}{[]{[]{}
+[[<[([]))<([[{}[[()]]]
+[{[{({}]{}}([{[{{{}}([]
+{<[[]]>}<{[{[{[]{()[[[]
+[<(<(<(<{}))><([]([]()
+<{([([[(<>()){}]>(<<{{
+<{([{{}}[<[[[<>{}]]]>[]]",
+    part1: Some("26397"),
+    part2: Some("288957"),
+}];
+
+/// Each part, solved straight from the input
+const PARTS: [Option<puzzle::Part>; 2] = [Some(solve_part1), Some(solve_part2)];
+
+fn parse_lines<'a>(input: &'a str, syntax: &Syntax) -> Vec<ParseResult<'a>> {
+    input.lines().map(|line| parse_line(line, syntax)).collect()
+}
+
+fn solve_part1(input: &str) -> Result<String, String> {
+    let syntax = Syntax::standard();
+    Ok(part1(&parse_lines(input, &syntax), &syntax).to_string())
+}
+
+fn solve_part2(input: &str) -> Result<String, String> {
+    let syntax = Syntax::standard();
+    part2(&parse_lines(input, &syntax), &syntax).map(|score| score.to_string())
 }
 
 fn main() {
+    // Pass `--example` to check the solutions against the puzzle's examples instead
+    if std::env::args().any(|arg| arg == "--example") {
+        std::process::exit(puzzle::run_examples(EXAMPLES, PARTS));
+    }
+
     let setup_time = std::time::Instant::now();
 
     let input_str =
         std::fs::read_to_string("input/day10.txt").expect("Failed to read day 10 input");
-    let input: Vec<ParseResult> = input_str.lines().map(parse_line).collect();
+    let syntax = Syntax::standard();
+    let input: Vec<ParseResult> = input_str
+        .lines()
+        .map(|line| parse_line(line, &syntax))
+        .collect();
     println!("Setup took {:.6} µs", setup_time.elapsed().as_micros());
 
     // Part 1
     let part1_time = std::time::Instant::now();
-    let part1_result = part1(&input);
+    let part1_result = part1(&input, &syntax);
     println!("Part 1 took {:.6} ns", part1_time.elapsed().as_nanos());
 
     // Part 2
     let part2_time = std::time::Instant::now();
-    let part2_result = part2(&input);
+    let part2_result = part2(&input, &syntax).expect("There are no incomplete lines");
     println!("Part 2 took {:.6} µs", part2_time.elapsed().as_micros());
 
     println!();
     println!("Part 1 result: {}", part1_result);
     println!("Part 2 result: {}", part2_result);
+
+    // Pass `--repair` to complete every line that can be, and say what is wrong with the rest
+    if std::env::args().any(|arg| arg == "--repair") {
+        println!();
+        for (line_idx, line) in input_str.lines().enumerate() {
+            match repair(line, &syntax) {
+                Ok(repaired) => println!("{}: {}", line_idx + 1, repaired),
+                Err(ParseResult::Corrupted {
+                    column,
+                    found,
+                    expected,
+                }) => println!(
+                    "{}:{}: expected {}, but found {} instead",
+                    line_idx + 1,
+                    column,
+                    expected,
+                    found
+                ),
+                Err(ParseResult::UnexpectedClose { column, found }) => println!(
+                    "{}:{}: found {} with nothing to close",
+                    line_idx + 1,
+                    column,
+                    found
+                ),
+                Err(_) => unreachable!(),
+            }
+        }
+    }
 }
 
 #[test]
 fn test_valid_1() {
     let input = "()";
-    let got = parse_line(input);
+    let got = parse_line(input, &Syntax::standard());
     assert_eq!(ParseResult::Ok("()"), got);
 }
 
 #[test]
 fn test_valid_2() {
     let input = "([])";
-    let got = parse_line(input);
+    let got = parse_line(input, &Syntax::standard());
     assert_eq!(ParseResult::Ok("([])"), got);
 }
 
 #[test]
 fn test_valid_3() {
     let input = "{()()()}";
-    let got = parse_line(input);
+    let got = parse_line(input, &Syntax::standard());
     assert_eq!(ParseResult::Ok("{()()()}"), got);
 }
 
 #[test]
 fn test_valid_4() {
     let input = "[<>({}){}[([])<>]]";
-    let got = parse_line(input);
+    let got = parse_line(input, &Syntax::standard());
     assert_eq!(ParseResult::Ok("[<>({}){}[([])<>]]"), got);
 }
 
 #[test]
 fn test_corrupted_1() {
     let input = "(]";
-    let got = parse_line(input);
-    assert_eq!(ParseResult::Corrupted(']'), got);
+    let got = parse_line(input, &Syntax::standard());
+    assert_eq!(
+        ParseResult::Corrupted {
+            column: 2,
+            found: ']',
+            expected: ')'
+        },
+        got
+    );
 }
 
 #[test]
 fn test_corrupted_2() {
     let input = "{()()()>";
-    let got = parse_line(input);
-    assert_eq!(ParseResult::Corrupted('>'), got);
+    let got = parse_line(input, &Syntax::standard());
+    assert_eq!(
+        ParseResult::Corrupted {
+            column: 8,
+            found: '>',
+            expected: '}'
+        },
+        got
+    );
 }
 
 #[test]
 fn test_corrupted_3() {
     let input = "(((()))}";
-    let got = parse_line(input);
-    assert_eq!(ParseResult::Corrupted('}'), got);
+    let got = parse_line(input, &Syntax::standard());
+    assert_eq!(
+        ParseResult::Corrupted {
+            column: 8,
+            found: '}',
+            expected: ')'
+        },
+        got
+    );
 }
 
 #[test]
 fn test_incomplete_1() {
     let input = "[({(<(())[]>[[{[]{<()<>>";
-    let got = parse_line(input);
+    let got = parse_line(input, &Syntax::standard());
     assert!(std::matches!(got, ParseResult::Incomplete(_)));
 }
 
 #[test]
 fn test_incomplete_2() {
     let input = "[(()[<>])]({[<{<<[]>>(";
-    let got = parse_line(input);
+    let got = parse_line(input, &Syntax::standard());
     assert!(std::matches!(got, ParseResult::Incomplete(_)));
 }
 
 #[test]
 fn test_incomplete_3() {
     let input = "(((({<>}<{<{<>}{[]{[]{}";
-    let got = parse_line(input);
+    let got = parse_line(input, &Syntax::standard());
     assert!(std::matches!(got, ParseResult::Incomplete(_)));
 }
 
 #[test]
 fn test_incomplete_4() {
     let input = "{<[[]]>}<{[{[{[]{()[[[]";
-    let got = parse_line(input);
+    let got = parse_line(input, &Syntax::standard());
     assert!(std::matches!(got, ParseResult::Incomplete(_)));
 }
 
@@ -190,8 +370,11 @@ fn test_part1() {
 [<(<(<(<{}))><([]([]()
 <{([([[(<>()){}]>(<<{{
 <{([{{}}[<[[[<>{}]]]>[]]";
-    let input: Vec<ParseResult> = input_str.lines().map(parse_line).collect();
-    let got = part1(&input);
+    let input: Vec<ParseResult> = input_str
+        .lines()
+        .map(|line| parse_line(line, &Syntax::standard()))
+        .collect();
+    let got = part1(&input, &Syntax::standard());
     assert_eq!(26397, got);
 }
 
@@ -199,15 +382,18 @@ fn test_part1() {
 fn test_part1_actual() {
     let input_str =
         std::fs::read_to_string("input/day10.txt").expect("Failed to read day 10 input");
-    let input: Vec<ParseResult> = input_str.lines().map(parse_line).collect();
-    let got = part1(&input);
+    let input: Vec<ParseResult> = input_str
+        .lines()
+        .map(|line| parse_line(line, &Syntax::standard()))
+        .collect();
+    let got = part1(&input, &Syntax::standard());
     assert_eq!(319329, got);
 }
 
 #[test]
 fn test_incomplete_stack_1() {
     let input = "[({(<(())[]>[[{[]{<()<>>";
-    let got = parse_line(input);
+    let got = parse_line(input, &Syntax::standard());
     let v: Vec<char> = vec!['}', '}', ']', ']', ')', '}', ')', ']'];
     assert_eq!(ParseResult::Incomplete(v), got);
 }
@@ -215,7 +401,7 @@ fn test_incomplete_stack_1() {
 #[test]
 fn test_incomplete_stack_2() {
     let input = "[(()[<>])]({[<{<<[]>>(";
-    let got = parse_line(input);
+    let got = parse_line(input, &Syntax::standard());
     let v: Vec<char> = vec![')', '}', '>', ']', '}', ')'];
     assert_eq!(ParseResult::Incomplete(v), got);
 }
@@ -223,7 +409,7 @@ fn test_incomplete_stack_2() {
 #[test]
 fn test_incomplete_stack_3() {
     let input = "(((({<>}<{<{<>}{[]{[]{}";
-    let got = parse_line(input);
+    let got = parse_line(input, &Syntax::standard());
     let v: Vec<char> = vec!['}', '}', '>', '}', '>', ')', ')', ')', ')'];
     assert_eq!(ParseResult::Incomplete(v), got);
 }
@@ -231,7 +417,7 @@ fn test_incomplete_stack_3() {
 #[test]
 fn test_incomplete_stack_4() {
     let input = "{<[[]]>}<{[{[{[]{()[[[]";
-    let got = parse_line(input);
+    let got = parse_line(input, &Syntax::standard());
     let v: Vec<char> = vec![']', ']', '}', '}', ']', '}', ']', '}', '>'];
     assert_eq!(ParseResult::Incomplete(v), got);
 }
@@ -239,7 +425,7 @@ fn test_incomplete_stack_4() {
 #[test]
 fn test_incomplete_stack_5() {
     let input = "<{([{{}}[<[[[<>{}]]]>[]]";
-    let got = parse_line(input);
+    let got = parse_line(input, &Syntax::standard());
     let v: Vec<char> = vec![']', ')', '}', '>'];
     assert_eq!(ParseResult::Incomplete(v), got);
 }
@@ -256,16 +442,117 @@ fn test_part2() {
         [<(<(<(<{}))><([]([]()
         <{([([[(<>()){}]>(<<{{
         <{([{{}}[<[[[<>{}]]]>[]]";
-    let input: Vec<ParseResult> = input_str.lines().map(parse_line).collect();
-    let got = part2(&input);
-    assert_eq!(288957, got);
+    let input: Vec<ParseResult> = input_str
+        .lines()
+        .map(|line| parse_line(line, &Syntax::standard()))
+        .collect();
+    let got = part2(&input, &Syntax::standard());
+    assert_eq!(Ok(288957), got);
 }
 
 #[test]
 fn test_part2_actual() {
     let input_str =
         std::fs::read_to_string("input/day10.txt").expect("Failed to read day 10 input");
-    let input: Vec<ParseResult> = input_str.lines().map(parse_line).collect();
-    let got = part2(&input);
-    assert_eq!(3515583998, got);
+    let input: Vec<ParseResult> = input_str
+        .lines()
+        .map(|line| parse_line(line, &Syntax::standard()))
+        .collect();
+    let got = part2(&input, &Syntax::standard());
+    assert_eq!(Ok(3515583998), got);
+}
+
+#[test]
+fn test_part2_no_incomplete_lines() {
+    let syntax = Syntax::standard();
+    let input = parse_lines("[<>({}){}[([])<>]]\n{()()()>", &syntax);
+    assert_eq!(
+        Err(String::from("No incomplete lines")),
+        part2(&input, &syntax)
+    );
+    assert_eq!(
+        Err(String::from("No incomplete lines")),
+        part2(&[], &syntax)
+    );
+    assert!(solve_part2("").is_err());
+}
+
+#[test]
+fn test_unexpected_close() {
+    // Used to panic from popping an empty stack
+    let got = parse_line("  ()]", &Syntax::standard());
+    assert_eq!(
+        ParseResult::UnexpectedClose {
+            column: 5,
+            found: ']'
+        },
+        got
+    );
+}
+
+#[test]
+fn test_repair() {
+    let syntax = Syntax::standard();
+    assert_eq!(
+        Ok("[({(<(())[]>[[{[]{<()<>>}}]])})]".to_string()),
+        repair("[({(<(())[]>[[{[]{<()<>>", &syntax)
+    );
+    assert_eq!(Ok("([])".to_string()), repair("([])", &syntax));
+    assert_eq!(
+        Err(ParseResult::Corrupted {
+            column: 2,
+            found: ']',
+            expected: ')'
+        }),
+        repair("(]", &syntax)
+    );
+}
+
+#[test]
+fn test_custom_syntax() {
+    let pair = |open, close, corrupted_score, completion_score| BracketPair {
+        open,
+        close,
+        corrupted_score,
+        completion_score,
+    };
+    let syntax = Syntax::new(vec![pair('/', '\\', 7, 1), pair('(', ')', 11, 2)], 3).unwrap();
+
+    // Angle brackets are no longer brackets, so are skipped over
+    assert_eq!(ParseResult::Ok("/<(>)\\"), parse_line("/<(>)\\", &syntax));
+
+    let input: Vec<ParseResult> = ["/(\\", "((/", "/(/)"]
+        .iter()
+        .map(|line| parse_line(line, &syntax))
+        .collect();
+    assert_eq!(7 + 11, part1(&input, &syntax));
+    // Only "((/" is incomplete, needing "\\))", scoring (1 * 3 + 2) * 3 + 2
+    assert_eq!(Ok(17), part2(&input, &syntax));
+
+    assert_eq!(
+        Err("'(' is used in more than one bracket".to_string()),
+        Syntax::new(vec![pair('(', ')', 1, 1), pair('(', ']', 1, 1)], 5)
+    );
+}
+
+#[test]
+fn test_examples() {
+    for check in puzzle::check_examples(EXAMPLES, PARTS) {
+        assert!(check.is_ok(), "{}", check);
+    }
+}
+
+#[test]
+fn test_generated_input() {
+    // Every generated line is either corrupted or incomplete
+    let syntax = Syntax::standard();
+    for seed in 0..10 {
+        let input_str = aoc_2021_rs::generate::generate(10, Some(50), seed).unwrap();
+        for line in input_str.lines() {
+            match parse_line(line, &syntax) {
+                ParseResult::Corrupted { .. } | ParseResult::Incomplete(_) => {}
+                other => panic!("{:?} parsed as {:?}", line, other),
+            }
+        }
+    }
 }
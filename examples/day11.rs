@@ -1,14 +1,10 @@
-use ndarray::{arr2, Array2, ArrayView2, ArrayViewMut2};
+use aoc_2021_rs::parsing;
+use aoc_2021_rs::puzzle::{self, Example};
+use ndarray::{Array2, ArrayView2, ArrayViewMut2};
 
 fn parse_input(input: &str) -> Array2<u8> {
-    let mut result = Array2::zeros((10, 10));
-    for (row_idx, row) in input.trim().lines().enumerate() {
-        for (col_idx, value) in row.chars().enumerate() {
-            result[(row_idx, col_idx)] =
-                value.to_digit(10).expect("Could not convert to digit") as u8;
-        }
-    }
-    result
+    let lines = parsing::sections(input).concat();
+    parsing::digit_grid(&lines).expect("Could not parse the energy levels")
 }
 
 fn get_neighbors(nrows: i32, ncols: i32, row_idx: i32, col_idx: i32) -> Vec<(usize, usize)> {
@@ -35,6 +31,13 @@ fn get_neighbors(nrows: i32, ncols: i32, row_idx: i32, col_idx: i32) -> Vec<(usi
         .collect()
 }
 
+/// Pre-compute the neighbors of every cell in a grid of shape `(nrows, ncols)`
+fn build_neighbors(nrows: usize, ncols: usize) -> Array2<Vec<(usize, usize)>> {
+    Array2::from_shape_fn((nrows, ncols), |(row_idx, col_idx)| {
+        get_neighbors(nrows as i32, ncols as i32, row_idx as i32, col_idx as i32)
+    })
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 enum Octopus {
     EnergyLevel(u8),
@@ -46,66 +49,66 @@ fn time_step(
     arr: &mut ArrayViewMut2<Octopus>,
     neighbors_array: ArrayView2<Vec<(usize, usize)>>,
 ) -> usize {
-    // First, the energy level of each octopus increases by 1.
-    arr.mapv_inplace(|octo| match octo {
-        Octopus::EnergyLevel(e) if e < 9 => Octopus::EnergyLevel(e + 1),
-        Octopus::EnergyLevel(_) => Octopus::AboutToFlash,
-        Octopus::AboutToFlash => panic!("Should not have any about to flash yet"),
-        Octopus::AlreadyFlashed => panic!("Should not have any that already flashed"),
-    });
+    // First, the energy level of each octopus increases by 1. Any that go over 9 are put on
+    // the worklist of octopuses about to flash.
+    let mut about_to_flash: Vec<(usize, usize)> = Vec::new();
+    for (idx, octo) in arr.indexed_iter_mut() {
+        *octo = match *octo {
+            Octopus::EnergyLevel(e) if e < 9 => Octopus::EnergyLevel(e + 1),
+            Octopus::EnergyLevel(_) => {
+                about_to_flash.push(idx);
+                Octopus::AboutToFlash
+            }
+            Octopus::AboutToFlash => panic!("Should not have any about to flash yet"),
+            Octopus::AlreadyFlashed => panic!("Should not have any that already flashed"),
+        };
+    }
 
     // Then, any octopus with an energy level greater than 9 flashes. This increases the
     // energy level of all adjacent octopuses by 1, including octopuses that are
     // diagonally adjacent. If this causes an octopus to have an energy level greater
     // than 9, it also flashes. This process continues as long as new octopuses keep
     // having their energy level increased beyond 9. (An octopus can only flash at most once per step.)
-
     let mut count: usize = 0;
 
-    loop {
-        // Find the ones about to flash
-        let about_to_flash: Vec<(usize, usize)> = arr
-            .indexed_iter()
-            .filter(|(_, &octo)| octo == Octopus::AboutToFlash)
-            .map(|((r, c), _)| (r, c))
-            .collect();
-
-        if about_to_flash.is_empty() {
-            // No one else will flash this step. Set everyone who has flashed back to 0
-            arr.mapv_inplace(|octo| match octo {
-                Octopus::EnergyLevel(e) => Octopus::EnergyLevel(e),
-                Octopus::AlreadyFlashed | Octopus::AboutToFlash => Octopus::EnergyLevel(0),
-            });
-
-            // Return the count
-            return count;
-        }
+    while let Some((r, c)) = about_to_flash.pop() {
+        // Flash the octopus in question
+        arr[(r, c)] = Octopus::AlreadyFlashed;
+        count += 1;
 
-        // Increase the count
-        count += about_to_flash.len();
-
-        // Carry out the flashes
-        for (r, c) in &about_to_flash {
-            // Flash the octopus in question
-            arr[(*r, *c)] = Octopus::AlreadyFlashed;
-
-            // Increment its neighbors
-            for (neighbor_r, neighbor_c) in &neighbors_array[(*r, *c)] {
-                match arr[(*neighbor_r, *neighbor_c)] {
-                    Octopus::EnergyLevel(e) if e < 9 => {
-                        arr[(*neighbor_r, *neighbor_c)] = Octopus::EnergyLevel(e + 1);
-                    }
-                    Octopus::EnergyLevel(_) => {
-                        arr[(*neighbor_r, *neighbor_c)] = Octopus::AboutToFlash;
-                    }
-                    // Do nothing if about to flash or already flashed
-                    Octopus::AboutToFlash | Octopus::AlreadyFlashed => (),
+        // Increment its neighbors, and add any that are pushed over 9 to the worklist
+        for &(neighbor_r, neighbor_c) in &neighbors_array[(r, c)] {
+            match arr[(neighbor_r, neighbor_c)] {
+                Octopus::EnergyLevel(e) if e < 9 => {
+                    arr[(neighbor_r, neighbor_c)] = Octopus::EnergyLevel(e + 1);
                 }
+                Octopus::EnergyLevel(_) => {
+                    arr[(neighbor_r, neighbor_c)] = Octopus::AboutToFlash;
+                    about_to_flash.push((neighbor_r, neighbor_c));
+                }
+                // Do nothing if about to flash or already flashed
+                Octopus::AboutToFlash | Octopus::AlreadyFlashed => (),
             }
         }
-
-        // Finally, any octopus that flashed during this step has its energy level set to 0, as it used all of its energy to flash.
     }
+
+    // Finally, any octopus that flashed during this step has its energy level set to 0, as
+    // it used all of its energy to flash.
+    arr.mapv_inplace(|octo| match octo {
+        Octopus::EnergyLevel(e) => Octopus::EnergyLevel(e),
+        Octopus::AlreadyFlashed | Octopus::AboutToFlash => Octopus::EnergyLevel(0),
+    });
+
+    count
+}
+
+/// An endless iterator over the number of flashes in each step, starting from `arr`
+fn flash_counts<'a>(
+    arr: ArrayView2<Octopus>,
+    neighbors_array: ArrayView2<'a, Vec<(usize, usize)>>,
+) -> impl Iterator<Item = usize> + 'a {
+    let mut arr = arr.to_owned();
+    std::iter::from_fn(move || Some(time_step(&mut arr.view_mut(), neighbors_array)))
 }
 
 fn part1(
@@ -113,32 +116,62 @@ fn part1(
     n_steps: usize,
     neighbors_array: ArrayView2<Vec<(usize, usize)>>,
 ) -> usize {
-    let mut arr = arr.to_owned();
-    let mut counter: usize = 0;
-    for _ in 0..n_steps {
-        counter += time_step(&mut arr.view_mut(), neighbors_array);
-    }
+    flash_counts(arr, neighbors_array).take(n_steps).sum()
+}
 
-    counter
+/// Find the first step on which every octopus flashes at once, giving up with `None` if
+/// that has not happened within `max_steps` steps
+fn part2(
+    arr: ArrayView2<Octopus>,
+    neighbors_array: ArrayView2<Vec<(usize, usize)>>,
+    max_steps: usize,
+) -> Option<usize> {
+    let noctopi = arr.len();
+    flash_counts(arr, neighbors_array)
+        .take(max_steps)
+        .position(|counter| counter == noctopi)
+        .map(|idx| idx + 1)
 }
 
-fn part2(arr: ArrayView2<Octopus>, neighbors_array: ArrayView2<Vec<(usize, usize)>>) -> usize {
-    let mut arr = arr.to_owned();
-    const NOCTOPI: usize = 100;
-    for iter in 1.. {
-        let counter = time_step(&mut arr.view_mut(), neighbors_array);
-        if counter == NOCTOPI {
-            return iter;
-        } else if iter > 10_000 {
-            return 0;
-        }
-    }
+/// The examples from the puzzle description
+const EXAMPLES: &[Example] = &[Example {
+    input: "5483143223
+2745854711
+5264556173
+6141336146
+6357385478
+4167524645
+2176841721
+6882881134
+4846848554
+5283751526",
+    part1: Some("1656"),
+    part2: Some("195"),
+}];
+
+/// Each part, solved straight from the input
+const PARTS: [Option<puzzle::Part>; 2] = [Some(solve_part1), Some(solve_part2)];
+
+fn solve_part1(input: &str) -> Result<String, String> {
+    let input = parse_input(input).mapv(Octopus::EnergyLevel);
+    let neighbors_arr = build_neighbors(input.nrows(), input.ncols());
+    Ok(part1(input.view(), 100, neighbors_arr.view()).to_string())
+}
 
-    // Have to put this otherwise compiler freaks out
-    0
+fn solve_part2(input: &str) -> Result<String, String> {
+    let input = parse_input(input).mapv(Octopus::EnergyLevel);
+    let neighbors_arr = build_neighbors(input.nrows(), input.ncols());
+    part2(input.view(), neighbors_arr.view(), 10_000)
+        .map(|step| step.to_string())
+        .ok_or_else(|| "The octopuses never all flashed at once".to_string())
 }
 
 fn main() {
+    // Pass `--example` to check the solutions against the puzzle's examples instead
+    if std::env::args().any(|arg| arg == "--example") {
+        std::process::exit(puzzle::run_examples(EXAMPLES, PARTS));
+    }
+
     let setup_time = std::time::Instant::now();
 
     let input_str =
@@ -146,18 +179,7 @@ fn main() {
     let input = parse_input(&input_str);
     let input = input.mapv(Octopus::EnergyLevel);
 
-    let neighbors_arr: Array2<Vec<(usize, usize)>> = Array2::from_shape_vec(
-        (10, 10),
-        (0..10)
-            .into_iter()
-            .flat_map(|row_idx| {
-                (0..10)
-                    .into_iter()
-                    .map(move |col_idx| get_neighbors(10, 10, row_idx, col_idx))
-            })
-            .collect(),
-    )
-    .expect("Could not create neighbors array");
+    let neighbors_arr = build_neighbors(input.nrows(), input.ncols());
 
     println!("Setup took {:.6} µs", setup_time.elapsed().as_micros());
 
@@ -168,14 +190,20 @@ fn main() {
 
     // Part 2
     let part2_time = std::time::Instant::now();
-    let part2_result = part2(input.view(), neighbors_arr.view());
+    let part2_result = part2(input.view(), neighbors_arr.view(), 10_000);
     println!("Part 2 took {:.6} µs", part2_time.elapsed().as_micros());
 
     println!();
     println!("Part 1 result: {}", part1_result);
-    println!("Part 2 result: {}", part2_result);
+    match part2_result {
+        Some(step) => println!("Part 2 result: {}", step),
+        None => println!("Part 2 result: the octopuses never all flashed at once in 10000 steps"),
+    }
 }
 
+#[cfg(test)]
+use ndarray::arr2;
+
 #[test]
 fn test_parse_input() {
     let input_str = "5483143223
@@ -204,6 +232,10 @@ fn test_parse_input() {
     ]);
 
     assert_eq!(expected, got);
+
+    // Windows line endings and a trailing blank line make no difference
+    let crlf = format!("{}\r\n\r\n", input_str.replace('\n', "\r\n"));
+    assert_eq!(expected, parse_input(&crlf));
 }
 
 #[test]
@@ -220,20 +252,9 @@ fn test_part1_1() {
         [4, 8, 4, 6, 8, 4, 8, 5, 5, 4],
         [5, 2, 8, 3, 7, 5, 1, 5, 2, 6],
     ]);
-    let arr = arr.mapv(|n| Octopus::EnergyLevel(n));
-
-    let neighbors_arr: Array2<Vec<(usize, usize)>> = Array2::from_shape_vec(
-        (10, 10),
-        (0..10)
-            .into_iter()
-            .flat_map(|row_idx| {
-                (0..10)
-                    .into_iter()
-                    .map(move |col_idx| get_neighbors(10, 10, row_idx, col_idx))
-            })
-            .collect(),
-    )
-    .expect("Could not create neighbors array");
+    let arr = arr.mapv(Octopus::EnergyLevel);
+
+    let neighbors_arr = build_neighbors(arr.nrows(), arr.ncols());
 
     let got = part1(arr.view(), 2, neighbors_arr.view());
     assert_eq!(35, got);
@@ -253,20 +274,9 @@ fn test_part1_2() {
         [4, 8, 4, 6, 8, 4, 8, 5, 5, 4],
         [5, 2, 8, 3, 7, 5, 1, 5, 2, 6],
     ]);
-    let arr = arr.mapv(|n| Octopus::EnergyLevel(n));
-
-    let neighbors_arr: Array2<Vec<(usize, usize)>> = Array2::from_shape_vec(
-        (10, 10),
-        (0..10)
-            .into_iter()
-            .flat_map(|row_idx| {
-                (0..10)
-                    .into_iter()
-                    .map(move |col_idx| get_neighbors(10, 10, row_idx, col_idx))
-            })
-            .collect(),
-    )
-    .expect("Could not create neighbors array");
+    let arr = arr.mapv(Octopus::EnergyLevel);
+
+    let neighbors_arr = build_neighbors(arr.nrows(), arr.ncols());
 
     let got = part1(arr.view(), 10, neighbors_arr.view());
     assert_eq!(204, got);
@@ -286,20 +296,9 @@ fn test_part1_3() {
         [4, 8, 4, 6, 8, 4, 8, 5, 5, 4],
         [5, 2, 8, 3, 7, 5, 1, 5, 2, 6],
     ]);
-    let arr = arr.mapv(|n| Octopus::EnergyLevel(n));
-
-    let neighbors_arr: Array2<Vec<(usize, usize)>> = Array2::from_shape_vec(
-        (10, 10),
-        (0..10)
-            .into_iter()
-            .flat_map(|row_idx| {
-                (0..10)
-                    .into_iter()
-                    .map(move |col_idx| get_neighbors(10, 10, row_idx, col_idx))
-            })
-            .collect(),
-    )
-    .expect("Could not create neighbors array");
+    let arr = arr.mapv(Octopus::EnergyLevel);
+
+    let neighbors_arr = build_neighbors(arr.nrows(), arr.ncols());
 
     let got = part1(arr.view(), 100, neighbors_arr.view());
     assert_eq!(1656, got);
@@ -314,19 +313,8 @@ fn test_time_step_1() {
         [1, 9, 9, 9, 1],
         [1, 1, 1, 1, 1],
     ]);
-    let mut arr = arr.mapv(|n| Octopus::EnergyLevel(n));
-    let neighbors_arr: Array2<Vec<(usize, usize)>> = Array2::from_shape_vec(
-        (10, 10),
-        (0..10)
-            .into_iter()
-            .flat_map(|row_idx| {
-                (0..10)
-                    .into_iter()
-                    .map(move |col_idx| get_neighbors(10, 10, row_idx, col_idx))
-            })
-            .collect(),
-    )
-    .expect("Could not create neighbors array");
+    let mut arr = arr.mapv(Octopus::EnergyLevel);
+    let neighbors_arr = build_neighbors(arr.nrows(), arr.ncols());
 
     let expected = arr2(&[
         [3, 4, 5, 4, 3],
@@ -335,7 +323,7 @@ fn test_time_step_1() {
         [4, 0, 0, 0, 4],
         [3, 4, 5, 4, 3],
     ]);
-    let expected = expected.mapv(|n| Octopus::EnergyLevel(n));
+    let expected = expected.mapv(Octopus::EnergyLevel);
 
     let got = time_step(&mut arr.view_mut(), neighbors_arr.view());
 
@@ -352,20 +340,9 @@ fn test_time_step_2() {
         [4, 0, 0, 0, 4],
         [3, 4, 5, 4, 3],
     ]);
-    let mut arr = arr.mapv(|n| Octopus::EnergyLevel(n));
-
-    let neighbors_arr: Array2<Vec<(usize, usize)>> = Array2::from_shape_vec(
-        (10, 10),
-        (0..10)
-            .into_iter()
-            .flat_map(|row_idx| {
-                (0..10)
-                    .into_iter()
-                    .map(move |col_idx| get_neighbors(10, 10, row_idx, col_idx))
-            })
-            .collect(),
-    )
-    .expect("Could not create neighbors array");
+    let mut arr = arr.mapv(Octopus::EnergyLevel);
+
+    let neighbors_arr = build_neighbors(arr.nrows(), arr.ncols());
 
     let expected = arr2(&[
         [4, 5, 6, 5, 4],
@@ -374,7 +351,7 @@ fn test_time_step_2() {
         [5, 1, 1, 1, 5],
         [4, 5, 6, 5, 4],
     ]);
-    let expected = expected.mapv(|n| Octopus::EnergyLevel(n));
+    let expected = expected.mapv(Octopus::EnergyLevel);
 
     let got = time_step(&mut arr.view_mut(), neighbors_arr.view());
 
@@ -396,20 +373,9 @@ fn test_time_step_3() {
         [4, 8, 4, 6, 8, 4, 8, 5, 5, 4],
         [5, 2, 8, 3, 7, 5, 1, 5, 2, 6],
     ]);
-    let mut arr = arr.mapv(|n| Octopus::EnergyLevel(n));
-
-    let neighbors_arr: Array2<Vec<(usize, usize)>> = Array2::from_shape_vec(
-        (10, 10),
-        (0..10)
-            .into_iter()
-            .flat_map(|row_idx| {
-                (0..10)
-                    .into_iter()
-                    .map(move |col_idx| get_neighbors(10, 10, row_idx, col_idx))
-            })
-            .collect(),
-    )
-    .expect("Could not create neighbors array");
+    let mut arr = arr.mapv(Octopus::EnergyLevel);
+
+    let neighbors_arr = build_neighbors(arr.nrows(), arr.ncols());
 
     let expected = arr2(&[
         [6, 5, 9, 4, 2, 5, 4, 3, 3, 4],
@@ -423,7 +389,7 @@ fn test_time_step_3() {
         [5, 9, 5, 7, 9, 5, 9, 6, 6, 5],
         [6, 3, 9, 4, 8, 6, 2, 6, 3, 7],
     ]);
-    let expected = expected.mapv(|n| Octopus::EnergyLevel(n));
+    let expected = expected.mapv(Octopus::EnergyLevel);
 
     let got = time_step(&mut arr.view_mut(), neighbors_arr.view());
 
@@ -445,20 +411,9 @@ fn test_time_step_4() {
         [5, 9, 5, 7, 9, 5, 9, 6, 6, 5],
         [6, 3, 9, 4, 8, 6, 2, 6, 3, 7],
     ]);
-    let mut arr = arr.mapv(|n| Octopus::EnergyLevel(n));
-
-    let neighbors_arr: Array2<Vec<(usize, usize)>> = Array2::from_shape_vec(
-        (10, 10),
-        (0..10)
-            .into_iter()
-            .flat_map(|row_idx| {
-                (0..10)
-                    .into_iter()
-                    .map(move |col_idx| get_neighbors(10, 10, row_idx, col_idx))
-            })
-            .collect(),
-    )
-    .expect("Could not create neighbors array");
+    let mut arr = arr.mapv(Octopus::EnergyLevel);
+
+    let neighbors_arr = build_neighbors(arr.nrows(), arr.ncols());
 
     let expected = arr2(&[
         [8, 8, 0, 7, 4, 7, 6, 5, 5, 5],
@@ -472,7 +427,7 @@ fn test_time_step_4() {
         [9, 0, 0, 0, 0, 0, 0, 8, 7, 6],
         [8, 7, 0, 0, 0, 0, 6, 8, 4, 8],
     ]);
-    let expected = expected.mapv(|n| Octopus::EnergyLevel(n));
+    let expected = expected.mapv(Octopus::EnergyLevel);
 
     let got = time_step(&mut arr.view_mut(), neighbors_arr.view());
 
@@ -494,20 +449,9 @@ fn test_time_step_5() {
         [9, 0, 0, 0, 0, 0, 0, 8, 7, 6],
         [8, 7, 0, 0, 0, 0, 6, 8, 4, 8],
     ]);
-    let mut arr = arr.mapv(|n| Octopus::EnergyLevel(n));
-
-    let neighbors_arr: Array2<Vec<(usize, usize)>> = Array2::from_shape_vec(
-        (10, 10),
-        (0..10)
-            .into_iter()
-            .flat_map(|row_idx| {
-                (0..10)
-                    .into_iter()
-                    .map(move |col_idx| get_neighbors(10, 10, row_idx, col_idx))
-            })
-            .collect(),
-    )
-    .expect("Could not create neighbors array");
+    let mut arr = arr.mapv(Octopus::EnergyLevel);
+
+    let neighbors_arr = build_neighbors(arr.nrows(), arr.ncols());
 
     let expected = arr2(&[
         [0, 0, 5, 0, 9, 0, 0, 8, 6, 6],
@@ -521,7 +465,7 @@ fn test_time_step_5() {
         [0, 4, 2, 1, 1, 2, 5, 0, 0, 0],
         [0, 0, 2, 1, 1, 1, 9, 0, 0, 0],
     ]);
-    let expected = expected.mapv(|n| Octopus::EnergyLevel(n));
+    let expected = expected.mapv(Octopus::EnergyLevel);
 
     let got = time_step(&mut arr.view_mut(), neighbors_arr.view());
 
@@ -543,23 +487,12 @@ fn test_part2() {
         [4, 8, 4, 6, 8, 4, 8, 5, 5, 4],
         [5, 2, 8, 3, 7, 5, 1, 5, 2, 6],
     ]);
-    let arr = arr.mapv(|n| Octopus::EnergyLevel(n));
-
-    let neighbors_arr: Array2<Vec<(usize, usize)>> = Array2::from_shape_vec(
-        (10, 10),
-        (0..10)
-            .into_iter()
-            .flat_map(|row_idx| {
-                (0..10)
-                    .into_iter()
-                    .map(move |col_idx| get_neighbors(10, 10, row_idx, col_idx))
-            })
-            .collect(),
-    )
-    .expect("Could not create neighbors array");
-
-    let got = part2(arr.view(), neighbors_arr.view());
-    assert_eq!(195, got);
+    let arr = arr.mapv(Octopus::EnergyLevel);
+
+    let neighbors_arr = build_neighbors(arr.nrows(), arr.ncols());
+
+    let got = part2(arr.view(), neighbors_arr.view(), 10_000);
+    assert_eq!(Some(195), got);
 }
 
 #[test]
@@ -567,20 +500,9 @@ fn test_part1_actual() {
     let input_str =
         std::fs::read_to_string("input/day11.txt").expect("Failed to read day 11 input");
     let arr = parse_input(&input_str);
-    let arr = arr.mapv(|n| Octopus::EnergyLevel(n));
-
-    let neighbors_arr: Array2<Vec<(usize, usize)>> = Array2::from_shape_vec(
-        (10, 10),
-        (0..10)
-            .into_iter()
-            .flat_map(|row_idx| {
-                (0..10)
-                    .into_iter()
-                    .map(move |col_idx| get_neighbors(10, 10, row_idx, col_idx))
-            })
-            .collect(),
-    )
-    .expect("Could not create neighbors array");
+    let arr = arr.mapv(Octopus::EnergyLevel);
+
+    let neighbors_arr = build_neighbors(arr.nrows(), arr.ncols());
 
     let got = part1(arr.view(), 100, neighbors_arr.view());
     assert_eq!(1757, got);
@@ -591,21 +513,49 @@ fn test_part2_actual() {
     let input_str =
         std::fs::read_to_string("input/day11.txt").expect("Failed to read day 11 input");
     let arr = parse_input(&input_str);
-    let arr = arr.mapv(|n| Octopus::EnergyLevel(n));
-
-    let neighbors_arr: Array2<Vec<(usize, usize)>> = Array2::from_shape_vec(
-        (10, 10),
-        (0..10)
-            .into_iter()
-            .flat_map(|row_idx| {
-                (0..10)
-                    .into_iter()
-                    .map(move |col_idx| get_neighbors(10, 10, row_idx, col_idx))
-            })
-            .collect(),
-    )
-    .expect("Could not create neighbors array");
-
-    let got = part2(arr.view(), neighbors_arr.view());
-    assert_eq!(422, got);
+    let arr = arr.mapv(Octopus::EnergyLevel);
+
+    let neighbors_arr = build_neighbors(arr.nrows(), arr.ncols());
+
+    let got = part2(arr.view(), neighbors_arr.view(), 10_000);
+    assert_eq!(Some(422), got);
+}
+
+#[test]
+fn test_part2_never_syncs() {
+    // The right octopus is always 5 behind the left one, so they never flash together
+    let arr = arr2(&[[0, 5]]).mapv(Octopus::EnergyLevel);
+    let neighbors_arr = build_neighbors(arr.nrows(), arr.ncols());
+
+    let got = part2(arr.view(), neighbors_arr.view(), 1_000);
+    assert_eq!(None, got);
+}
+
+#[test]
+fn test_flash_counts() {
+    let input_str = "5483143223
+2745854711
+5264556173
+6141336146
+6357385478
+4167524645
+2176841721
+6882881134
+4846848554
+5283751526";
+    let arr = parse_input(input_str).mapv(Octopus::EnergyLevel);
+    let neighbors_arr = build_neighbors(arr.nrows(), arr.ncols());
+
+    let got: Vec<usize> = flash_counts(arr.view(), neighbors_arr.view())
+        .take(10)
+        .collect();
+    assert_eq!(vec![0, 35, 45, 16, 8, 1, 7, 24, 39, 29], got);
+    assert_eq!(204, got.iter().sum::<usize>());
+}
+
+#[test]
+fn test_examples() {
+    for check in puzzle::check_examples(EXAMPLES, PARTS) {
+        assert!(check.is_ok(), "{}", check);
+    }
 }
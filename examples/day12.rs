@@ -1,5 +1,7 @@
 use std::collections::{HashMap, HashSet, VecDeque};
 
+use aoc_2021_rs::puzzle::{self, Example};
+
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
 enum Node<'a> {
     Small {
@@ -228,7 +230,76 @@ fn find_all_paths2<'a>(
     results
 }
 
+/// The examples from the puzzle description
+const EXAMPLES: &[Example] = &[
+    Example {
+        input: "start-A
+start-b
+A-c
+A-b
+b-d
+A-end
+b-end",
+        part1: Some("10"),
+        part2: Some("36"),
+    },
+    Example {
+        input: "dc-end
+HN-start
+start-kj
+dc-start
+dc-HN
+LN-dc
+HN-end
+kj-sa
+kj-HN
+kj-dc",
+        part1: Some("19"),
+        part2: Some("103"),
+    },
+    Example {
+        input: "fs-end
+he-DX
+fs-he
+start-DX
+pj-DX
+end-zg
+zg-sl
+zg-pj
+pj-he
+RW-he
+fs-DX
+pj-RW
+zg-RW
+start-pj
+he-WI
+zg-he
+pj-fs
+start-RW",
+        part1: Some("226"),
+        part2: Some("3509"),
+    },
+];
+
+/// Each part, solved straight from the input
+const PARTS: [Option<puzzle::Part>; 2] = [Some(solve_part1), Some(solve_part2)];
+
+fn solve_part1(input: &str) -> Result<String, String> {
+    let (nodes, adjacency_list) = parse_input(input);
+    Ok(find_all_paths(&nodes, &adjacency_list).len().to_string())
+}
+
+fn solve_part2(input: &str) -> Result<String, String> {
+    let (nodes, adjacency_list) = parse_input2(input);
+    Ok(find_all_paths2(&nodes, &adjacency_list).to_string())
+}
+
 fn main() {
+    // Pass `--example` to check the solutions against the puzzle's examples instead
+    if std::env::args().any(|arg| arg == "--example") {
+        std::process::exit(puzzle::run_examples(EXAMPLES, PARTS));
+    }
+
     let setup_time = std::time::Instant::now();
 
     let input_str =
@@ -508,4 +579,11 @@ start-RW";
         let got = find_all_paths2(&nodes, &adjacency_list);
         assert_eq!(3509, got)
     }
+
+    #[test]
+    fn test_examples() {
+        for check in puzzle::check_examples(EXAMPLES, PARTS) {
+            assert!(check.is_ok(), "{}", check);
+        }
+    }
 }
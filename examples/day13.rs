@@ -1,4 +1,7 @@
-use ndarray::{arr2, s, Array2, ArrayView2, Axis};
+use aoc_2021_rs::font::{FONT, GLYPH_HEIGHT, GLYPH_WIDTH};
+use aoc_2021_rs::parsing;
+use aoc_2021_rs::puzzle::{self, Example};
+use ndarray::{s, Array2, ArrayView2, Axis, Slice};
 
 #[derive(Debug, PartialEq)]
 struct Fold {
@@ -6,27 +9,51 @@ struct Fold {
     index: usize,
 }
 
-fn parse_input(input: &str) -> (Array2<bool>, Vec<Fold>) {
-    let (dot_inds_str, fold_strs) = input
-        .split_once("\n\n")
-        .expect("Could not split on a double new line");
+/// Parse the (row, col) of every dot, and the folds to make
+fn parse_dots(input: &str) -> (Vec<(usize, usize)>, Vec<Fold>) {
+    let sections = parsing::sections(input);
+    let (dot_lines, fold_lines) = match sections.as_slice() {
+        [dots, folds] => (dots, folds),
+        _ => panic!("Expected a section of dots, and a section of folds"),
+    };
 
     // Convert all of the indices to number pairs, and flip them so they have the proper
     // row->col indexing order
-    let dot_inds: Vec<(usize, usize)> = dot_inds_str
-        .lines()
+    let dot_inds: Vec<(usize, usize)> = dot_lines
+        .iter()
+        .map(
+            |line| match parsing::numbers(line).expect("Could not parse coordinates")[..] {
+                [col, row] => (row, col),
+                _ => panic!("Expected a pair of coordinates, not {:?}", line),
+            },
+        )
+        .collect();
+
+    // Convert "fold along x/y=\d+" to a vec of Folds
+    let folds: Vec<Fold> = fold_lines
+        .iter()
         .map(|line| {
-            let (col, row) = line
-                .split_once(',')
-                .expect("Could not split coordinates around comma");
-            (
-                row.parse().expect("Could not parse row number"),
-                col.parse().expect("Could not parse col number"),
-            )
+            line.split_ascii_whitespace()
+                .last()
+                .expect("Could not find fold str")
+        })
+        .map(|s| {
+            let (x_or_y, fold_idx) = s.split_once('=').expect("Could not split around '='");
+            let axis: Axis = match x_or_y {
+                "y" => Axis(0),
+                "x" => Axis(1),
+                _ => panic!("Fold axis was not x or y"),
+            };
+            let index: usize = fold_idx.parse().expect("Could not parse fold index");
+            Fold { axis, index }
         })
         .collect();
 
-    // Get the extents of the array
+    (dot_inds, folds)
+}
+
+/// The size of the smallest sheet of paper that holds all of `dot_inds`
+fn paper_shape(dot_inds: &[(usize, usize)]) -> (usize, usize) {
     let max_row = dot_inds
         .iter()
         .map(|(row, _)| row)
@@ -39,6 +66,14 @@ fn parse_input(input: &str) -> (Array2<bool>, Vec<Fold>) {
         .max()
         .expect("Could not find a minimum col")
         + 1;
+    (max_row, max_col)
+}
+
+fn parse_input(input: &str) -> (Array2<bool>, Vec<Fold>) {
+    let (dot_inds, folds) = parse_dots(input);
+
+    // Get the extents of the array
+    let (max_row, max_col) = paper_shape(&dot_inds);
 
     // Create an array of all false
     let mut arr: Array2<bool> = Array2::from_elem((max_row, max_col), false);
@@ -48,107 +83,294 @@ fn parse_input(input: &str) -> (Array2<bool>, Vec<Fold>) {
         .iter()
         .for_each(|(row, col)| arr[[*row, *col]] = true);
 
-    // Convert "fold along x/y=\d+" to a vec of Folds
-    let folds: Vec<Fold> = fold_strs
-        .lines()
-        .map(|line| {
-            line.split_ascii_whitespace()
-                .last()
-                .expect("Could not find fold str")
-        })
-        .map(|s| {
-            let (x_or_y, fold_idx) = s.split_once('=').expect("Could not split around '='");
-            let axis: Axis = match x_or_y {
-                "y" => Axis(0),
-                "x" => Axis(1),
-                _ => panic!("Fold axis was not x or y"),
-            };
-            let index: usize = fold_idx.parse().expect("Could not parse fold index");
-            Fold { axis, index }
-        })
-        .collect();
-
     (arr, folds)
 }
 
 fn apply_fold(arr: ArrayView2<bool>, fold: &Fold) -> Array2<bool> {
-    // Split the array at the correct spot
-    let (top_left, bottom_right) = arr.split_at(fold.axis, fold.index);
-    // Remove the top row or left column from `bottom_right`
-    let bottom_right = match fold.axis {
-        Axis(0) => bottom_right.slice(s![1.., ..]),
-        Axis(1) => bottom_right.slice(s![.., 1..]),
-        _ => panic!("Fold should not be along an axis other than 0 or 1"),
-    };
+    // The near side is everything before the fold line, and the far side everything after it.
+    // The fold line can be past the edge of the paper, in which case there is no far side.
+    let len = arr.len_of(fold.axis);
+    let near_len = fold.index.min(len);
+    let far_len = len.saturating_sub(fold.index + 1);
+
+    // If the far side is bigger than the near side, it sticks out past the near side's edge
+    // once it is folded over, so the near side gets shifted along
+    let new_len = fold.index.max(far_len);
+    let offset = new_len - fold.index;
+    let mut new_shape = arr.raw_dim();
+    new_shape[fold.axis.index()] = new_len;
+    let mut folded = Array2::from_elem(new_shape, false);
+
+    let near = arr.slice_axis(fold.axis, Slice::from(..near_len));
+    folded
+        .slice_axis_mut(fold.axis, Slice::from(offset..offset + near_len))
+        .zip_mut_with(&near, |a, &b| *a |= b);
+
+    // Flip the far side over, so that the row or column just past the fold line ends up
+    // next to it
+    if far_len > 0 {
+        let mut far = arr.slice_axis(fold.axis, Slice::from(fold.index + 1..));
+        far.invert_axis(fold.axis);
+        folded
+            .slice_axis_mut(fold.axis, Slice::from(new_len - far_len..))
+            .zip_mut_with(&far, |a, &b| *a |= b);
+    }
 
-    // Flip the bottom/right side as required
-    let mut new_bottom_right: Array2<bool> = bottom_right.into_owned();
-    new_bottom_right.invert_axis(fold.axis);
+    folded
+}
 
-    // The fold will not always fold the original array in half. One dimension will
-    // always be the same between the two arrays, but one may be smaller than the other.
-    // Assume the bottom_right array is always the smaller
+/// A sheet of transparent paper. It can be stored as a dense grid, which is quick to fold
+/// when the paper is small, or as a sorted list of the (row, col) of every dot, which only
+/// uses memory for the dots, no matter how large the paper is.
+#[derive(Debug, Clone, PartialEq)]
+enum Paper {
+    Dense(Array2<bool>),
+    Sparse {
+        dots: Vec<(usize, usize)>,
+        shape: (usize, usize),
+    },
+}
 
-    let new_dim: (usize, usize) = if bottom_right.nrows() < top_left.nrows() {
-        (top_left.nrows() - bottom_right.nrows(), top_left.ncols())
-    } else {
-        (top_left.nrows(), top_left.ncols() - bottom_right.ncols())
-    };
+impl Paper {
+    fn sparse(mut dots: Vec<(usize, usize)>) -> Paper {
+        let shape = paper_shape(&dots);
+        dots.sort_unstable();
+        dots.dedup();
+        Paper::Sparse { dots, shape }
+    }
 
-    if (new_dim.0 > 0) & (new_dim.1 > 0) {
-        // Create an array of false to concatenate with `new_bottom_right`
-        let fs = Array2::from_elem(new_dim, false);
+    fn fold(&self, fold: &Fold) -> Paper {
+        match self {
+            Paper::Dense(arr) => Paper::Dense(apply_fold(arr.view(), fold)),
+            Paper::Sparse { dots, shape } => {
+                let (nrows, ncols) = *shape;
+                let len = if fold.axis == Axis(0) { nrows } else { ncols };
+
+                // If the far side is bigger than the near side, it sticks out past the near
+                // side's edge once it is folded over, so everything gets shifted along. The
+                // fold line can be past every dot, in which case there is no far side.
+                let new_len = fold.index.max(len.saturating_sub(fold.index + 1));
+                let offset = new_len - fold.index;
+                let reflect = |idx: usize| {
+                    if idx < fold.index {
+                        Some(idx + offset)
+                    } else if idx > fold.index {
+                        Some(new_len + fold.index - idx)
+                    } else {
+                        // Dots are never on the fold line
+                        None
+                    }
+                };
+
+                let mut new_dots: Vec<(usize, usize)> = dots
+                    .iter()
+                    .filter_map(|&(row, col)| {
+                        if fold.axis == Axis(0) {
+                            reflect(row).map(|row| (row, col))
+                        } else {
+                            reflect(col).map(|col| (row, col))
+                        }
+                    })
+                    .collect();
+                new_dots.sort_unstable();
+                new_dots.dedup();
+
+                let new_shape = if fold.axis == Axis(0) {
+                    (new_len, ncols)
+                } else {
+                    (nrows, new_len)
+                };
+                Paper::Sparse {
+                    dots: new_dots,
+                    shape: new_shape,
+                }
+            }
+        }
+    }
 
-        // Concatenate the falses with `new_bottom_right`
-        new_bottom_right = ndarray::concatenate(fold.axis, &[fs.view(), new_bottom_right.view()])
-            .expect("Could not concatenate axes");
+    fn count_dots(&self) -> usize {
+        match self {
+            Paper::Dense(arr) => arr.iter().filter(|&&b| b).count(),
+            Paper::Sparse { dots, .. } => dots.len(),
+        }
     }
 
-    // // Zip them together into a new array
-    new_bottom_right.zip_mut_with(&top_left, |a, b| {
-        *a |= b;
-    });
+    fn to_dense(&self) -> Array2<bool> {
+        match self {
+            Paper::Dense(arr) => arr.clone(),
+            Paper::Sparse { dots, shape } => {
+                let mut arr = Array2::from_elem(*shape, false);
+                dots.iter().for_each(|&(row, col)| arr[[row, col]] = true);
+                arr
+            }
+        }
+    }
+}
 
-    new_bottom_right
+fn part1(paper: &Paper, fold: &Fold) -> usize {
+    // Apply one fold, and count how many dots are left
+    paper.fold(fold).count_dots()
 }
 
-fn part1(arr: ArrayView2<bool>, fold: &Fold) -> usize {
-    // Apply one fold, and count how many trues exist in the output
-    apply_fold(arr, fold).iter().filter(|&&b| b).count()
+/// Read the capital letters written out by the dots in `arr`. Any rows below the letters
+/// must be empty. If some of the letters can't be recognised, the error lists the columns
+/// they start at.
+fn read_letters(arr: ArrayView2<bool>) -> Result<String, String> {
+    if arr.nrows() < GLYPH_HEIGHT {
+        return Err(format!(
+            "Expected at least {} rows of dots, found {}",
+            GLYPH_HEIGHT,
+            arr.nrows()
+        ));
+    }
+    if arr.slice(s![GLYPH_HEIGHT.., ..]).iter().any(|&b| b) {
+        return Err(format!("Found dots below row {}", GLYPH_HEIGHT));
+    }
+
+    let n_letters = arr.ncols().div_ceil(GLYPH_WIDTH + 1);
+    let mut letters = String::with_capacity(n_letters);
+    let mut unrecognised: Vec<String> = Vec::new();
+
+    for letter_idx in 0..n_letters {
+        let start_col = letter_idx * (GLYPH_WIDTH + 1);
+        // Include the blank column after the letter, to make sure it is blank
+        let end_col = (start_col + GLYPH_WIDTH + 1).min(arr.ncols());
+        let glyph = arr.slice(s![..GLYPH_HEIGHT, start_col..end_col]);
+
+        // Compare against every letter in the font. Columns past the edge of `arr` are empty.
+        let letter = FONT.iter().find(|(_, rows)| {
+            rows.iter().enumerate().all(|(row_idx, row)| {
+                (0..=GLYPH_WIDTH).all(|col_idx| {
+                    let dot = glyph.get((row_idx, col_idx)).copied().unwrap_or(false);
+                    dot == (row.chars().nth(col_idx) == Some('#'))
+                })
+            })
+        });
+
+        match letter {
+            Some((c, _)) => letters.push(*c),
+            None => unrecognised.push(format!("{}..{}", start_col, start_col + GLYPH_WIDTH)),
+        }
+    }
+
+    if unrecognised.is_empty() {
+        Ok(letters.trim_end().to_string())
+    } else {
+        Err(format!(
+            "Could not recognise the glyphs in columns {}",
+            unrecognised.join(", ")
+        ))
+    }
+}
+
+/// Draw the dots in `arr` as lines of `#` and ` `
+fn render(arr: ArrayView2<bool>) -> String {
+    arr.rows()
+        .into_iter()
+        .map(|row| row.iter().map(|&b| if b { '#' } else { ' ' }).collect())
+        .collect::<Vec<String>>()
+        .join("\n")
 }
 
-fn part2(arr: ArrayView2<bool>, folds: &[Fold]) -> Array2<char> {
-    let mut bool_result: Array2<bool> = arr.to_owned();
+fn fold_all(paper: &Paper, folds: &[Fold]) -> Paper {
+    let mut result = paper.clone();
     for f in folds {
-        bool_result = apply_fold(bool_result.view(), f);
+        result = result.fold(f);
     }
+    result
+}
 
-    bool_result.mapv(|b| if b { '#' } else { ' ' })
+fn part2(paper: &Paper, folds: &[Fold]) -> Result<String, String> {
+    read_letters(fold_all(paper, folds).to_dense().view())
+}
+
+/// The examples from the puzzle description. Folding the second part's example makes a
+/// square rather than letters, so there is no answer to check for it.
+const EXAMPLES: &[Example] = &[Example {
+    input: "6,10
+0,14
+9,10
+0,3
+10,4
+4,11
+6,0
+6,12
+4,1
+0,13
+10,12
+3,4
+3,0
+8,4
+1,10
+2,14
+8,10
+9,0
+
+fold along y=7
+fold along x=5",
+    part1: Some("17"),
+    part2: None,
+}];
+
+/// Each part, solved straight from the input
+const PARTS: [Option<puzzle::Part>; 2] = [Some(solve_part1), Some(solve_part2)];
+
+fn solve_part1(input: &str) -> Result<String, String> {
+    let (arr, folds) = parse_input(input);
+    let fold = folds.first().ok_or("There are no folds")?;
+    Ok(part1(&Paper::Dense(arr), fold).to_string())
+}
+
+fn solve_part2(input: &str) -> Result<String, String> {
+    let (arr, folds) = parse_input(input);
+    part2(&Paper::Dense(arr), &folds)
 }
 
 fn main() {
+    // Pass `--example` to check the solutions against the puzzle's examples instead
+    if std::env::args().any(|arg| arg == "--example") {
+        std::process::exit(puzzle::run_examples(EXAMPLES, PARTS));
+    }
+
     let setup_time = std::time::Instant::now();
 
     let input_str =
         std::fs::read_to_string("input/day13.txt").expect("Failed to read day 13 input");
-    let (arr, folds) = parse_input(&input_str);
+    // Pass `--sparse` to only store the dots, rather than the whole sheet of paper
+    let (paper, folds) = if std::env::args().any(|arg| arg == "--sparse") {
+        let (dot_inds, folds) = parse_dots(&input_str);
+        (Paper::sparse(dot_inds), folds)
+    } else {
+        let (arr, folds) = parse_input(&input_str);
+        (Paper::Dense(arr), folds)
+    };
     println!("Setup took {:.6} µs", setup_time.elapsed().as_micros());
 
     // Part 1
     let part1_time = std::time::Instant::now();
-    let part1_result = part1(arr.view(), &folds[0]);
+    let part1_result = part1(&paper, &folds[0]);
     println!("Part 1 took {:.6} µs", part1_time.elapsed().as_micros());
 
     // Part 2
     let part2_time = std::time::Instant::now();
-    let part2_result = part2(arr.view(), &folds);
+    let part2_result = part2(&paper, &folds);
     println!("Part 2 took {:.6} µs", part2_time.elapsed().as_micros());
 
     println!();
     println!("Part 1 result: {}", part1_result);
-    println!("Part 2 result:\n {}", part2_result);
+    match part2_result {
+        Ok(letters) => println!("Part 2 result: {}", letters),
+        Err(e) => println!(
+            "Part 2 result: {}\n{}",
+            e,
+            render(fold_all(&paper, &folds).to_dense().view())
+        ),
+    }
 }
 
+#[cfg(test)]
+use ndarray::arr2;
+
 #[test]
 fn test_parse_input() {
     let input_str = "6,10
@@ -419,7 +641,7 @@ fn test_part1() {
         index: 7,
     };
 
-    let got = part1(arr.view(), &fold);
+    let got = part1(&Paper::Dense(arr), &fold);
     assert_eq!(17, got);
 }
 
@@ -428,6 +650,172 @@ fn test_part1_actual() {
     let input_str =
         std::fs::read_to_string("input/day13.txt").expect("Failed to read day 13 input");
     let (arr, folds) = parse_input(&input_str);
-    let got = part1(arr.view(), &folds[0]);
+    let got = part1(&Paper::Dense(arr), &folds[0]);
     assert_eq!(661, got);
 }
+
+#[test]
+fn test_part2_actual() {
+    let input_str =
+        std::fs::read_to_string("input/day13.txt").expect("Failed to read day 13 input");
+    let (arr, folds) = parse_input(&input_str);
+    let got = part2(&Paper::Dense(arr), &folds);
+    assert_eq!(Ok(String::from("PFKLKCFP")), got);
+}
+
+#[test]
+fn test_read_letters() {
+    // Draw out every letter in the font, and make sure it reads back the same
+    let text: String = FONT.iter().map(|(c, _)| c).collect();
+    let mut arr = Array2::from_elem((GLYPH_HEIGHT, FONT.len() * 5 - 1), false);
+    for (letter_idx, (_, rows)) in FONT.iter().enumerate() {
+        for (row_idx, row) in rows.iter().enumerate() {
+            for (col_idx, c) in row.chars().enumerate() {
+                arr[[row_idx, letter_idx * 5 + col_idx]] = c == '#';
+            }
+        }
+    }
+    assert_eq!(Ok(text.trim_end().to_string()), read_letters(arr.view()));
+}
+
+#[test]
+fn test_read_letters_unrecognised() {
+    // The square from the example is not a letter
+    let arr: Array2<bool> = arr2(&[
+        [true, true, true, true, true],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [true, true, true, true, true],
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+    ]);
+    assert_eq!(
+        Err(String::from(
+            "Could not recognise the glyphs in columns 0..4"
+        )),
+        read_letters(arr.view())
+    );
+
+    // Too short to hold any letters
+    let arr = Array2::from_elem((3, 4), false);
+    assert_eq!(
+        Err(String::from("Expected at least 6 rows of dots, found 3")),
+        read_letters(arr.view())
+    );
+}
+
+#[test]
+fn test_sparse_matches_dense() {
+    let input_str =
+        std::fs::read_to_string("input/day13.txt").expect("Failed to read day 13 input");
+    let (arr, folds) = parse_input(&input_str);
+    let (dot_inds, _) = parse_dots(&input_str);
+
+    let mut dense = Paper::Dense(arr);
+    let mut sparse = Paper::sparse(dot_inds);
+    assert_eq!(dense.to_dense(), sparse.to_dense());
+
+    // Every fold should leave the same dots on the same size of paper
+    for fold in &folds {
+        dense = dense.fold(fold);
+        sparse = sparse.fold(fold);
+        assert_eq!(dense.count_dots(), sparse.count_dots());
+        assert_eq!(dense.to_dense(), sparse.to_dense());
+    }
+
+    assert_eq!(part2(&dense, &[]), part2(&sparse, &[]));
+}
+
+#[test]
+fn test_sparse_huge_coordinates() {
+    // Far too big to store densely, but only a few dots
+    let input_str = "0,0
+3,1
+1000000000,0
+
+fold along x=500000000";
+    let (dot_inds, folds) = parse_dots(input_str);
+    let paper = Paper::sparse(dot_inds).fold(&folds[0]);
+
+    assert_eq!(2, paper.count_dots());
+    assert_eq!(
+        Paper::Sparse {
+            dots: vec![(0, 0), (1, 3)],
+            shape: (2, 500000000),
+        },
+        paper
+    );
+}
+
+#[test]
+fn test_fold_bigger_far_side() {
+    // Folding at x=1 leaves the 3 columns on the right sticking out past the left edge, for
+    // dense and sparse paper alike
+    let input_str = "0,0
+4,0
+
+fold along x=1";
+    let (dot_inds, folds) = parse_dots(input_str);
+    let paper = Paper::sparse(dot_inds).fold(&folds[0]);
+    let (arr, _) = parse_input(input_str);
+    let dense = Paper::Dense(arr).fold(&folds[0]);
+    assert_eq!(dense.to_dense(), paper.to_dense());
+    assert_eq!(
+        Paper::Sparse {
+            dots: vec![(0, 0), (0, 2)],
+            shape: (1, 3),
+        },
+        paper
+    );
+}
+
+#[test]
+fn test_fold_past_every_dot() {
+    // Nothing is on the far side, so the dots stay where they are, on dense and sparse paper
+    let input_str = "0,0
+1,1
+
+fold along y=5";
+    let (dot_inds, folds) = parse_dots(input_str);
+    let paper = Paper::sparse(dot_inds).fold(&folds[0]);
+    let (arr, _) = parse_input(input_str);
+    let dense = Paper::Dense(arr).fold(&folds[0]);
+    assert_eq!(dense.to_dense(), paper.to_dense());
+    assert_eq!(
+        Paper::Sparse {
+            dots: vec![(0, 0), (1, 1)],
+            shape: (5, 2),
+        },
+        paper
+    );
+}
+
+#[test]
+fn test_parse_input_crlf() {
+    let input_str = "6,10\n0,14\n9,10\n\nfold along y=7\nfold along x=5\n";
+    let windows_str = "6,10\r\n0,14\r\n9,10 \r\n\r\nfold along y=7\r\nfold along x=5\r\n\r\n";
+    assert_eq!(parse_dots(input_str), parse_dots(windows_str));
+    assert_eq!(parse_input(input_str), parse_input(windows_str));
+}
+
+#[test]
+fn test_examples() {
+    for check in puzzle::check_examples(EXAMPLES, PARTS) {
+        assert!(check.is_ok(), "{}", check);
+    }
+}
+
+#[test]
+fn test_generated_input() {
+    // Generated paper always folds up into eight letters, whichever way it is stored
+    for seed in 0..10 {
+        let input_str = aoc_2021_rs::generate::generate(13, Some(300), seed).unwrap();
+        let (dot_inds, folds) = parse_dots(&input_str);
+        let (arr, _) = parse_input(&input_str);
+
+        let letters = part2(&Paper::Dense(arr), &folds).unwrap();
+        assert_eq!(8, letters.len());
+        assert_eq!(Ok(letters), part2(&Paper::sparse(dot_inds), &folds));
+    }
+}
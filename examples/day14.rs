@@ -1,18 +1,33 @@
+use aoc_2021_rs::crosscheck::{self, Crosscheck};
+use aoc_2021_rs::matrix::{checked_matrix_power, matrix_power_mod};
+use aoc_2021_rs::parsing;
+use aoc_2021_rs::puzzle::{self, Example};
 use itertools::Itertools;
+use ndarray::{Array1, Array2};
 use std::{collections::HashMap, str};
 
-fn parse_input(
-    input: &str,
-) -> (
-    HashMap<(char, char), usize>,
-    HashMap<(char, char), ((char, char), (char, char))>,
-) {
-    let (start_str, rules) = input
-        .split_once("\n\n")
-        .expect("Could not split around double newline");
+/// How many times each pair of letters appears in the polymer
+type PairCounts = HashMap<(char, char), u128>;
+
+/// The two pairs that each pair turns into when its element is inserted
+type Rules = HashMap<(char, char), ((char, char), (char, char))>;
+
+/// How many times each element appears in the polymer
+type Histogram = HashMap<char, u128>;
+
+fn overflow_error() -> String {
+    String::from("Element counts are too large to fit in a u128")
+}
+
+fn parse_input(input: &str) -> (PairCounts, Rules) {
+    let sections = parsing::sections(input);
+    let (start_str, rules) = match sections.as_slice() {
+        [start, rules] if start.len() == 1 => (start[0], rules),
+        _ => panic!("Expected the template, then a section of rules"),
+    };
 
     // Now break up the start_str into pairs of letters
-    let pairs: HashMap<(char, char), usize> =
+    let pairs: PairCounts =
         start_str
             .trim()
             .chars()
@@ -25,11 +40,8 @@ fn parse_input(
             });
 
     let rules = rules
-        .lines()
-        .map(|line| {
-            line.split_once(" -> ")
-                .expect("Could not split around arrow")
-        })
+        .iter()
+        .map(|line| parsing::rule(line).expect("Could not split around arrow"))
         .map(|(key, val)| {
             let cs: Vec<char> = key.chars().collect();
             let char_val = val
@@ -40,100 +52,501 @@ fn parse_input(
             let right_side = (char_val, cs[1]);
             ((cs[0], cs[1]), (left_side, right_side))
         })
-        .collect::<HashMap<(char, char), ((char, char), (char, char))>>();
+        .collect::<Rules>();
 
     (pairs, rules)
 }
 
-fn step(
-    input: &HashMap<(char, char), usize>,
-    rules: &HashMap<(char, char), ((char, char), (char, char))>,
-) -> HashMap<(char, char), usize> {
-    // Create a result vec
-    let mut result: HashMap<(char, char), usize> = HashMap::new();
+/// The number of possible elements, `A` to `Z`
+const N_ELEMENTS: usize = 26;
 
-    // Iterate over the input
-    input
-        .iter()
-        // Check if a rule exists for this pair
-        .filter(|(pair, _)| rules.contains_key(pair))
-        // If so, add the new values to the result
-        .for_each(|(pair, val)| {
-            // Get the replacement pairs
-            let (p1, p2) = rules
-                .get(pair)
-                .expect("Could not find pair we should have filtered out");
+/// The number of possible pairs of elements
+const N_PAIRS: usize = N_ELEMENTS * N_ELEMENTS;
+
+/// How many times each pair appears in the polymer, indexed by `pair_index`
+type DensePairCounts = [u128; N_PAIRS];
 
-            // Add them to the result
-            *result.entry(*p1).or_insert(0) += val;
-            *result.entry(*p2).or_insert(0) += val;
-        });
+/// The rules compiled into a table indexed by `pair_index`, holding the indices of the two
+/// pairs each pair turns into. Pairs without a rule are `None`.
+type CompiledRules = [Option<(usize, usize)>; N_PAIRS];
 
-    result
+/// Where an element goes in the tables. Elements must be uppercase letters.
+fn element_index(c: char) -> Result<usize, String> {
+    if c.is_ascii_uppercase() {
+        Ok(c as usize - 'A' as usize)
+    } else {
+        Err(format!("'{}' is not an uppercase element", c))
+    }
 }
 
-fn solve(
-    input_str: &HashMap<(char, char), usize>,
-    rules: &HashMap<(char, char), ((char, char), (char, char))>,
-    nsteps: usize,
-    last_char: char,
-) -> usize {
-    let mut input = input_str.clone();
-    for _ in 0..nsteps {
-        input = step(&input, rules);
+/// Where a pair of elements goes in the tables
+fn pair_index(c1: char, c2: char) -> Result<usize, String> {
+    Ok(element_index(c1)? * N_ELEMENTS + element_index(c2)?)
+}
+
+/// The pair of elements at `idx` in the tables
+fn pair_chars(idx: usize) -> (char, char) {
+    let to_char = |element: usize| (b'A' + element as u8) as char;
+    (to_char(idx / N_ELEMENTS), to_char(idx % N_ELEMENTS))
+}
+
+fn compile_rules(rules: &Rules) -> Result<CompiledRules, String> {
+    let mut table: CompiledRules = [None; N_PAIRS];
+    for (&(c1, c2), &(p1, p2)) in rules {
+        let describe = |e: String| format!("In rule {}{} -> {}: {}", c1, c2, p1.1, e);
+        let from = pair_index(c1, c2).map_err(describe)?;
+        let p1 = pair_index(p1.0, p1.1).map_err(describe)?;
+        let p2 = pair_index(p2.0, p2.1).map_err(describe)?;
+        table[from] = Some((p1, p2));
+    }
+    Ok(table)
+}
+
+fn dense_pair_counts(pairs: &PairCounts) -> Result<DensePairCounts, String> {
+    let mut counts: DensePairCounts = [0; N_PAIRS];
+    for (&(c1, c2), &count) in pairs {
+        counts[pair_index(c1, c2)?] += count;
+    }
+    Ok(counts)
+}
+
+/// Every pair that is in the template, or can be made from it, that has no rule. These
+/// pairs never have anything inserted between them.
+fn pairs_without_rules(
+    pairs: &PairCounts,
+    rules: &CompiledRules,
+) -> Result<Vec<(char, char)>, String> {
+    let mut seen = [false; N_PAIRS];
+    let mut to_visit: Vec<usize> = Vec::new();
+    for (idx, &count) in dense_pair_counts(pairs)?.iter().enumerate() {
+        if count > 0 {
+            seen[idx] = true;
+            to_visit.push(idx);
+        }
     }
 
-    // Calculate how many times each letter appears
-    let mut letter_count: HashMap<char, usize> = HashMap::new();
+    let mut missing = Vec::new();
+    while let Some(idx) = to_visit.pop() {
+        match rules[idx] {
+            Some((p1, p2)) => {
+                for p in [p1, p2] {
+                    if !seen[p] {
+                        seen[p] = true;
+                        to_visit.push(p);
+                    }
+                }
+            }
+            None => missing.push(pair_chars(idx)),
+        }
+    }
+
+    missing.sort_unstable();
+    Ok(missing)
+}
+
+/// Apply the rules to every pair in `input`, writing the new counts into `output`
+fn step(
+    input: &DensePairCounts,
+    rules: &CompiledRules,
+    output: &mut DensePairCounts,
+) -> Result<(), String> {
+    output.fill(0);
+
+    for (pair, &val) in input.iter().enumerate().filter(|(_, &val)| val > 0) {
+        // If there is no rule, nothing is inserted, and the pair stays as it is
+        let new_pairs = match rules[pair] {
+            Some((p1, p2)) => [Some(p1), Some(p2)],
+            None => [Some(pair), None],
+        };
+        for p in new_pairs.into_iter().flatten() {
+            output[p] = output[p].checked_add(val).ok_or_else(overflow_error)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Calculate how many times each letter appears
+fn element_counts(pairs: &DensePairCounts, last_char: char) -> Result<Histogram, String> {
+    let mut letter_count: Histogram = HashMap::new();
+
     // Get only the first from each. For the last item, also get its last character
-    for ((c1, _), val) in &input {
-        *letter_count.entry(*c1).or_insert(0) += val;
+    for (pair, &val) in pairs.iter().enumerate().filter(|(_, &val)| val > 0) {
+        let count = letter_count.entry(pair_chars(pair).0).or_insert(0);
+        *count = count.checked_add(val).ok_or_else(overflow_error)?;
     }
 
     // Get the last character from
-    *letter_count.entry(last_char).or_insert(0) += 1;
+    let count = letter_count.entry(last_char).or_insert(0);
+    *count = count.checked_add(1).ok_or_else(overflow_error)?;
 
+    Ok(letter_count)
+}
+
+/// An endless iterator over how many times each element appears after each step, starting
+/// with the template itself. If the rules are invalid, or the counts overflow, the error is
+/// yielded, and the iterator stops.
+fn histograms(
+    pairs: &PairCounts,
+    rules: &Rules,
+    last_char: char,
+) -> impl Iterator<Item = Result<Histogram, String>> {
+    let compiled = compile_rules(rules);
+    let mut next = Some(dense_pair_counts(pairs));
+    std::iter::from_fn(move || {
+        let rules = match &compiled {
+            Ok(rules) => rules,
+            Err(e) => return next.take().map(|_| Err(e.clone())),
+        };
+        let pairs = match next.take()? {
+            Ok(pairs) => pairs,
+            Err(e) => return Some(Err(e)),
+        };
+        let histogram = element_counts(&pairs, last_char);
+        if histogram.is_ok() {
+            let mut new_pairs = [0; N_PAIRS];
+            next = Some(step(&pairs, rules, &mut new_pairs).map(|_| new_pairs));
+        }
+        Some(histogram)
+    })
+}
+
+/// The difference between the most and least common elements
+fn max_minus_min(letter_count: &Histogram) -> u128 {
     // Get the min and the max
-    let min_max = letter_count.iter().map(|(_, &n)| n).minmax();
+    let min_max = letter_count.values().minmax();
     let (min_char, max_char) = match min_max {
         itertools::MinMaxResult::NoElements => panic!("Could not find min max"),
         itertools::MinMaxResult::OneElement(_) => panic!("Could only find one element"),
         itertools::MinMaxResult::MinMax(min_char, max_char) => (min_char, max_char),
     };
     max_char - min_char
+}
+
+fn solve(
+    input_str: &PairCounts,
+    rules: &Rules,
+    nsteps: usize,
+    last_char: char,
+) -> Result<u128, String> {
+    let rules = compile_rules(rules)?;
+    let mut pairs = dense_pair_counts(input_str)?;
+    let mut new_pairs = [0; N_PAIRS];
+    for _ in 0..nsteps {
+        step(&pairs, &rules, &mut new_pairs)?;
+        std::mem::swap(&mut pairs, &mut new_pairs);
+    }
+
+    Ok(max_minus_min(&element_counts(&pairs, last_char)?))
+}
+
+/// Insert the elements into the polymer itself, as the puzzle describes it. The polymer
+/// doubles in length every step, so this is only any good for a few steps, but it is simple
+/// enough to check the pair counting against.
+fn expand(template: &str, rules: &Rules, nsteps: usize) -> String {
+    let mut polymer = template.to_string();
+    for _ in 0..nsteps {
+        let mut next = String::with_capacity(2 * polymer.len());
+        for (c1, c2) in polymer.chars().tuple_windows() {
+            next.push(c1);
+            if let Some(((_, inserted), _)) = rules.get(&(c1, c2)) {
+                next.push(*inserted);
+            }
+        }
+        next.extend(polymer.chars().last());
+        polymer = next;
+    }
+    polymer
+}
+
+/// Every pair that can ever appear, the matrix of how they turn into each other in one step,
+/// and how many of each there are to start with. `transition[[to, from]]` is how many `to`
+/// pairs a single `from` pair becomes.
+fn pair_matrix(
+    input_str: &PairCounts,
+    rules: &Rules,
+) -> (Vec<(char, char)>, Array2<u128>, Array1<u128>) {
+    // Give every pair that can ever appear a row and column in the matrix
+    let all_pairs: Vec<(char, char)> = input_str
+        .keys()
+        .chain(rules.keys())
+        .chain(rules.values().flat_map(|(p1, p2)| [p1, p2]))
+        .copied()
+        .sorted()
+        .dedup()
+        .collect();
+    let index: HashMap<(char, char), usize> = all_pairs
+        .iter()
+        .enumerate()
+        .map(|(idx, pair)| (*pair, idx))
+        .collect();
+
+    let n = all_pairs.len();
+    let mut transition: Array2<u128> = Array2::zeros((n, n));
+    for (from, pair) in all_pairs.iter().enumerate() {
+        match rules.get(pair) {
+            Some((p1, p2)) => {
+                transition[[index[p1], from]] += 1;
+                transition[[index[p2], from]] += 1;
+            }
+            // If there is no rule, the pair stays as it is
+            None => transition[[from, from]] += 1,
+        }
+    }
+
+    let mut start: Array1<u128> = Array1::zeros(n);
+    for (pair, count) in input_str {
+        start[index[pair]] = *count;
+    }
+
+    (all_pairs, transition, start)
+}
+
+/// The same as `solve`, but by raising the matrix of how pairs turn into other pairs to the
+/// power `nsteps`. This takes O(log(nsteps)) matrix multiplications, instead of `nsteps`
+/// steps.
+fn solve_matrix(
+    input_str: &PairCounts,
+    rules: &Rules,
+    nsteps: usize,
+    last_char: char,
+) -> Result<u128, String> {
+    let (all_pairs, transition, start) = pair_matrix(input_str, rules);
+    let power = checked_matrix_power(transition.view(), nsteps).ok_or_else(overflow_error)?;
+
+    let mut pairs: DensePairCounts = [0; N_PAIRS];
+    for (to, &(c1, c2)) in all_pairs.iter().enumerate() {
+        let mut total: u128 = 0;
+        for from in 0..all_pairs.len() {
+            let count = power[[to, from]]
+                .checked_mul(start[from])
+                .ok_or_else(overflow_error)?;
+            total = total.checked_add(count).ok_or_else(overflow_error)?;
+        }
+        pairs[pair_index(c1, c2)?] = total;
+    }
+
+    Ok(max_minus_min(&element_counts(&pairs, last_char)?))
+}
+
+/// How many of each element there are after `nsteps` steps, modulo `modulus`. The counts
+/// can't overflow, so this works for any number of steps, but they can't be compared to find
+/// the most and least common elements. Like `element_counts`, pairs whose count comes to
+/// zero are left out.
+fn element_counts_mod(
+    input_str: &PairCounts,
+    rules: &Rules,
+    nsteps: usize,
+    last_char: char,
+    modulus: u64,
+) -> Result<Histogram, String> {
+    if modulus == 0 {
+        return Err(String::from("The modulus must be positive"));
+    }
+    let (all_pairs, transition, start) = pair_matrix(input_str, rules);
+    let power = matrix_power_mod(transition.view(), nsteps, modulus);
+    let modulus = modulus as u128;
+
+    // Each pair counts its first element, and the last element is counted on top
+    let mut letter_count: Histogram = HashMap::new();
+    for (to, &(c1, _)) in all_pairs.iter().enumerate() {
+        let mut total: u128 = 0;
+        for from in 0..all_pairs.len() {
+            total = (total + power[[to, from]] * (start[from] % modulus)) % modulus;
+        }
+        if total > 0 {
+            let count = letter_count.entry(c1).or_insert(0);
+            *count = (*count + total) % modulus;
+        }
+    }
+    let count = letter_count.entry(last_char).or_insert(0);
+    *count = (*count + 1) % modulus;
+
+    Ok(letter_count)
+}
+
+/// The examples from the puzzle description
+const EXAMPLES: &[Example] = &[Example {
+    input: "NNCB
+
+CH -> B
+HH -> N
+CB -> H
+NH -> C
+HB -> C
+HC -> B
+HN -> C
+NN -> C
+BH -> H
+NC -> B
+NB -> B
+BN -> B
+BB -> N
+BC -> B
+CC -> N
+CN -> C",
+    part1: Some("1588"),
+    part2: Some("2188189693529"),
+}];
+
+/// Each part, solved straight from the input
+const PARTS: [Option<puzzle::Part>; 2] = [Some(solve_part1), Some(solve_part2)];
+
+/// The polymer template, which is the first line of the input
+fn template(input: &str) -> Result<&str, String> {
+    input
+        .trim_start()
+        .lines()
+        .next()
+        .map(str::trim_end)
+        .filter(|line| !line.is_empty())
+        .ok_or_else(|| "There is no polymer template".to_string())
+}
+
+fn last_letter(input: &str) -> Result<char, String> {
+    template(input)?
+        .chars()
+        .last()
+        .ok_or_else(|| "There is no polymer template".to_string())
+}
+
+fn solve_steps(input: &str, nsteps: usize) -> Result<String, String> {
+    let (pairs, rules) = parse_input(input);
+    solve(&pairs, &rules, nsteps, last_letter(input)?).map(|answer| answer.to_string())
+}
 
-    // SHOULD USE A HASHMAP, AND THEN ADD 1 TO THE COUNT OF THE LAST LETTER OF THE INPUT.
-    // SUM ONLY USING THE FIRST ITEM IN EACH HASHMAP
+fn solve_part1(input: &str) -> Result<String, String> {
+    solve_steps(input, 10)
+}
+
+fn solve_part2(input: &str) -> Result<String, String> {
+    solve_steps(input, 40)
+}
+
+/// Counting pairs, each checked against building the polymer
+const CROSSCHECKS: &[Crosscheck] = &[
+    Crosscheck {
+        name: "Part 1 by pair counts",
+        reference: expand_part1,
+        optimised: solve_part1,
+    },
+    Crosscheck {
+        name: "Part 1 by matrix power",
+        reference: expand_part1,
+        optimised: matrix_part1,
+    },
+];
+
+fn expand_part1(input: &str) -> Result<String, String> {
+    let (_, rules) = parse_input(input);
+    let histogram =
+        expand(template(input)?, &rules, 10)
+            .chars()
+            .fold(Histogram::new(), |mut acc, c| {
+                *acc.entry(c).or_insert(0) += 1;
+                acc
+            });
+    Ok(max_minus_min(&histogram).to_string())
+}
+
+fn matrix_part1(input: &str) -> Result<String, String> {
+    let (pairs, rules) = parse_input(input);
+    solve_matrix(&pairs, &rules, 10, last_letter(input)?).map(|answer| answer.to_string())
 }
 
 fn main() {
+    // Pass `--example` to check the solutions against the puzzle's examples instead
+    if std::env::args().any(|arg| arg == "--example") {
+        std::process::exit(puzzle::run_examples(EXAMPLES, PARTS));
+    }
+    // Pass `--crosscheck` to check the quick solutions against simple ones on random inputs
+    if std::env::args().any(|arg| arg == "--crosscheck") {
+        std::process::exit(crosscheck::run(14, CROSSCHECKS));
+    }
+
     let setup_time = std::time::Instant::now();
 
     let input_str =
         std::fs::read_to_string("input/day14.txt").expect("Failed to read day 14 input");
     let (input, rules) = parse_input(&input_str);
-    let last_letter = input_str
-        .lines()
-        .next()
-        .expect("Could not get first line from input string")
-        .chars()
-        .last()
-        .expect("Could not get last char of first line");
+    let last_letter = last_letter(&input_str).expect("The input is not valid");
+    let missing = compile_rules(&rules)
+        .and_then(|compiled| pairs_without_rules(&input, &compiled))
+        .expect("The input is not valid");
+    if !missing.is_empty() {
+        println!("These pairs have no insertion rule: {:?}", missing);
+    }
     println!("Setup took {:.6} µs", setup_time.elapsed().as_micros());
 
     // Part 1
     let part1_time = std::time::Instant::now();
-    let part1_result = solve(&input, &rules, 10, last_letter);
+    let part1_result = solve(&input, &rules, 10, last_letter).expect("Part 1 overflowed");
     println!("Part 1 took {:.6} µs", part1_time.elapsed().as_micros());
 
     // Part 2
     let part2_time = std::time::Instant::now();
-    let part2_result = solve(&input, &rules, 40, last_letter);
+    let part2_result = solve(&input, &rules, 40, last_letter).expect("Part 2 overflowed");
     println!("Part 2 took {:.6} µs", part2_time.elapsed().as_micros());
 
     println!();
     println!("Part 1 result: {}", part1_result);
     println!("Part 2 result: {}", part2_result);
+
+    // Pass `--histograms=N` to see how many of each element there are for the first N steps,
+    // or `--steps=N` to run any number of steps using matrix exponentiation
+    for arg in std::env::args() {
+        if let Some(nsteps) = arg.strip_prefix("--histograms=") {
+            let nsteps: usize = nsteps.parse().expect("Could not parse number of steps");
+            for (step_idx, histogram) in histograms(&input, &rules, last_letter)
+                .take(nsteps + 1)
+                .enumerate()
+            {
+                match histogram {
+                    Ok(histogram) => {
+                        let counts: Vec<String> = histogram
+                            .iter()
+                            .sorted()
+                            .map(|(c, n)| format!("{}={}", c, n))
+                            .collect();
+                        println!("Step {}: {}", step_idx, counts.join(" "));
+                    }
+                    Err(e) => println!("Step {}: {}", step_idx, e),
+                }
+            }
+        }
+        if let Some(nsteps) = arg.strip_prefix("--steps=") {
+            let nsteps = nsteps.parse().expect("Could not parse number of steps");
+            match solve_matrix(&input, &rules, nsteps, last_letter) {
+                Ok(result) => println!("After {} steps: {}", nsteps, result),
+                Err(e) => println!("After {} steps: {}", nsteps, e),
+            }
+        }
+        // Pass `--steps-mod=N,M` to count each element after N steps, modulo M. This never
+        // overflows, however many steps there are.
+        if let Some(args) = arg.strip_prefix("--steps-mod=") {
+            let (nsteps, modulus) = args
+                .split_once(',')
+                .expect("Expected the number of steps and the modulus");
+            let nsteps = nsteps.parse().expect("Could not parse number of steps");
+            let modulus = modulus.parse().expect("Could not parse modulus");
+            match element_counts_mod(&input, &rules, nsteps, last_letter, modulus) {
+                Ok(histogram) => {
+                    let counts: Vec<String> = histogram
+                        .iter()
+                        .sorted()
+                        .map(|(c, n)| format!("{}={}", c, n))
+                        .collect();
+                    println!(
+                        "After {} steps, mod {}: {}",
+                        nsteps,
+                        modulus,
+                        counts.join(" ")
+                    );
+                }
+                Err(e) => println!("After {} steps, mod {}: {}", nsteps, modulus, e),
+            }
+        }
+    }
 }
 
 #[test]
@@ -158,7 +571,7 @@ CC -> N
 CN -> C";
 
     let (input_str, rules) = parse_input(input_str);
-    let expected_str: HashMap<(char, char), usize> =
+    let expected_str: PairCounts =
         HashMap::from([(('N', 'N'), 1), (('N', 'C'), 1), (('C', 'B'), 1)]);
     let expected_rules = HashMap::from([
         (('C', 'H'), (('C', 'B'), ('B', 'H'))),
@@ -185,8 +598,7 @@ CN -> C";
 
 #[test]
 fn test_step_1() {
-    let input: HashMap<(char, char), usize> =
-        HashMap::from([(('N', 'N'), 1), (('N', 'C'), 1), (('C', 'B'), 1)]);
+    let input: PairCounts = HashMap::from([(('N', 'N'), 1), (('N', 'C'), 1), (('C', 'B'), 1)]);
     let rules = HashMap::from([
         (('C', 'H'), (('C', 'B'), ('B', 'H'))),
         (('H', 'H'), (('H', 'N'), ('N', 'H'))),
@@ -206,8 +618,14 @@ fn test_step_1() {
         (('C', 'N'), (('C', 'C'), ('C', 'N'))),
     ]);
 
-    let out = step(&input, &rules);
-    let expected: HashMap<(char, char), usize> = HashMap::from([
+    let mut out = [0; N_PAIRS];
+    step(
+        &dense_pair_counts(&input).unwrap(),
+        &compile_rules(&rules).unwrap(),
+        &mut out,
+    )
+    .unwrap();
+    let expected: PairCounts = HashMap::from([
         (('N', 'C'), 1),
         (('C', 'N'), 1),
         (('N', 'B'), 1),
@@ -216,13 +634,12 @@ fn test_step_1() {
         (('H', 'B'), 1),
     ]);
 
-    assert_eq!(expected, out);
+    assert_eq!(dense_pair_counts(&expected).unwrap(), out);
 }
 
 #[test]
 fn test_part1_1() {
-    let input: HashMap<(char, char), usize> =
-        HashMap::from([(('N', 'N'), 1), (('N', 'C'), 1), (('C', 'B'), 1)]);
+    let input: PairCounts = HashMap::from([(('N', 'N'), 1), (('N', 'C'), 1), (('C', 'B'), 1)]);
     let rules = HashMap::from([
         (('C', 'H'), (('C', 'B'), ('B', 'H'))),
         (('H', 'H'), (('H', 'N'), ('N', 'H'))),
@@ -243,13 +660,12 @@ fn test_part1_1() {
     ]);
 
     let got = solve(&input, &rules, 10, 'B');
-    assert_eq!(1588, got);
+    assert_eq!(Ok(1588), got);
 }
 
 #[test]
 fn test_part1_2() {
-    let input: HashMap<(char, char), usize> =
-        HashMap::from([(('N', 'N'), 1), (('N', 'C'), 1), (('C', 'B'), 1)]);
+    let input: PairCounts = HashMap::from([(('N', 'N'), 1), (('N', 'C'), 1), (('C', 'B'), 1)]);
     let rules = HashMap::from([
         (('C', 'H'), (('C', 'B'), ('B', 'H'))),
         (('H', 'H'), (('H', 'N'), ('N', 'H'))),
@@ -270,13 +686,12 @@ fn test_part1_2() {
     ]);
 
     let got = solve(&input, &rules, 2, 'B');
-    assert_eq!(5, got);
+    assert_eq!(Ok(5), got);
 }
 
 #[test]
 fn test_part2() {
-    let input: HashMap<(char, char), usize> =
-        HashMap::from([(('N', 'N'), 1), (('N', 'C'), 1), (('C', 'B'), 1)]);
+    let input: PairCounts = HashMap::from([(('N', 'N'), 1), (('N', 'C'), 1), (('C', 'B'), 1)]);
     let rules = HashMap::from([
         (('C', 'H'), (('C', 'B'), ('B', 'H'))),
         (('H', 'H'), (('H', 'N'), ('N', 'H'))),
@@ -297,7 +712,7 @@ fn test_part2() {
     ]);
 
     let got = solve(&input, &rules, 40, 'B');
-    assert_eq!(2_188_189_693_529, got);
+    assert_eq!(Ok(2_188_189_693_529), got);
 }
 
 #[test]
@@ -307,7 +722,7 @@ fn test_part1_actual() {
     let (input, rules) = parse_input(&input_str);
 
     let got = solve(&input, &rules, 10, 'K');
-    assert_eq!(2345, got);
+    assert_eq!(Ok(2345), got);
 }
 
 #[test]
@@ -317,5 +732,230 @@ fn test_part2_actual() {
     let (input, rules) = parse_input(&input_str);
 
     let got = solve(&input, &rules, 40, 'K');
-    assert_eq!(2432786807053, got);
+    assert_eq!(Ok(2432786807053), got);
+}
+
+#[test]
+fn test_histograms() {
+    let input_str = "NNCB
+
+CH -> B
+HH -> N
+CB -> H
+NH -> C
+HB -> C
+HC -> B
+HN -> C
+NN -> C
+BH -> H
+NC -> B
+NB -> B
+BN -> B
+BB -> N
+BC -> B
+CC -> N
+CN -> C";
+    let (input, rules) = parse_input(input_str);
+    let got: Vec<Histogram> = histograms(&input, &rules, 'B')
+        .take(11)
+        .collect::<Result<_, _>>()
+        .unwrap();
+
+    assert_eq!(
+        HashMap::from([('N', 2), ('C', 1), ('B', 1)]),
+        got[0],
+        "NNCB"
+    );
+    assert_eq!(
+        HashMap::from([('N', 2), ('C', 2), ('B', 2), ('H', 1)]),
+        got[1],
+        "NCNBCHB"
+    );
+    assert_eq!(
+        HashMap::from([('B', 1749), ('C', 298), ('H', 161), ('N', 865)]),
+        got[10]
+    );
+
+    // The polymer roughly doubles in length every step
+    for (step_idx, histogram) in got.iter().enumerate() {
+        let length: u128 = histogram.values().sum();
+        assert_eq!(3 * 2_u128.pow(step_idx as u32) + 1, length);
+    }
+}
+
+#[test]
+fn test_overflow() {
+    let input_str =
+        std::fs::read_to_string("input/day14.txt").expect("Failed to read day 14 input");
+    let (input, rules) = parse_input(&input_str);
+
+    // The polymer doubles in length every step, so it can't fit in a u128 after about 128
+    // steps
+    assert_eq!(Err(overflow_error()), solve(&input, &rules, 200, 'K'));
+    let n_ok = histograms(&input, &rules, 'K')
+        .take_while(|histogram| histogram.is_ok())
+        .count();
+    assert!(n_ok > 100 && n_ok < 130);
+    assert_eq!(
+        Some(Err(overflow_error())),
+        histograms(&input, &rules, 'K').nth(n_ok)
+    );
+    assert_eq!(None, histograms(&input, &rules, 'K').nth(n_ok + 1));
+
+    // The matrix version finds out quickly too
+    assert_eq!(
+        Err(overflow_error()),
+        solve_matrix(&input, &rules, 10_000, 'K')
+    );
+
+    // Working modulo a prime, it can go as far as you like
+    let counts = element_counts_mod(&input, &rules, 10_000, 'K', 1_000_000_007).unwrap();
+    let expected: Histogram = [
+        ('B', 189056025),
+        ('C', 716500553),
+        ('F', 158553702),
+        ('H', 805265573),
+        ('K', 584379923),
+        ('N', 380032255),
+        ('O', 306064209),
+        ('P', 731535887),
+        ('S', 739413927),
+        ('V', 595822158),
+    ]
+    .into_iter()
+    .collect();
+    assert_eq!(expected, counts);
+}
+
+#[test]
+fn test_element_counts_mod() {
+    let input_str =
+        std::fs::read_to_string("input/day14.txt").expect("Failed to read day 14 input");
+    let (input, rules) = parse_input(&input_str);
+
+    // With a modulus bigger than every count, these are the exact counts
+    for nsteps in [0, 1, 10, 40] {
+        assert_eq!(
+            histograms(&input, &rules, 'K').nth(nsteps).unwrap(),
+            element_counts_mod(&input, &rules, nsteps, 'K', u64::MAX)
+        );
+    }
+    assert!(element_counts_mod(&input, &rules, 10, 'K', 0).is_err());
+}
+
+#[test]
+fn test_element_counts_overflow() {
+    // The last letter is counted on top of the pairs it starts
+    let mut pairs: DensePairCounts = [0; N_PAIRS];
+    pairs[pair_index('A', 'B').unwrap()] = u128::MAX;
+    assert_eq!(Err(overflow_error()), element_counts(&pairs, 'A'));
+    assert_eq!(Some(&1), element_counts(&pairs, 'B').unwrap().get(&'B'));
+}
+
+#[test]
+fn test_solve_matrix() {
+    let input_str =
+        std::fs::read_to_string("input/day14.txt").expect("Failed to read day 14 input");
+    let (input, rules) = parse_input(&input_str);
+
+    assert_eq!(Ok(2345), solve_matrix(&input, &rules, 10, 'K'));
+    assert_eq!(Ok(2432786807053), solve_matrix(&input, &rules, 40, 'K'));
+    for nsteps in [0, 1, 2, 3, 17, 64, 100] {
+        assert_eq!(
+            solve(&input, &rules, nsteps, 'K'),
+            solve_matrix(&input, &rules, nsteps, 'K')
+        );
+    }
+}
+
+#[test]
+fn test_pair_index() {
+    assert_eq!(Ok(0), pair_index('A', 'A'));
+    assert_eq!(Ok(27), pair_index('B', 'B'));
+    assert_eq!(Ok(N_PAIRS - 1), pair_index('Z', 'Z'));
+    for idx in 0..N_PAIRS {
+        let (c1, c2) = pair_chars(idx);
+        assert_eq!(Ok(idx), pair_index(c1, c2));
+    }
+}
+
+#[test]
+fn test_invalid_elements() {
+    let (input, rules) = parse_input("NNCB\n\nNN -> c\nNC -> B\nCB -> H");
+    assert_eq!(
+        Err(String::from(
+            "In rule NN -> c: 'c' is not an uppercase element"
+        )),
+        compile_rules(&rules).map(|_| ())
+    );
+    assert_eq!(
+        Err(compile_rules(&rules).unwrap_err()),
+        solve(&input, &rules, 1, 'B')
+    );
+
+    let (input, rules) = parse_input("NN1\n\nNN -> C");
+    assert_eq!(
+        Err(String::from("'1' is not an uppercase element")),
+        solve(&input, &rules, 1, '1')
+    );
+}
+
+#[test]
+fn test_pairs_without_rules() {
+    // AB becomes ACB, but there are no rules for AC or CB, so nothing more happens
+    let (input, rules) = parse_input("AB\n\nAB -> C");
+    let compiled = compile_rules(&rules).unwrap();
+    assert_eq!(
+        Ok(vec![('A', 'C'), ('C', 'B')]),
+        pairs_without_rules(&input, &compiled)
+    );
+
+    let got: Vec<Histogram> = histograms(&input, &rules, 'B')
+        .take(3)
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(HashMap::from([('A', 1), ('B', 1)]), got[0]);
+    assert_eq!(HashMap::from([('A', 1), ('C', 1), ('B', 1)]), got[1]);
+    assert_eq!(got[1], got[2]);
+    assert_eq!(Ok(0), solve(&input, &rules, 5, 'B'));
+    assert_eq!(Ok(0), solve_matrix(&input, &rules, 5, 'B'));
+
+    // The real input has a rule for every pair
+    let input_str =
+        std::fs::read_to_string("input/day14.txt").expect("Failed to read day 14 input");
+    let (input, rules) = parse_input(&input_str);
+    let compiled = compile_rules(&rules).unwrap();
+    assert_eq!(Ok(vec![]), pairs_without_rules(&input, &compiled));
+}
+
+#[test]
+fn test_parse_input_crlf() {
+    let input_str = "NNCB\n\nCH -> B\nHH -> N\n";
+    let windows_str = "NNCB  \r\n\r\n\r\nCH -> B\r\nHH -> N\r\n\r\n";
+    assert_eq!(parse_input(input_str), parse_input(windows_str));
+}
+
+#[test]
+fn test_examples() {
+    for check in puzzle::check_examples(EXAMPLES, PARTS) {
+        assert!(check.is_ok(), "{}", check);
+    }
+}
+
+#[test]
+fn test_expand() {
+    let (_, rules) = parse_input(EXAMPLES[0].input);
+    assert_eq!("NCNBCHB", expand("NNCB", &rules, 1));
+    assert_eq!(
+        "NBBNBNBBCCNBCNCCNBBNBBNBBBNBBNBBCBHCBHHNHCBBCBHCB",
+        expand("NNCB", &rules, 4)
+    );
+    assert_eq!("1588", expand_part1(EXAMPLES[0].input).unwrap());
+}
+
+#[test]
+fn test_crosschecks() {
+    for check in CROSSCHECKS {
+        assert_eq!(Ok(None), check.find_disagreement(14, 5, Some(10)));
+    }
 }
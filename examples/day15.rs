@@ -1,5 +1,7 @@
 use std::collections::BinaryHeap;
 
+use aoc_2021_rs::puzzle::{self, Example};
+
 use ndarray::{concatenate, Array2, ArrayView2, Axis};
 
 fn parse_input(input: &str) -> Array2<u8> {
@@ -143,7 +145,47 @@ fn quintuple_map(arr: ArrayView2<u8>) -> Array2<u8> {
     concatenate![Axis(1), c1, c2, c3, c4, c5]
 }
 
+/// The examples from the puzzle description
+const EXAMPLES: &[Example] = &[Example {
+    input: "1163751742
+1381373672
+2136511328
+3694931569
+7463417111
+1319128137
+1359912421
+3125421639
+1293138521
+2311944581",
+    part1: Some("40"),
+    part2: Some("315"),
+}];
+
+/// Each part, solved straight from the input
+const PARTS: [Option<puzzle::Part>; 2] = [Some(solve_part1), Some(solve_part2)];
+
+fn lowest_total_risk(arr: ArrayView2<u8>) -> String {
+    let goal = (arr.nrows() - 1, arr.ncols() - 1);
+    let (costs, _) = uniform_cost_search(arr, (0, 0), goal);
+    costs[goal].to_string()
+}
+
+fn solve_part1(input: &str) -> Result<String, String> {
+    Ok(lowest_total_risk(parse_input(input).view()))
+}
+
+fn solve_part2(input: &str) -> Result<String, String> {
+    Ok(lowest_total_risk(
+        quintuple_map(parse_input(input).view()).view(),
+    ))
+}
+
 fn main() {
+    // Pass `--example` to check the solutions against the puzzle's examples instead
+    if std::env::args().any(|arg| arg == "--example") {
+        std::process::exit(puzzle::run_examples(EXAMPLES, PARTS));
+    }
+
     let setup_time = std::time::Instant::now();
 
     let input_str =
@@ -399,4 +441,11 @@ mod tests {
         let got = costs[goal];
         assert_eq!(315, got);
     }
+
+    #[test]
+    fn test_examples() {
+        for check in puzzle::check_examples(EXAMPLES, PARTS) {
+            assert!(check.is_ok(), "{}", check);
+        }
+    }
 }
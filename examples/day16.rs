@@ -1,5 +1,7 @@
 use std::rc::Rc;
 
+use aoc_2021_rs::puzzle::{self, Example};
+
 enum PacketContents {
     LiteralValue(usize),
     Operator { op_type: u8, packets: Vec<Packet> },
@@ -98,7 +100,79 @@ fn to_decimal(s: &str) -> usize {
     usize::from_str_radix(s, 2).expect("Could not parse binary digit")
 }
 
+/// The examples from the puzzle description, each a single transmission
+const EXAMPLES: &[Example] = &[
+    Example {
+        input: "8A004A801A8002F478",
+        part1: Some("16"),
+        part2: None,
+    },
+    Example {
+        input: "620080001611562C8802118E34",
+        part1: Some("12"),
+        part2: None,
+    },
+    Example {
+        input: "C0015000016115A2E0802F182340",
+        part1: Some("23"),
+        part2: None,
+    },
+    Example {
+        input: "A0016C880162017C3686B18A3D4780",
+        part1: Some("31"),
+        part2: None,
+    },
+    Example {
+        input: "C200B40A82",
+        part1: None,
+        part2: Some("3"),
+    },
+    Example {
+        input: "04005AC33890",
+        part1: None,
+        part2: Some("54"),
+    },
+    Example {
+        input: "880086C3E88112",
+        part1: None,
+        part2: Some("7"),
+    },
+    Example {
+        input: "CE00C43D881120",
+        part1: None,
+        part2: Some("9"),
+    },
+    Example {
+        input: "D8005AC2A8F0",
+        part1: None,
+        part2: Some("1"),
+    },
+    Example {
+        input: "F600BC2D8F",
+        part1: None,
+        part2: Some("0"),
+    },
+    Example {
+        input: "9C005AC2F8F0",
+        part1: None,
+        part2: Some("0"),
+    },
+    Example {
+        input: "9C0141080250320F1802104A08",
+        part1: None,
+        part2: Some("1"),
+    },
+];
+
+/// Neither part is solved yet
+const PARTS: [Option<puzzle::Part>; 2] = [None, None];
+
 fn main() {
+    // Pass `--example` to check the solutions against the puzzle's examples instead
+    if std::env::args().any(|arg| arg == "--example") {
+        std::process::exit(puzzle::run_examples(EXAMPLES, PARTS));
+    }
+
     let s = "110100101111111000101000";
     let version = u8::from_str_radix(&s[..3], 2).expect("Could not parse packet version");
     dbg!(version);
@@ -139,3 +213,10 @@ fn test_hex_parse_3() {
 
 //     let got = parse_packet(bin);
 // }
+
+#[test]
+fn test_examples() {
+    for check in puzzle::check_examples(EXAMPLES, PARTS) {
+        assert!(check.is_ok(), "{}", check);
+    }
+}
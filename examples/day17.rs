@@ -1,3 +1,5 @@
+use aoc_2021_rs::parallel;
+use aoc_2021_rs::puzzle::{self, Example};
 use itertools::Itertools;
 
 fn parse_input(input: &str) -> Area {
@@ -133,6 +135,7 @@ impl Area {
 }
 
 impl ProbeState {
+    #[cfg(test)]
     fn will_hit_area(&self, area: &Area) -> bool {
         // If we are going in the wrong x direction, we will never hit the area
         if self.x.vel.signum() != area.x_high.signum() {
@@ -164,99 +167,186 @@ impl ProbeState {
     }
 }
 
-fn part1(area: &Area) -> i64 {
-    // Get the minimum x veloctiy that will hit the area
-    let min_goal = area.x_low as f64;
-    // The sum from 1 to n is n(n+1)/2. So the to reach the goal we need to
-    // find n such that n(n+1)/2 >= goal. That's the minimum x velocity
-    let min_x_vel = (0.5 * ((8.0 * min_goal + 1.0).sqrt() - 1.0)).ceil() as i64;
-
-    // Get the maximum x velocity that will hit the area
-    let max_goal = area.x_high as f64;
-    let max_x_vel = (0.5 * ((8.0 * max_goal + 1.0).sqrt() - 1.0)).ceil() as i64;
-
-    // Since we are going for height, don't go lower than 0
-    let min_y_vel = 0;
-
-    // The highest y velocity is abs(area.y_low) - 1
-    let max_y_vel = area.y_low.abs() - 1;
-
-    // Work downwards to find the min y velocity that will hit the area
-    let result = (min_y_vel..=max_y_vel).into_iter().rev().find_map(|y_vel| {
-        // Try each x velocity
-        (min_x_vel..=max_x_vel).into_iter().find_map(|x_vel| {
-            // Create a ProbeState with the given x and y velocities
-            let probe = ProbeState {
-                x: XState {
-                    pos: 0,
-                    vel: x_vel,
-                    target_max_range: area.x_high,
-                },
-                y: YState {
-                    pos: 0,
-                    vel: y_vel,
-                    target_bottom: area.y_low,
-                },
-            };
-
-            // Check if the probe will hit the area
-            if probe.will_hit_area(area) {
-                // If so, return the max height
-                probe.max_height()
-            } else {
-                None
-            }
-        })
-    });
+/// The smallest x velocity that will reach the start of the area before drag stops the probe.
+/// The probe can travel at most `sum_to_n(x_vel)` in x, so this is the smallest `n` where
+/// that reaches `x_low`.
+fn min_x_vel(area: &Area) -> i64 {
+    (0..)
+        .find(|&n| sum_to_n(n) >= area.x_low)
+        .expect("Could not find a minimum x velocity")
+}
+
+/// The first and last step on which an x velocity puts the probe within the x range of the
+/// area. The last step is `None` if drag stops the probe inside the area, so it stays there
+/// forever.
+fn x_steps_in_area(x_vel: i64, area: &Area) -> Option<(i64, Option<i64>)> {
+    let mut pos = 0;
+    let mut vel = x_vel;
+    let mut first = None;
+    let mut last = None;
+
+    for step in 1.. {
+        if vel == 0 {
+            // The probe has stopped, and will stay wherever it is now
+            return first.map(|first| {
+                let stays_inside = pos >= area.x_low && pos <= area.x_high;
+                (first, if stays_inside { None } else { last })
+            });
+        }
+        pos += vel;
+        vel -= 1;
+        if pos > area.x_high {
+            break;
+        }
+        if pos >= area.x_low {
+            first.get_or_insert(step);
+            last = Some(step);
+        }
+    }
 
-    result.expect("Could not find a solution for part 1")
+    first.map(|first| (first, last))
 }
 
-fn get_all_possible_vels(area: &Area) -> impl Iterator<Item = (i64, i64)> + '_ {
-    // The minimum x velocity that will hit the area
-    let min_goal = area.x_low as f64;
-    // The sum from 1 to n is n(n+1)/2. So the to reach the goal we need to
-    // find n such that n(n+1)/2 >= goal. That's the minimum x velocity
-    let min_x_vel = (0.5 * ((8.0 * min_goal + 1.0).sqrt() - 1.0)).ceil() as i64;
+/// The first and last step on which a y velocity puts the probe within the y range of the
+/// area
+fn y_steps_in_area(y_vel: i64, area: &Area) -> Option<(i64, i64)> {
+    let mut pos = 0;
+    let mut vel = y_vel;
+    let mut first = None;
+    let mut last = None;
+
+    for step in 1.. {
+        pos += vel;
+        vel -= 1;
+        if pos < area.y_low {
+            break;
+        }
+        if pos <= area.y_high {
+            first.get_or_insert(step);
+            last = Some(step);
+        }
+    }
+
+    first.zip(last)
+}
 
+/// Every x velocity that ever has the probe within the x range of the area, along with the
+/// steps on which it does
+fn x_vels_in_area(area: &Area) -> Vec<(i64, (i64, Option<i64>))> {
     // The maximum x velocity that will hit the area is a speed where the first step
     // will hit the x_high limit
-    let max_x_vel = area.x_high;
-
-    // The lowest y velocity that will hit the area is one where the first step will
-    // just hit the y_low limit
-    let min_y_vel = area.y_low;
-
-    // The highest y velocity is abs(min_y_vel) - 1
-    let max_y_vel = min_y_vel.abs() - 1;
-
-    // Iterate over each possible x velocity with each possible y velocity
-    (min_x_vel..=max_x_vel)
-        .into_iter()
-        .flat_map(move |x_vel| {
-            (min_y_vel..=max_y_vel)
-                .into_iter()
-                .map(move |y_vel| (x_vel, y_vel))
+    (min_x_vel(area)..=area.x_high)
+        .filter_map(|x_vel| x_steps_in_area(x_vel, area).map(|steps| (x_vel, steps)))
+        .collect()
+}
+
+/// All of the x velocities that land in the area on the same step as `y_vel` does
+fn matching_x_vels<'a>(
+    y_vel: i64,
+    x_vels: &'a [(i64, (i64, Option<i64>))],
+    area: &Area,
+) -> impl Iterator<Item = i64> + 'a {
+    let y_steps = y_steps_in_area(y_vel, area);
+    x_vels
+        .iter()
+        .filter(move |(_, (x_first, x_last))| match y_steps {
+            Some((y_first, y_last)) => {
+                *x_first <= y_last && x_last.is_none_or(|x_last| y_first <= x_last)
+            }
+            None => false,
         })
-        // Filter out the velocity pairs that will never hit the area
-        .filter(|(xv, yv)| {
-            let probe = ProbeState {
-                x: XState {
-                    pos: 0,
-                    vel: *xv,
-                    target_max_range: area.x_high,
-                },
-                y: YState {
-                    pos: 0,
-                    vel: *yv,
-                    target_bottom: area.y_low,
-                },
-            };
-            probe.will_hit_area(area)
+        .map(|(x_vel, _)| *x_vel)
+}
+
+fn part1(area: &Area) -> i64 {
+    let x_vels = x_vels_in_area(area);
+
+    // The highest y velocity is abs(area.y_low) - 1, since anything faster will skip
+    // straight past the area on the way back down through y = 0. Work downwards to find the
+    // first y velocity that can hit the area.
+    let (x_vel, y_vel) = (0..area.y_low.abs())
+        .rev()
+        .find_map(|y_vel| {
+            matching_x_vels(y_vel, &x_vels, area)
+                .next()
+                .map(|x_vel| (x_vel, y_vel))
         })
+        .expect("Could not find a solution for part 1");
+
+    probe_with_vel(x_vel, y_vel, area)
+        .max_height()
+        .expect("Could not find the max height")
+}
+
+/// Every velocity that hits the area, in order of y velocity. Each y velocity is matched up
+/// with x velocities on its own, so they are shared out between `jobs` threads.
+fn get_all_possible_vels(area: &Area, jobs: usize) -> Vec<(i64, i64)> {
+    let x_vels = x_vels_in_area(area);
+
+    // The lowest y velocity that will hit the area is one where the first step will
+    // just hit the y_low limit, and the highest is abs(y_low) - 1
+    let y_vels: Vec<i64> = (area.y_low..area.y_low.abs()).collect();
+    parallel::map(&y_vels, jobs, |&y_vel| {
+        matching_x_vels(y_vel, &x_vels, area)
+            .map(move |x_vel| (x_vel, y_vel))
+            .collect::<Vec<_>>()
+    })
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+/// Create a probe fired from the origin with the given velocity
+fn probe_with_vel(x_vel: i64, y_vel: i64, area: &Area) -> ProbeState {
+    ProbeState {
+        x: XState {
+            pos: 0,
+            vel: x_vel,
+            target_max_range: area.x_high,
+        },
+        y: YState {
+            pos: 0,
+            vel: y_vel,
+            target_bottom: area.y_low,
+        },
+    }
+}
+
+/// The full trajectory of a probe as CSV, one row per step, until it has passed the area
+fn trajectory_csv(x_vel: i64, y_vel: i64, area: &Area) -> String {
+    let mut csv = String::from("step,x,y,in_area\n0,0,0,false\n");
+    for (step, (x, y)) in probe_with_vel(x_vel, y_vel, area).enumerate() {
+        csv += &format!("{},{},{},{}\n", step + 1, x, y, area.contains(x, y));
+    }
+    csv
+}
+
+/// The examples from the puzzle description
+const EXAMPLES: &[Example] = &[Example {
+    input: "target area: x=20..30, y=-10..-5",
+    part1: Some("45"),
+    part2: Some("112"),
+}];
+
+/// Each part, solved straight from the input
+const PARTS: [Option<puzzle::Part>; 2] = [Some(solve_part1), Some(solve_part2)];
+
+fn solve_part1(input: &str) -> Result<String, String> {
+    Ok(part1(&parse_input(input)).to_string())
+}
+
+fn solve_part2(input: &str) -> Result<String, String> {
+    Ok(get_all_possible_vels(&parse_input(input), parallel::jobs())
+        .len()
+        .to_string())
 }
 
 fn main() {
+    // Pass `--example` to check the solutions against the puzzle's examples instead
+    if std::env::args().any(|arg| arg == "--example") {
+        std::process::exit(puzzle::run_examples(EXAMPLES, PARTS));
+    }
+
     let setup_time = std::time::Instant::now();
 
     let input_str =
@@ -269,14 +359,37 @@ fn main() {
     let part1_result = part1(&area);
     println!("Part 1 took {:.6} ns", part1_time.elapsed().as_nanos());
 
-    // Part 2
+    // Part 2. Pass `--jobs=N` to search with N threads, rather than one per core.
     let part2_time = std::time::Instant::now();
-    let part2_result = get_all_possible_vels(&area).count();
+    let part2_result = get_all_possible_vels(&area, parallel::jobs()).len();
     println!("Part 2 took {:.6} µs", part2_time.elapsed().as_micros());
 
     println!();
     println!("Part 1 result: {}", part1_result);
     println!("Part 2 result: {}", part2_result);
+
+    // Pass `--trajectory=X,Y` to get the path of a probe fired with that velocity as CSV
+    for arg in std::env::args() {
+        if let Some(vel) = arg.strip_prefix("--trajectory=") {
+            let (x_vel, y_vel) = vel
+                .split_once(',')
+                .expect("Trajectory velocity should look like `X,Y`");
+            let x_vel = x_vel.parse().expect("Could not parse x velocity");
+            let y_vel = y_vel.parse().expect("Could not parse y velocity");
+            println!();
+            print!("{}", trajectory_csv(x_vel, y_vel, &area));
+        }
+    }
+}
+
+/// The original search, which fires every velocity and watches where it goes. Kept to check
+/// the analytical solver against.
+#[cfg(test)]
+fn get_all_possible_vels_brute_force(area: &Area) -> Vec<(i64, i64)> {
+    (min_x_vel(area)..=area.x_high)
+        .flat_map(|x_vel| (area.y_low..area.y_low.abs()).map(move |y_vel| (x_vel, y_vel)))
+        .filter(|&(x_vel, y_vel)| probe_with_vel(x_vel, y_vel, area).will_hit_area(area))
+        .collect()
 }
 
 #[test]
@@ -662,7 +775,7 @@ fn test_get_all_possible_vels() {
     expected_vels.dedup();
     expected_vels.sort();
 
-    let mut got: Vec<(i64, i64)> = get_all_possible_vels(&area).collect();
+    let mut got: Vec<(i64, i64)> = get_all_possible_vels(&area, 4);
     got.sort();
 
     assert_eq!(expected_vels, got);
@@ -677,7 +790,7 @@ fn test_part2() {
         y_high: -5,
     };
 
-    let got = get_all_possible_vels(&area).count();
+    let got = get_all_possible_vels(&area, 4).len();
     assert_eq!(112, got);
 }
 
@@ -687,6 +800,109 @@ fn test_part2_actual() {
         std::fs::read_to_string("input/day17.txt").expect("Failed to read day 17 input");
     let area = parse_input(&input_str);
 
-    let got = get_all_possible_vels(&area).count();
+    let got = get_all_possible_vels(&area, 4).len();
     assert_eq!(4748, got);
 }
+
+#[test]
+fn test_min_x_vel() {
+    let area = Area {
+        x_low: 20,
+        x_high: 30,
+        y_low: -10,
+        y_high: -5,
+    };
+    assert_eq!(6, min_x_vel(&area));
+
+    // 1 + 2 + ... + 6 is exactly 21
+    let area = Area { x_low: 21, ..area };
+    assert_eq!(6, min_x_vel(&area));
+    let area = Area { x_low: 22, ..area };
+    assert_eq!(7, min_x_vel(&area));
+}
+
+#[test]
+fn test_steps_in_area() {
+    let area = Area {
+        x_low: 20,
+        x_high: 30,
+        y_low: -10,
+        y_high: -5,
+    };
+
+    // x = 6 goes 6, 11, 15, 18, 20, 21, and stops there
+    assert_eq!(Some((5, None)), x_steps_in_area(6, &area));
+    // x = 9 goes 9, 17, 24, 30, 35
+    assert_eq!(Some((3, Some(4))), x_steps_in_area(9, &area));
+    // x = 5 stops short at 15
+    assert_eq!(None, x_steps_in_area(5, &area));
+
+    // y = 2 goes 2, 3, 3, 2, 0, -3, -7, -12
+    assert_eq!(Some((7, 7)), y_steps_in_area(2, &area));
+    // y = 10 goes straight past the area
+    assert_eq!(None, y_steps_in_area(10, &area));
+}
+
+#[test]
+fn test_matches_brute_force() {
+    let input_str =
+        std::fs::read_to_string("input/day17.txt").expect("Failed to read day 17 input");
+    let areas = [
+        parse_input(&input_str),
+        Area {
+            x_low: 20,
+            x_high: 30,
+            y_low: -10,
+            y_high: -5,
+        },
+        Area {
+            x_low: 1,
+            x_high: 3,
+            y_low: -3,
+            y_high: -1,
+        },
+    ];
+
+    for area in &areas {
+        let mut expected = get_all_possible_vels_brute_force(area);
+        expected.sort_unstable();
+        let got_in_order = get_all_possible_vels(area, 1);
+        let mut got = got_in_order.clone();
+        got.sort_unstable();
+        assert_eq!(expected, got);
+
+        // Sharing out the work doesn't change the answer, or the order it comes out in
+        for jobs in [2, 3, 8] {
+            assert_eq!(got_in_order, get_all_possible_vels(area, jobs));
+        }
+    }
+}
+
+#[test]
+fn test_trajectory_csv() {
+    let area = Area {
+        x_low: 20,
+        x_high: 30,
+        y_low: -10,
+        y_high: -5,
+    };
+    let expected = "step,x,y,in_area
+0,0,0,false
+1,7,2,false
+2,13,3,false
+3,18,3,false
+4,22,2,false
+5,25,0,false
+6,27,-3,false
+7,28,-7,true
+8,28,-12,false
+";
+    assert_eq!(expected, trajectory_csv(7, 2, &area));
+}
+
+#[test]
+fn test_examples() {
+    for check in puzzle::check_examples(EXAMPLES, PARTS) {
+        assert!(check.is_ok(), "{}", check);
+    }
+}
@@ -1,5 +1,7 @@
 use std::fmt::Display;
 
+use aoc_2021_rs::puzzle::{self, Example};
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 enum SingleSfn {
     Number(usize),
@@ -110,7 +112,31 @@ fn parse_input(input: &str) -> Vec<Sfn> {
     input.lines().map(Sfn::from).collect()
 }
 
+/// The examples from the puzzle description
+const EXAMPLES: &[Example] = &[Example {
+    input: "[[[0,[5,8]],[[1,7],[9,6]]],[[4,[1,2]],[[1,4],2]]]
+[[[5,[2,8]],4],[5,[[9,9],0]]]
+[6,[[[6,2],[5,6]],[[7,6],[4,7]]]]
+[[[6,[0,7]],[0,9]],[4,[9,[9,0]]]]
+[[[7,[6,4]],[3,[1,3]]],[[[5,5],1],9]]
+[[6,[[7,3],[3,2]]],[[[3,8],[5,7]],4]]
+[[[[5,4],[7,7]],8],[[8,3],8]]
+[[9,3],[[9,9],[6,[4,9]]]]
+[[2,[[7,7],7]],[[5,8],[[9,3],[0,2]]]]
+[[[[5,2],5],[8,[3,7]]],[[5,[7,5]],[4,4]]]",
+    part1: Some("4140"),
+    part2: Some("3993"),
+}];
+
+/// Neither part is solved yet
+const PARTS: [Option<puzzle::Part>; 2] = [None, None];
+
 fn main() {
+    // Pass `--example` to check the solutions against the puzzle's examples instead
+    if std::env::args().any(|arg| arg == "--example") {
+        std::process::exit(puzzle::run_examples(EXAMPLES, PARTS));
+    }
+
     let setup_time = std::time::Instant::now();
 
     let input_str =
@@ -425,4 +451,11 @@ mod tests {
     //     let got = sfn.explode();
     //     assert_eq!(want, got);
     // }
+
+    #[test]
+    fn test_examples() {
+        for check in puzzle::check_examples(EXAMPLES, PARTS) {
+            assert!(check.is_ok(), "{}", check);
+        }
+    }
 }
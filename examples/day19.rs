@@ -1,20 +1,20 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{HashMap, HashSet, VecDeque},
     ops::{Add, Div, Mul, Sub},
 };
 
+use aoc_2021_rs::parallel;
+use aoc_2021_rs::parsing;
+use aoc_2021_rs::puzzle::{self, Example};
 use itertools::Itertools;
 
 fn parse_input(input: &str) -> HashMap<usize, Vec<Point>> {
-    // Split on double newlines
-    input
-        .split("\n\n")
+    // Each scanner is its own section
+    parsing::sections(input)
+        .iter()
         .map(|scanner| {
-            let mut scanner_lines = scanner.lines();
             // The first line is the scanner number
-            let scanner_number = scanner_lines
-                .next()
-                .expect("Could not get first line containing scanner number")
+            let scanner_number = scanner[0]
                 // Split on spaces, and grab the item at index 2
                 .split_whitespace()
                 .nth(2)
@@ -24,30 +24,11 @@ fn parse_input(input: &str) -> HashMap<usize, Vec<Point>> {
                 .expect("Could not parse scanner number");
 
             // The rest of the lines are Points
-            let mut ps = scanner_lines
-                // Map each line to a Point
+            let mut ps = scanner[1..]
+                .iter()
                 .map(|line| {
-                    let mut nums = line.trim().split(',');
-                    // Grab the first item
-                    let x: i64 = nums
-                        .next()
-                        .expect("Could not get x coordinate")
-                        .parse()
-                        .expect("Could not parse x coordinate");
-                    // Grab the second item
-                    let y: i64 = nums
-                        .next()
-                        .expect("Could not get y coordinate")
-                        .parse()
-                        .expect("Could not parse y coordinate");
-                    // Grab the third item
-                    let z: i64 = nums
-                        .next()
-                        .expect("Could not get z coordinate")
-                        .parse()
-                        .expect("Could not parse z coordinate");
-                    // Return the Point
-                    Point { 0: x, 1: y, 2: z }
+                    let (x, y, z) = parsing::triple(line).expect("Could not parse a point");
+                    Point(x, y, z)
                 })
                 .collect::<Vec<Point>>();
             ps.sort_unstable();
@@ -64,11 +45,7 @@ impl Add for Point {
     type Output = Self;
 
     fn add(self, other: Self) -> Self {
-        Self {
-            0: self.0 + other.0,
-            1: self.1 + other.1,
-            2: self.2 + other.2,
-        }
+        Self(self.0 + other.0, self.1 + other.1, self.2 + other.2)
     }
 }
 
@@ -76,165 +53,473 @@ impl Sub for Point {
     type Output = Self;
 
     fn sub(self, rhs: Self) -> Self {
-        Self {
-            0: self.0 - rhs.0,
-            1: self.1 - rhs.1,
-            2: self.2 - rhs.2,
-        }
+        Self(self.0 - rhs.0, self.1 - rhs.1, self.2 - rhs.2)
     }
 }
 
 impl Mul for Point {
     type Output = Self;
     fn mul(self, rhs: Self) -> Self {
-        Self {
-            0: self.0 * rhs.0,
-            1: self.1 * rhs.1,
-            2: self.2 * rhs.2,
-        }
+        Self(self.0 * rhs.0, self.1 * rhs.1, self.2 * rhs.2)
     }
 }
 
 impl Div for Point {
     type Output = Self;
     fn div(self, rhs: Self) -> Self {
-        Self {
-            0: self.0 / rhs.0,
-            1: self.1 / rhs.1,
-            2: self.2 / rhs.2,
-        }
+        Self(self.0 / rhs.0, self.1 / rhs.1, self.2 / rhs.2)
     }
 }
 
 fn rotate_z_90(p: &Point) -> Point {
-    Point {
-        0: -p.1,
-        1: p.0,
-        2: p.2,
-    }
+    Point(-p.1, p.0, p.2)
 }
 
 fn rotate_z_180(p: &Point) -> Point {
-    Point {
-        0: -p.0,
-        1: -p.1,
-        2: p.2,
-    }
+    Point(-p.0, -p.1, p.2)
 }
 
 fn rotate_z_270(p: &Point) -> Point {
-    Point {
-        0: p.1,
-        1: -p.0,
-        2: p.2,
-    }
+    Point(p.1, -p.0, p.2)
 }
 
 fn rotate_y_90(p: &Point) -> Point {
-    Point {
-        0: p.2,
-        1: p.1,
-        2: -p.0,
-    }
+    Point(p.2, p.1, -p.0)
 }
 
 fn rotate_y_180(p: &Point) -> Point {
-    Point {
-        0: -p.0,
-        1: p.1,
-        2: -p.2,
-    }
+    Point(-p.0, p.1, -p.2)
 }
 
 fn rotate_y_270(p: &Point) -> Point {
-    Point {
-        0: -p.2,
-        1: p.1,
-        2: p.0,
-    }
+    Point(-p.2, p.1, p.0)
 }
 
 fn rotate_0(p: &Point) -> Point {
-    p.clone()
+    *p
 }
 
 fn rotate_x_90(p: &Point) -> Point {
-    Point {
-        0: p.0,
-        1: -p.2,
-        2: p.1,
-    }
+    Point(p.0, -p.2, p.1)
 }
 
 fn rotate_x_180(p: &Point) -> Point {
-    Point {
-        0: p.0,
-        1: -p.1,
-        2: -p.2,
-    }
+    Point(p.0, -p.1, -p.2)
 }
 
 fn rotate_x_270(p: &Point) -> Point {
-    Point {
-        0: p.0,
-        1: p.2,
-        2: -p.1,
-    }
+    Point(p.0, p.2, -p.1)
 }
 
 fn offset_if_match(v1: &[Point], v2: &[Point], match_num: usize) -> Option<Point> {
-    // Calculate the distance from each pair of points
+    // Every pair of points, one from each scanner, could be the same beacon. If the second
+    // scanner is at some offset from the first, that offset turns up for every beacon they
+    // both see.
     v1.iter()
-        .zip(v2.iter())
+        .cartesian_product(v2.iter())
         // Calculate the distance
         .map(|(&p1, &p2)| p1 - p2)
         // How many times do we see each distance?
         .counts()
         // Look for a distance that appears >= match_num times
-        .iter()
-        .find(|(_, &count)| count >= match_num)
-        .map(|(&p, _)| p)
+        .into_iter()
+        .find(|(_, count)| *count >= match_num)
+        .map(|(p, _)| p)
+}
+
+/// A way a scanner could be facing, as the rotations about x, then y, then z, that turn it to
+/// face the same way as another
+#[derive(Debug, Clone, Copy)]
+struct Orientation {
+    x_rot: fn(&Point) -> Point,
+    y_rot: fn(&Point) -> Point,
+    z_rot: fn(&Point) -> Point,
+}
+
+impl Orientation {
+    fn apply(&self, p: &Point) -> Point {
+        (self.z_rot)(&(self.y_rot)(&(self.x_rot)(p)))
+    }
+
+    /// The orientation that turns things the same way as `rotate`, which must be a rotation
+    fn matching(rotate: impl Fn(&Point) -> Point) -> Orientation {
+        // Like in `orientations`, this point tells every way of facing apart
+        let p = Point(1, 2, 3);
+        orientations()
+            .into_iter()
+            .find(|orientation| orientation.apply(&p) == rotate(&p))
+            .expect("Every rotation is one of the orientations")
+    }
+}
+
+/// Where a scanner is and which way it faces, relative to another scanner. A beacon the first
+/// scanner sees at `p` is at `orientation.apply(p) + offset` to the other.
+#[derive(Debug, Clone, Copy)]
+struct Placement {
+    orientation: Orientation,
+    offset: Point,
+}
+
+impl Placement {
+    /// A scanner relative to itself
+    fn identity() -> Placement {
+        Placement {
+            orientation: Orientation {
+                x_rot: rotate_0,
+                y_rot: rotate_0,
+                z_rot: rotate_0,
+            },
+            offset: Point(0, 0, 0),
+        }
+    }
+
+    fn apply(&self, p: &Point) -> Point {
+        self.orientation.apply(p) + self.offset
+    }
+
+    /// Placing a scanner by `self`, and then the scanner it is relative to by `next`, as one
+    /// placement
+    fn then(&self, next: &Placement) -> Placement {
+        Placement {
+            orientation: Orientation::matching(|p| {
+                next.orientation.apply(&self.orientation.apply(p))
+            }),
+            offset: next.apply(&self.offset),
+        }
+    }
+
+    /// Where the other scanner is relative to this one
+    fn inverse(&self) -> Placement {
+        // The orientation that turns the point back to where it started undoes this one
+        let p = Point(1, 2, 3);
+        let orientation = orientations()
+            .into_iter()
+            .find(|undo| undo.apply(&self.orientation.apply(&p)) == p)
+            .expect("Every orientation can be undone");
+        Placement {
+            orientation,
+            offset: Point(0, 0, 0) - orientation.apply(&self.offset),
+        }
+    }
 }
 
-fn rotate_and_compare(v1: &[Point], v2: &[Point], match_num: usize) -> Option<Point> {
-    // Try each possible set of rotations, and if we find a match, return the
-    // offset
+/// Every way a scanner could be facing. Lots of the 64 combinations of rotations face the same
+/// way, so only the first of each of the 24 different ones is kept.
+fn orientations() -> Vec<Orientation> {
     let x_rots = [rotate_0, rotate_x_90, rotate_x_180, rotate_x_270];
     let y_rots = [rotate_0, rotate_y_90, rotate_y_180, rotate_y_270];
     let z_rots = [rotate_0, rotate_z_90, rotate_z_180, rotate_z_270];
-    let rotations: Vec<_> = x_rots
-        .iter()
-        .flat_map(|x_rot| y_rots.iter().map(move |y_rot| (x_rot, y_rot)))
-        .flat_map(|(x_rot, y_rot)| z_rots.iter().map(move |z_rot| (x_rot, y_rot, z_rot)))
-        .collect();
-
-    for (x_rot, y_rot, z_rot) in rotations {
-        let v2_rot: Vec<Point> = v2
-            .iter()
-            .map(|p| x_rot(p))
-            .map(|p| y_rot(&p))
-            .map(|p| z_rot(&p))
-            .collect();
-
-        if let Some(offset) = offset_if_match(&v1, &v2_rot, match_num) {
-            return Some(offset);
+
+    // A point with different sized coordinates ends up somewhere different for each way
+    let p = Point(1, 2, 3);
+    let mut seen = HashSet::new();
+    x_rots
+        .into_iter()
+        .cartesian_product(y_rots)
+        .cartesian_product(z_rots)
+        .map(|((x_rot, y_rot), z_rot)| Orientation {
+            x_rot,
+            y_rot,
+            z_rot,
+        })
+        .filter(|orientation| seen.insert(orientation.apply(&p)))
+        .collect()
+}
+
+/// Try each way the second scanner could be facing, and if at least `match_num` of its beacons
+/// line up with the first's, return which way it faces and where it is relative to the first
+fn rotate_and_compare(
+    v1: &[Point],
+    v2: &[Point],
+    match_num: usize,
+) -> Option<(Orientation, Point)> {
+    orientations().into_iter().find_map(|orientation| {
+        let v2_rot: Vec<Point> = v2.iter().map(|p| orientation.apply(p)).collect();
+        offset_if_match(v1, &v2_rot, match_num).map(|offset| (orientation, offset))
+    })
+}
+
+/// How many beacons two scanners must both see to be sure they overlap
+const MATCH_NUM: usize = 12;
+
+/// Every pair of scanners that see the same beacons, with where the second is relative to the
+/// first. The pairs are all checked on their own, so they are shared out between `jobs`
+/// threads.
+fn overlapping_pairs(scanners: &[Vec<Point>], jobs: usize) -> Vec<(usize, usize, Placement)> {
+    let pairs: Vec<(usize, usize)> = (0..scanners.len()).tuple_combinations().collect();
+    let overlaps = parallel::map(&pairs, jobs, |&(idx1, idx2)| {
+        rotate_and_compare(&scanners[idx1], &scanners[idx2], MATCH_NUM)
+    });
+    pairs
+        .into_iter()
+        .zip(overlaps)
+        .filter_map(|((idx1, idx2), overlap)| {
+            overlap.map(|(orientation, offset)| {
+                (
+                    idx1,
+                    idx2,
+                    Placement {
+                        orientation,
+                        offset,
+                    },
+                )
+            })
+        })
+        .collect()
+}
+
+/// Where every scanner and beacon is, relative to scanner 0
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Map {
+    scanners: Vec<Point>,
+    beacons: HashSet<Point>,
+}
+
+/// Piece the scanners together, starting from scanner 0
+fn assemble(input: &HashMap<usize, Vec<Point>>, jobs: usize) -> Result<Map, String> {
+    let scanners: Vec<Vec<Point>> = (0..input.len())
+        .map(|idx| {
+            input
+                .get(&idx)
+                .cloned()
+                .ok_or_else(|| format!("There is no scanner {}", idx))
+        })
+        .collect::<Result<_, _>>()?;
+
+    // Each scanner's neighbours, and where each neighbour is relative to it
+    let mut neighbours = vec![Vec::new(); scanners.len()];
+    for (idx1, idx2, placement) in overlapping_pairs(&scanners, jobs) {
+        neighbours[idx1].push((idx2, placement));
+        neighbours[idx2].push((idx1, placement.inverse()));
+    }
+
+    // Place the scanners outwards from scanner 0, by where each one is relative to the
+    // neighbour that was placed before it, and where that neighbour is relative to scanner 0
+    let mut placed: Vec<Option<Placement>> = vec![None; scanners.len()];
+    placed[0] = Some(Placement::identity());
+    let mut to_visit = VecDeque::from([0]);
+    while let Some(idx) = to_visit.pop_front() {
+        let placement = placed[idx].expect("Only placed scanners are visited");
+        for (neighbour, relative) in &neighbours[idx] {
+            if placed[*neighbour].is_none() {
+                placed[*neighbour] = Some(relative.then(&placement));
+                to_visit.push_back(*neighbour);
+            }
         }
     }
-    None
+
+    let mut map = Map {
+        scanners: Vec::new(),
+        beacons: HashSet::new(),
+    };
+    for (idx, (placement, beacons)) in placed.into_iter().zip(&scanners).enumerate() {
+        let placement =
+            placement.ok_or_else(|| format!("Scanner {} does not overlap the others", idx))?;
+        map.scanners.push(placement.offset);
+        map.beacons
+            .extend(beacons.iter().map(|p| placement.apply(p)));
+    }
+    Ok(map)
 }
 
-fn main() {
-    println!("Hello, world!");
-    let p = Point(1, 2, 3);
-    let x_rots = [rotate_x_90, rotate_x_180, rotate_x_270];
-    let y_rots = [rotate_y_90, rotate_y_180, rotate_y_270];
-    let z_rots = [rotate_z_90, rotate_z_180, rotate_z_270];
-    // Create a vector of all possible rotations
-    let rotations: Vec<_> = x_rots
+fn manhattan_distance(p1: &Point, p2: &Point) -> i64 {
+    (p1.0 - p2.0).abs() + (p1.1 - p2.1).abs() + (p1.2 - p2.2).abs()
+}
+
+fn part1(map: &Map) -> usize {
+    map.beacons.len()
+}
+
+fn part2(map: &Map) -> i64 {
+    map.scanners
         .iter()
-        .flat_map(|x_rot| y_rots.iter().map(move |y_rot| (x_rot, y_rot)))
-        .flat_map(|(x_rot, y_rot)| z_rots.iter().map(move |z_rot| (x_rot, y_rot, z_rot)))
-        .collect();
+        .tuple_combinations()
+        .map(|(p1, p2)| manhattan_distance(p1, p2))
+        .max()
+        .unwrap_or(0)
+}
+
+/// The example from the puzzle description
+const EXAMPLES: &[Example] = &[Example {
+    input: "--- scanner 0 ---
+404,-588,-901
+528,-643,409
+-838,591,734
+390,-675,-793
+-537,-823,-458
+-485,-357,347
+-345,-311,381
+-661,-816,-575
+-876,649,763
+-618,-824,-621
+553,345,-567
+474,580,667
+-447,-329,318
+-584,868,-557
+544,-627,-890
+564,392,-477
+455,729,728
+-892,524,684
+-689,845,-530
+423,-701,434
+7,-33,-71
+630,319,-379
+443,580,662
+-789,900,-551
+459,-707,401
+
+--- scanner 1 ---
+686,422,578
+605,423,415
+515,917,-361
+-336,658,858
+95,138,22
+-476,619,847
+-340,-569,-846
+567,-361,727
+-460,603,-452
+669,-402,600
+729,430,532
+-500,-761,534
+-322,571,750
+-466,-666,-811
+-429,-592,574
+-355,545,-477
+703,-491,-529
+-328,-685,520
+413,935,-424
+-391,539,-444
+586,-435,557
+-364,-763,-893
+807,-499,-711
+755,-354,-619
+553,889,-390
+
+--- scanner 2 ---
+649,640,665
+682,-795,504
+-784,533,-524
+-644,584,-595
+-588,-843,648
+-30,6,44
+-674,560,763
+500,723,-460
+609,671,-379
+-555,-800,653
+-675,-892,-343
+697,-426,-610
+578,704,681
+493,664,-388
+-671,-858,530
+-667,343,800
+571,-461,-707
+-138,-166,112
+-889,563,-600
+646,-828,498
+640,759,510
+-630,509,768
+-681,-892,-333
+673,-379,-804
+-742,-814,-386
+577,-820,562
+
+--- scanner 3 ---
+-589,542,597
+605,-692,669
+-500,565,-823
+-660,373,557
+-458,-679,-417
+-488,449,543
+-626,468,-788
+338,-750,-386
+528,-832,-391
+562,-778,733
+-938,-730,414
+543,643,-506
+-524,371,-870
+407,773,750
+-104,29,83
+378,-903,-323
+-778,-728,485
+426,699,580
+-438,-605,-362
+-469,-447,-387
+509,732,623
+647,635,-688
+-868,-804,481
+614,-800,639
+595,780,-596
+
+--- scanner 4 ---
+727,592,562
+-293,-554,779
+441,611,-461
+-714,465,-776
+-743,427,-804
+-660,-479,-426
+832,-632,460
+927,-485,-438
+408,393,-506
+466,436,-512
+110,16,151
+-258,-428,682
+-393,719,612
+-211,-452,876
+808,-476,-593
+-575,615,604
+-485,667,467
+-680,325,-822
+-627,-443,-432
+872,-547,-609
+833,512,582
+807,604,487
+839,-516,451
+891,-625,532
+-652,-548,-490
+30,-46,-14",
+    part1: Some("79"),
+    part2: Some("3621"),
+}];
+
+/// Each part, solved straight from the input
+const PARTS: [Option<puzzle::Part>; 2] = [Some(solve_part1), Some(solve_part2)];
+
+fn solve_part1(input: &str) -> Result<String, String> {
+    Ok(part1(&assemble(&parse_input(input), parallel::jobs())?).to_string())
+}
+
+fn solve_part2(input: &str) -> Result<String, String> {
+    Ok(part2(&assemble(&parse_input(input), parallel::jobs())?).to_string())
+}
+
+fn main() {
+    // Pass `--example` to check the solutions against the puzzle's examples instead
+    if std::env::args().any(|arg| arg == "--example") {
+        std::process::exit(puzzle::run_examples(EXAMPLES, PARTS));
+    }
+
+    let setup_time = std::time::Instant::now();
+
+    let input_str =
+        std::fs::read_to_string("input/day19.txt").expect("Failed to read day 19 input");
+    let scanners = parse_input(&input_str);
+    println!("Setup took {:.6} µs", setup_time.elapsed().as_micros());
+
+    // Part 1, which pieces the scanners together. Pass `--jobs=N` to compare the scanners
+    // with N threads, rather than one per core.
+    let part1_time = std::time::Instant::now();
+    let map = assemble(&scanners, parallel::jobs()).expect("Could not piece the scanners together");
+    let part1_result = part1(&map);
+    println!("Part 1 took {:.6} ms", part1_time.elapsed().as_millis());
+
+    // Part 2
+    let part2_time = std::time::Instant::now();
+    let part2_result = part2(&map);
+    println!("Part 2 took {:.6} µs", part2_time.elapsed().as_micros());
+
+    println!();
+    println!("Part 1 result: {}", part1_result);
+    println!("Part 2 result: {}", part2_result);
 }
 
 #[test]
@@ -324,8 +609,24 @@ fn test_rotate_and_compare() {
     let v1 = input.get(&0).unwrap();
     let v2 = input.get(&1).unwrap();
 
-    let got = rotate_and_compare(&v1, &v2, 12);
-    // assert!(matches!(got, Some(_)));
+    let got = rotate_and_compare(v1, v2, 12);
+    assert!(matches!(got, Some((_, Point(68, -1246, -43)))));
+}
+
+#[test]
+fn test_part1_actual() {
+    let input_str =
+        std::fs::read_to_string("input/day19.txt").expect("Failed to read day 19 input");
+    let map = assemble(&parse_input(&input_str), 4).unwrap();
+    assert_eq!(367, part1(&map));
+}
+
+#[test]
+fn test_part2_actual() {
+    let input_str =
+        std::fs::read_to_string("input/day19.txt").expect("Failed to read day 19 input");
+    let map = assemble(&parse_input(&input_str), 4).unwrap();
+    assert_eq!(11925, part2(&map));
 }
 
 #[test]
@@ -390,3 +691,89 @@ fn test_rotate_x_270() {
     let p2 = rotate_x_270(&p1);
     assert_eq!(p2, Point(10, 30, -20));
 }
+
+#[test]
+fn test_parse_input_crlf() {
+    let input_str =
+        "--- scanner 0 ---\n404,-588,-901\n528,-643,409\n\n--- scanner 1 ---\n686,422,578\n";
+    let windows_str =
+        "--- scanner 0 ---\r\n404,-588,-901\r\n528,-643,409 \r\n\r\n\r\n--- scanner 1 ---\r\n686,422,578\r\n";
+    assert_eq!(parse_input(input_str), parse_input(windows_str));
+}
+
+#[test]
+fn test_assemble_example() {
+    // Scanners 2 and 3 can only be placed through others, so their placements are combined
+    let map = assemble(&parse_input(EXAMPLES[0].input), 2).unwrap();
+    assert_eq!(
+        vec![
+            Point(0, 0, 0),
+            Point(68, -1246, -43),
+            Point(1105, -1205, 1229),
+            Point(-92, -2380, -20),
+            Point(-20, -1133, 1061),
+        ],
+        map.scanners
+    );
+}
+
+#[test]
+fn test_placement_inverse() {
+    for orientation in orientations() {
+        let placement = Placement {
+            orientation,
+            offset: Point(5, -7, 11),
+        };
+        let p = Point(3, 1, -4);
+        assert_eq!(p, placement.inverse().apply(&placement.apply(&p)));
+        assert_eq!(p, placement.then(&placement.inverse()).apply(&p));
+    }
+}
+
+#[test]
+fn test_examples() {
+    for check in puzzle::check_examples(EXAMPLES, PARTS) {
+        assert!(check.is_ok(), "{}", check);
+    }
+}
+
+#[test]
+fn test_orientations() {
+    let orientations = orientations();
+    assert_eq!(24, orientations.len());
+
+    // Every way of facing keeps the axes at right angles, and doesn't mirror them
+    let x = Point(1, 0, 0);
+    let y = Point(0, 1, 0);
+    for orientation in &orientations {
+        let (x, y) = (orientation.apply(&x), orientation.apply(&y));
+        let cross = Point(
+            x.1 * y.2 - x.2 * y.1,
+            x.2 * y.0 - x.0 * y.2,
+            x.0 * y.1 - x.1 * y.0,
+        );
+        assert_eq!(orientation.apply(&Point(0, 0, 1)), cross);
+    }
+}
+
+#[test]
+fn test_generated_input() {
+    // Generated scanners all overlap, however many threads compare them
+    let input_str = aoc_2021_rs::generate::generate(19, Some(5), 3).unwrap();
+    let scanners = parse_input(&input_str);
+    let map = assemble(&scanners, 1).unwrap();
+    assert_eq!(5, map.scanners.len());
+    assert_eq!(Point(0, 0, 0), map.scanners[0]);
+    for jobs in [2, 4] {
+        assert_eq!(map, assemble(&scanners, jobs).unwrap());
+    }
+}
+
+#[test]
+fn test_separate_scanners() {
+    let input_str = "--- scanner 0 ---\n1,2,3\n\n--- scanner 1 ---\n4,5,6\n";
+    assert_eq!(
+        Err("Scanner 1 does not overlap the others".to_string()),
+        assemble(&parse_input(input_str), 2)
+    );
+}
@@ -1,22 +1,22 @@
 use std::collections::{HashMap, HashSet};
 
+use aoc_2021_rs::parsing;
+use aoc_2021_rs::puzzle::{self, Example};
+
 fn parse_input(input: &str) -> (Vec<char>, HashMap<(i64, i64), char>) {
-    // The first line is the image enhancement algorithm
-    let algorithm: Vec<char> = input
-        .lines()
-        .next()
-        .expect("Could not get the first line")
-        .chars()
-        .collect();
+    let sections = parsing::sections(input);
+    let (algorithm_lines, image_lines) = match sections.as_slice() {
+        [algorithm, image] => (algorithm, image),
+        _ => panic!("Expected the image enhancement algorithm, then the image"),
+    };
 
-    // Then there's a double newline
-    let (_, image_str) = input
-        .split_once("\n\n")
-        .expect("Could not get the image lines");
+    // The first section is the image enhancement algorithm, which may be wrapped over lines
+    let algorithm: Vec<char> = algorithm_lines.concat().chars().collect();
 
     // Get the image array into a HashMap
-    let mut image: HashMap<(i64, i64), char> = HashMap::with_capacity(image_str.len());
-    for (row_idx, row) in image_str.lines().enumerate() {
+    let mut image: HashMap<(i64, i64), char> =
+        HashMap::with_capacity(image_lines.len() * image_lines[0].len());
+    for (row_idx, row) in image_lines.iter().enumerate() {
         for (col_idx, c) in row.chars().enumerate() {
             // Insert the character into the hashmap
             image.insert((row_idx as i64, col_idx as i64), c);
@@ -141,7 +141,38 @@ fn solve(curr_points: &HashMap<(i64, i64), char>, algo: &[char], n_times: usize)
     img.values().filter(|&&c| c == '#').count()
 }
 
+/// The examples from the puzzle description
+const EXAMPLES: &[Example] = &[Example {
+    input: "..#.#..#####.#.#.#.###.##.....###.##.#..###.####..#####..#....#..#..##..###..######.###...####..#..#####..##..#.#####...##.#.#..#.##..#.#......#.###.######.###.####...#.##.##..#..#..#####.....#.#....###..#.##......#.....#..#..#..##..#...##.######.####.####.#.#...#.......#..#.#.#...####.##.#......#..#...##.#.##..#...##.#.##..###.#......#.#.......#.#.#.####.###.##...#.....####.#..#..#.##.#....##..#.####....##...##..#...#......#.#.......#.......##..####..#...#.#.#...##..#.#..###..#####........#..####......#..#
+
+#..#.
+#....
+##..#
+..#..
+..###",
+    part1: Some("35"),
+    part2: Some("3351"),
+}];
+
+/// Each part, solved straight from the input
+const PARTS: [Option<puzzle::Part>; 2] = [Some(solve_part1), Some(solve_part2)];
+
+fn solve_part1(input: &str) -> Result<String, String> {
+    let (algo, input_image) = parse_input(input);
+    Ok(solve(&input_image, &algo, 2).to_string())
+}
+
+fn solve_part2(input: &str) -> Result<String, String> {
+    let (algo, input_image) = parse_input(input);
+    Ok(solve(&input_image, &algo, 50).to_string())
+}
+
 fn main() {
+    // Pass `--example` to check the solutions against the puzzle's examples instead
+    if std::env::args().any(|arg| arg == "--example") {
+        std::process::exit(puzzle::run_examples(EXAMPLES, PARTS));
+    }
+
     let setup_time = std::time::Instant::now();
 
     let input_str =
@@ -210,30 +241,30 @@ fn test_parse_input() {
 
     let expected_image = HashMap::from([
         ((0, 0), '#'),
-        (((0, 1), '.')),
-        (((0, 2), '.')),
-        (((0, 3), '#')),
-        (((0, 4), '.')),
+        ((0, 1), '.'),
+        ((0, 2), '.'),
+        ((0, 3), '#'),
+        ((0, 4), '.'),
         ((1, 0), '#'),
-        (((1, 1), '.')),
-        (((1, 2), '.')),
-        (((1, 3), '.')),
-        (((1, 4), '.')),
+        ((1, 1), '.'),
+        ((1, 2), '.'),
+        ((1, 3), '.'),
+        ((1, 4), '.'),
         ((2, 0), '#'),
-        (((2, 1), '#')),
-        (((2, 2), '.')),
-        (((2, 3), '.')),
-        (((2, 4), '#')),
+        ((2, 1), '#'),
+        ((2, 2), '.'),
+        ((2, 3), '.'),
+        ((2, 4), '#'),
         ((3, 0), '.'),
-        (((3, 1), '.')),
-        (((3, 2), '#')),
-        (((3, 3), '.')),
-        (((3, 4), '.')),
+        ((3, 1), '.'),
+        ((3, 2), '#'),
+        ((3, 3), '.'),
+        ((3, 4), '.'),
         ((4, 0), '.'),
-        (((4, 1), '.')),
-        (((4, 2), '#')),
-        (((4, 3), '#')),
-        (((4, 4), '#')),
+        ((4, 1), '.'),
+        ((4, 2), '#'),
+        ((4, 3), '#'),
+        ((4, 4), '#'),
     ]);
 
     assert_eq!(expected_algo, algo);
@@ -565,3 +596,17 @@ fn test_part2_actual() {
     let got = solve(&input_image, &algo, 50);
     assert_eq!(20395, got);
 }
+
+#[test]
+fn test_parse_input_crlf() {
+    let input_str = "..#.#\n\n#..#.\n#....\n";
+    let windows_str = "..#\r\n.#\r\n\r\n#..#. \r\n#....\r\n\r\n";
+    assert_eq!(parse_input(input_str), parse_input(windows_str));
+}
+
+#[test]
+fn test_examples() {
+    for check in puzzle::check_examples(EXAMPLES, PARTS) {
+        assert!(check.is_ok(), "{}", check);
+    }
+}
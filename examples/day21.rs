@@ -1,308 +1,379 @@
-use itertools::Itertools;
-
-fn parse_input(input: &str) -> (usize, usize) {
-    let (p1_line, p2_line) = input
-        .split_once('\n')
-        .expect("Could not split around newline");
-
-    let p1_start: usize = p1_line
-        .split_ascii_whitespace()
-        .last()
-        .expect("Could not get last item from first line")
-        .parse()
-        .expect("Could not convert first to number");
-
-    let p2_start: usize = p2_line
-        .split_ascii_whitespace()
-        .last()
-        .expect("Could not get last item from second line")
-        .parse()
-        .expect("Could not convert second to number");
-
-    (p1_start, p2_start)
+use std::collections::HashMap;
+
+use aoc_2021_rs::puzzle::{self, Example};
+
+fn parse_input(input: &str) -> Vec<usize> {
+    input
+        .lines()
+        .map(|line| {
+            line.split_ascii_whitespace()
+                .last()
+                .expect("Could not get last item from line")
+                .parse()
+                .expect("Could not convert starting position to number")
+        })
+        .collect()
 }
 
-fn part1(p1_start: usize, p2_start: usize) -> usize {
-    let mut p1_score = 0;
-    let mut p2_score = 0;
+/// The rules of a game of Dirac Dice. Pawns move around a circular board with spaces
+/// numbered `1..=board_size`, and on each turn a player rolls the die `rolls_per_turn`
+/// times, moves that far, and adds the number of the space they land on to their score.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct DiceGame {
+    board_size: usize,
+    winning_score: usize,
+    die_sides: usize,
+    rolls_per_turn: usize,
+    n_players: usize,
+}
+
+impl DiceGame {
+    /// The rules for part 1, played with a deterministic 100-sided die
+    const PRACTICE: DiceGame = DiceGame {
+        board_size: 10,
+        winning_score: 1_000,
+        die_sides: 100,
+        rolls_per_turn: 3,
+        n_players: 2,
+    };
+
+    /// The rules for part 2, played with the three-sided Dirac die
+    const DIRAC: DiceGame = DiceGame {
+        board_size: 10,
+        winning_score: 21,
+        die_sides: 3,
+        rolls_per_turn: 3,
+        n_players: 2,
+    };
+
+    /// Move a pawn `steps` spaces forward from `pos`, wrapping back around to space 1
+    fn advance(&self, pos: usize, steps: usize) -> usize {
+        (pos - 1 + steps) % self.board_size + 1
+    }
+
+    /// The number of ways each total can come up in a single turn. The index is the total,
+    /// and the value is the number of universes that produce it.
+    fn roll_counts(&self) -> Vec<usize> {
+        (0..self.rolls_per_turn).fold(vec![1], |counts, _| add_roll(&counts, self.die_sides))
+    }
+
+    /// Returns a vector representing the board, where the index represents a spot on the
+    /// board (1..=board_size). Note that this vector has a spot at 0, which will hold 0.
+    /// The value at each index represents the number of pawns that end up at that location
+    /// after one turn starting from `start`.
+    fn positions_after_turn(&self, start: usize) -> Vec<usize> {
+        let mut result = vec![0; self.board_size + 1];
+        for (total, count) in self.roll_counts().into_iter().enumerate() {
+            result[self.advance(start, total)] += count;
+        }
+        result
+    }
+
+    /// Play with a deterministic die that rolls `1, 2, ..., die_sides, 1, 2, ...` until
+    /// someone wins. Returns the number of times the die was rolled, and every player's
+    /// final score.
+    fn play_deterministic(&self, starts: &[usize]) -> (usize, Vec<usize>) {
+        assert_eq!(self.n_players, starts.len(), "Wrong number of players");
+
+        let mut positions = starts.to_vec();
+        let mut scores = vec![0; self.n_players];
+        let mut die = (1..=self.die_sides).cycle();
+        let mut n_die_rolls = 0;
+
+        for player in (0..self.n_players).cycle() {
+            let steps: usize = die.by_ref().take(self.rolls_per_turn).sum();
+            n_die_rolls += self.rolls_per_turn;
+
+            positions[player] = self.advance(positions[player], steps);
+            scores[player] += positions[player];
+            if scores[player] >= self.winning_score {
+                return (n_die_rolls, scores);
+            }
+        }
+
+        unreachable!()
+    }
 
-    let mut p1_loc = p1_start;
-    let mut p2_loc = p2_start;
+    /// Count the number of universes in which each player wins, when every roll of the die
+    /// splits the universe into `die_sides` copies
+    fn count_wins(&self, starts: &[usize]) -> Vec<usize> {
+        assert_eq!(self.n_players, starts.len(), "Wrong number of players");
 
-    let locs = [10, 1, 2, 3, 4, 5, 6, 7, 8, 9];
+        let mut memo = HashMap::new();
+        let players: Vec<(usize, usize)> = starts.iter().map(|&start| (start, 0)).collect();
+        self.count_wins_from(&players, &mut memo)
+    }
 
-    let mut n_die_rolls = 0;
+    /// `players` holds the (position, score) of each player, starting with the player whose
+    /// turn it is. The returned win counts are in the same order.
+    fn count_wins_from(
+        &self,
+        players: &[(usize, usize)],
+        memo: &mut HashMap<Vec<(usize, usize)>, Vec<usize>>,
+    ) -> Vec<usize> {
+        if let Some(wins) = memo.get(players) {
+            return wins.clone();
+        }
 
-    for (a, b, c) in (1..=100).cycle().tuples() {
-        // Increase die rolls
-        n_die_rolls += 3;
+        let n_players = players.len();
+        let mut wins = vec![0; n_players];
+        let (pos, score) = players[0];
 
-        // If odd, then it's the first player
-        if a % 2 == 1 {
-            p1_loc = locs[(p1_loc + a + b + c) % 10];
-            p1_score += p1_loc;
-            if p1_score >= 1_000 {
-                return p2_score * n_die_rolls;
+        let new_positions = self.positions_after_turn(pos);
+        for (new_pos, &count) in new_positions.iter().enumerate().filter(|(_, &c)| c > 0) {
+            let new_score = score + new_pos;
+            if new_score >= self.winning_score {
+                wins[0] += count;
+                continue;
             }
-        } else {
-            p2_loc = locs[(p2_loc + a + b + c) % 10];
-            p2_score += p2_loc;
-            if p2_score >= 1_000 {
-                return p1_score * n_die_rolls;
+
+            // Rotate so that the next player is first, and the current player is last
+            let mut next_players = players[1..].to_vec();
+            next_players.push((new_pos, new_score));
+            let next_wins = self.count_wins_from(&next_players, memo);
+            for (idx, n_wins) in next_wins.into_iter().enumerate() {
+                wins[(idx + 1) % n_players] += count * n_wins;
             }
         }
+
+        memo.insert(players.to_vec(), wins.clone());
+        wins
     }
 
-    unreachable!()
+    /// Work out how likely each result of a game with the Dirac die is, where every roll
+    /// of the die is equally likely to come up on any face
+    fn analyse(&self, starts: &[usize]) -> Outcome {
+        assert_eq!(self.n_players, starts.len(), "Wrong number of players");
+
+        let n_rolls = self.die_sides.pow(self.rolls_per_turn as u32) as f64;
+        let moves: Vec<Vec<usize>> = (0..=self.board_size)
+            .map(|pos| {
+                if pos == 0 {
+                    vec![]
+                } else {
+                    self.positions_after_turn(pos)
+                }
+            })
+            .collect();
+        let max_score = self.winning_score + self.board_size;
+
+        let mut outcome = Outcome {
+            win_probability: vec![0.0; self.n_players],
+            expected_turns: 0.0,
+            final_scores: vec![vec![0.0; max_score]; self.n_players],
+        };
+
+        // The probability of every game still in play, keyed by each player's
+        // (position, score) in turn order
+        let mut in_play: HashMap<Vec<(usize, usize)>, f64> = HashMap::new();
+        in_play.insert(starts.iter().map(|&start| (start, 0)).collect(), 1.0);
+
+        for turn in 0.. {
+            if in_play.is_empty() {
+                break;
+            }
+
+            let player = turn % self.n_players;
+            let mut next_in_play = HashMap::new();
+
+            for (players, probability) in in_play {
+                let (pos, score) = players[player];
+                for (new_pos, &count) in moves[pos].iter().enumerate().filter(|(_, &c)| c > 0) {
+                    let probability = probability * count as f64 / n_rolls;
+                    let mut next_players = players.clone();
+                    next_players[player] = (new_pos, score + new_pos);
+
+                    if score + new_pos >= self.winning_score {
+                        outcome.win_probability[player] += probability;
+                        outcome.expected_turns += probability * (turn + 1) as f64;
+                        for (scores, &(_, final_score)) in
+                            outcome.final_scores.iter_mut().zip(&next_players)
+                        {
+                            scores[final_score] += probability;
+                        }
+                    } else {
+                        *next_in_play.entry(next_players).or_insert(0.0) += probability;
+                    }
+                }
+            }
+
+            in_play = next_in_play;
+        }
+
+        outcome
+    }
+}
+
+/// How a game of Dirac Dice is likely to play out from some starting positions
+#[derive(Debug, Clone, PartialEq)]
+struct Outcome {
+    /// The probability that each player wins
+    win_probability: Vec<f64>,
+    /// The expected number of turns in the game, counting each player's turn separately
+    expected_turns: f64,
+    /// For each player, the probability of finishing the game with each score
+    final_scores: Vec<Vec<f64>>,
+}
+
+fn part1(starts: &[usize]) -> usize {
+    let game = DiceGame::PRACTICE;
+    let (n_die_rolls, scores) = game.play_deterministic(starts);
+    let losing_score = scores
+        .into_iter()
+        .filter(|&score| score < game.winning_score)
+        .min()
+        .expect("Everyone won at once");
+
+    losing_score * n_die_rolls
 }
 
-fn add_three(count: &[usize]) -> Vec<usize> {
+fn add_roll(count: &[usize], die_sides: usize) -> Vec<usize> {
     // count is a slice where the "key" is the index, and the "value" is the number
     // of times that key appears
     // This means there will be some numbers near the start with a count of 0, and that's
     // fine
 
-    let mut result = vec![0; count.len() + 3];
+    let mut result = vec![0; count.len() + die_sides];
     for (item, count) in count.iter().enumerate() {
-        result[item + 1] += count;
-        result[item + 2] += count;
-        result[item + 3] += count;
+        for face in 1..=die_sides {
+            result[item + face] += count;
+        }
     }
 
     result
 }
 
-/// Returns an array representing the board, where the index repesents a spot on the
-/// board (1-10). Note that this array has a spot at 0, which should hold 0. The value
-/// at each index represents the number pawns at that location
-fn roll_dirac_dice_thre_times(start: usize) -> [usize; 11] {
-    const LOCS: [usize; 10] = [10, 1, 2, 3, 4, 5, 6, 7, 8, 9];
-
-    // After rolling dirac dice three times, we would add the following to the start
-    const TO_ADD: [usize; 27] = [
-        3, 4, 4, 4, 5, 5, 5, 5, 5, 5, 6, 6, 6, 6, 6, 6, 6, 7, 7, 7, 7, 7, 7, 8, 8, 8, 9,
-    ];
+fn part2(starts: &[usize]) -> usize {
+    DiceGame::DIRAC
+        .count_wins(starts)
+        .into_iter()
+        .max()
+        .expect("There were no players")
+}
 
-    // Count up the results
-    let mut result = [0_usize; 11];
+/// A table of player 1's chance of winning a two player game, with a row for each of player
+/// 1's starting positions, and a column for each of player 2's
+fn win_probability_table(game: &DiceGame) -> String {
+    let mut table = String::from("p1 \\ p2");
+    for p2_start in 1..=game.board_size {
+        table += &format!(" {:>6}", p2_start);
+    }
 
-    TO_ADD
-        .iter()
-        // Add start to everything in `TO_ADD`
-        .map(|f| f + start)
-        // Get the correct board location using locs
-        .map(|f| LOCS[f % 10])
-        // Add 1 to that location on the board
-        .for_each(|f| result[f] += 1);
+    for p1_start in 1..=game.board_size {
+        table += &format!("\n{:>8}", p1_start);
+        for p2_start in 1..=game.board_size {
+            let outcome = game.analyse(&[p1_start, p2_start]);
+            table += &format!(" {:>6.4}", outcome.win_probability[0]);
+        }
+    }
 
-    result
+    table
 }
 
-fn part2(p1_start: usize, p2_start: usize) -> usize {
-    // The idea here is to have an array representing the board, where index 0 is board
-    // position 1, index 1 is board position 2, etc.
-    // Each item in the array is an array representing the score from 0 to 21
-    // (inclusive), and the value at each index tells us how many "players" are at that
-    // spot on the board, and have
-    // that score
-    let mut p1_old: [[usize; 22]; 10] = [[0; 22]; 10];
-    let mut p2_old: [[usize; 22]; 10] = [[0; 22]; 10];
-    let mut p1_new: [[usize; 22]; 10] = [[0; 22]; 10];
-    let mut p2_new: [[usize; 22]; 10] = [[0; 22]; 10];
-
-    // Add the players to the correct positions. They have starting scores of 0
-    p1_old[p1_start][0] = 1;
-    p2_old[p2_start][0] = 1;
-
-    // To keep track of games won
-    let mut p1_games_won: usize = 0;
-    let mut p2_games_won: usize = 0;
-
-    // Compute the ending positions for each of the ten board positions
-    let positions_after_rolling: [[usize; 11]; 10] = [
-        roll_dirac_dice_thre_times(1),
-        roll_dirac_dice_thre_times(2),
-        roll_dirac_dice_thre_times(3),
-        roll_dirac_dice_thre_times(4),
-        roll_dirac_dice_thre_times(5),
-        roll_dirac_dice_thre_times(6),
-        roll_dirac_dice_thre_times(7),
-        roll_dirac_dice_thre_times(8),
-        roll_dirac_dice_thre_times(9),
-        roll_dirac_dice_thre_times(10),
-    ];
-
-    let mut idx: usize = 0;
-
-    // Begin playing the game
-    loop {
-        // Check if all the pawns have finished
-
-        // Player 1
-        for (board_pos, scores_at_pos) in p1_old.iter().enumerate() {
-            // For each player 1 position, get the positions after rolling
-            let new_positions = positions_after_rolling[board_pos];
-            
-            // For each new position
-            for new_pos in new_positions {
-                // Need to move pawns from `board_pos` to `new_pos`, and add `new_pos` 
-                // to the score at `new_pos` for each old score as `board_pos`
-                for (old_score, n_items) in scores_at_pos.iter().enumerate() {
-                    let new_score = old_score + new_pos;
-                    if new_score >= 21 {
-                        p1_games_won += n_items
-                    } else {
-                        p1_new[new_pos][new_score] += n_items;
-                    }
+/// A CSV with a row for each player in each pair of starting positions of a two player game,
+/// holding their chance of winning, the expected length of the game, and the probability of
+/// them finishing with each score
+fn analysis_csv(game: &DiceGame) -> String {
+    let max_score = game.winning_score + game.board_size;
+    let mut csv = String::from("p1_start,p2_start,player,win_probability,expected_turns");
+    for score in 0..max_score {
+        csv += &format!(",score_{}", score);
+    }
+    csv.push('\n');
+
+    for p1_start in 1..=game.board_size {
+        for p2_start in 1..=game.board_size {
+            let outcome = game.analyse(&[p1_start, p2_start]);
+            for (player, scores) in outcome.final_scores.iter().enumerate() {
+                csv += &format!(
+                    "{},{},{},{},{}",
+                    p1_start,
+                    p2_start,
+                    player + 1,
+                    outcome.win_probability[player],
+                    outcome.expected_turns
+                );
+                for probability in scores {
+                    csv += &format!(",{}", probability);
                 }
+                csv.push('\n');
             }
         }
+    }
 
-        // Player 2
-        for (board_pos, scores_at_pos) in p2_old.iter().enumerate() {
-            // For each player 1 position, get the positions after rolling
-            let new_positions = positions_after_rolling[board_pos];
-            
-            // For each new position
-            for new_pos in new_positions {
-                // Need to move pawns from `board_pos` to `new_pos`, and add `new_pos` 
-                // to the score at `new_pos` for each old score as `board_pos`
-                for (old_score, n_items) in scores_at_pos.iter().enumerate() {
-                    let new_score = old_score + new_pos;
-                    if new_score >= 21 {
-                        p2_games_won += n_items
-                    } else {
-                        p2_new[new_pos][new_score] += n_items;
-                    }
-                }
-            }
-        }
+    csv
+}
 
-        // Finally copy new into old. Don't have to clone bc array impls Copy trait,
-        // so they are copied instead of moved.
-        p1_old = p1_new;
-        p2_old = p2_new;
-        // Clean out the new arrays
-        p1_new = [[0; 22]; 10];
-        p2_new = [[0; 22]; 10];
-
-        // Failsafe
-        idx += 1;
-        if idx >= 10_000_000 {
-            return 0;
-        }
-    }
+/// The examples from the puzzle description
+const EXAMPLES: &[Example] = &[Example {
+    input: "Player 1 starting position: 4
+Player 2 starting position: 8",
+    part1: Some("739785"),
+    part2: Some("444356092776315"),
+}];
+
+/// Each part, solved straight from the input
+const PARTS: [Option<puzzle::Part>; 2] = [Some(solve_part1), Some(solve_part2)];
 
+fn solve_part1(input: &str) -> Result<String, String> {
+    Ok(part1(&parse_input(input)).to_string())
 }
 
-// fn part2(p1_start: usize, p2_start: usize) -> usize {
-//     // It looks like at each step, the number of possibilities increases by 2
-//     // The pattern looks like
-//     // 1
-//     // 2,3,4
-//     // 3:1, 4:2, 5:3, 6:2, 7:1
-//     // 4:1, 5:3, 6:6, 7:7, 8:6, 9:3, 10:1
-//     // 5:1, 6:4, 7:9, 8:16, 9:19, 10:16, 11:9, 12:4, 13:1
-//     // The number of items produced at each stage, n, (initial stage is 0) is 3^n,
-
-//     let mut p1_games_won = 0;
-//     let mut p2_games_won = 0;
-
-//     // Put the starting positions in vectors
-//     let mut p1_positions = vec![0; p1_start + 1];
-//     p1_positions[p1_start] = 1;
-//     let mut p2_positions = vec![0; p2_start + 1];
-//     p2_positions[p2_start] = 1;
-
-//     loop {
-//         // Player 1 rolls the dice three times
-//         p1_positions = add_three(&p1_positions);
-//         p1_positions = add_three(&p1_positions);
-//         p1_positions = add_three(&p1_positions);
-
-//         // Get all of the positions into the 1..=10 range
-//         // Anything at index 11 should be at index 1
-//         if p1_positions.len() >= 12 {
-//             let cnt = p1_positions[11];
-//             p1_positions[1] += cnt;
-//             if p1_positions.len() >= 13 {
-//                 let cnt = p1_positions[12];
-//                 p1_positions[2] += cnt;
-//                 if p1_positions.len() >= 14 {
-//                     let cnt = p1_positions[13];
-//                     p1_positions[3] += cnt;
-//                     p1_positions.pop();
-//                 }
-//                 p1_positions.pop();
-//             }
-//             p1_positions.pop();
-//         }
-
-//         // If player 1 has any counts at index 21, that means they won that many games
-//         // Add that to their count, and pop that index from the vec
-//         while p1_positions.len() >= 22 {
-//             p1_games_won += p1_positions
-//                 .pop()
-//                 .expect("Could not pop player 1 items at index 21");
-//         }
-
-//         // Player 2 rolls the dice three times
-//         p2_positions = add_three(&p2_positions);
-//         p2_positions = add_three(&p2_positions);
-//         p2_positions = add_three(&p2_positions);
-
-//         // If player 2 has any counts at index 21, that means they won that many games
-//         // Add that to their count, and pop that index from the vec
-//         while p2_positions.len() >= 22 {
-//             p2_games_won += p2_positions
-//                 .pop()
-//                 .expect("Could not pop player 1 items at index 21");
-//         }
-
-//         // If both have all zeros, all games are over
-//         if p1_positions.iter().all(|x| *x == 0) && p2_positions.iter().all(|x| *x == 0) {
-//             break;
-//         }
-//     }
-
-//     p1_games_won.max(p2_games_won)
-// }
+fn solve_part2(input: &str) -> Result<String, String> {
+    Ok(part2(&parse_input(input)).to_string())
+}
 
 fn main() {
+    // Pass `--example` to check the solutions against the puzzle's examples instead
+    if std::env::args().any(|arg| arg == "--example") {
+        std::process::exit(puzzle::run_examples(EXAMPLES, PARTS));
+    }
+
     let setup_time = std::time::Instant::now();
 
     let input_str =
         std::fs::read_to_string("input/day21.txt").expect("Failed to read day 21 input");
-    let (p1_start, p2_start) = parse_input(&input_str);
+    let starts = parse_input(&input_str);
     println!("Setup took {:.6} µs", setup_time.elapsed().as_micros());
 
     // Part 1
     let part1_time = std::time::Instant::now();
-    let part1_result = part1(p1_start, p2_start);
+    let part1_result = part1(&starts);
     println!("Part 1 took {:.6} µs", part1_time.elapsed().as_micros());
 
     // Part 2
-    // let part2_time = std::time::Instant::now();
-    // let part2_result = part2(p1_start, p2_start);
-    // println!("Part 2 took {:.6} ms", part2_time.elapsed().as_millis());
+    let part2_time = std::time::Instant::now();
+    let part2_result = part2(&starts);
+    println!("Part 2 took {:.6} ms", part2_time.elapsed().as_millis());
 
     println!();
     println!("Part 1 result: {}", part1_result);
-    // println!("Part 2 result: {}", part2_result);
+    println!("Part 2 result: {}", part2_result);
+
+    // Pass `--table` or `--csv` to see how likely each player is to win from every pair of
+    // starting positions
+    if std::env::args().any(|arg| arg == "--table") {
+        println!();
+        println!("{}", win_probability_table(&DiceGame::DIRAC));
+    }
+    if std::env::args().any(|arg| arg == "--csv") {
+        println!();
+        print!("{}", analysis_csv(&DiceGame::DIRAC));
+    }
 }
 
 #[test]
 fn test_parse_input() {
     let input_str = "Player 1 starting position: 10
 Player 2 starting position: 6";
-    let expected = (10, 6);
+    let expected = vec![10, 6];
     let got = parse_input(input_str);
     assert_eq!(expected, got);
 }
 
 #[test]
 fn test_part1() {
-    let got = part1(4, 8);
+    let got = part1(&[4, 8]);
     assert_eq!(739785, got);
 }
 
@@ -310,16 +381,16 @@ fn test_part1() {
 fn test_part1_actual() {
     let input_str =
         std::fs::read_to_string("input/day21.txt").expect("Failed to read day 21 input");
-    let (p1_start, p2_start) = parse_input(&input_str);
-    let got = part1(p1_start, p2_start);
+    let starts = parse_input(&input_str);
+    let got = part1(&starts);
     assert_eq!(900099, got);
 }
 
 #[test]
-fn test_add_three_1() {
+fn test_add_roll_1() {
     // This represents 0:1, 1:2, 2:3
     let input = [1, 2, 3];
-    let got = add_three(&input);
+    let got = add_roll(&input, 3);
     // Expect to get 1,2,3,2,2,3,3,4,4,3,3,3,4,4,4,5,5,5
     // So a counter would hold {1: 1, 2: 3, 3: 6, 4: 5, 5: 3}
     let expected = vec![0, 1, 3, 6, 5, 3];
@@ -327,11 +398,11 @@ fn test_add_three_1() {
 }
 
 #[test]
-fn test_add_three_2() {
+fn test_add_roll_2() {
     // Input is
     // 3:1, 4:2, 5:3, 6:2, 7:1
     let input = [0, 0, 0, 1, 2, 3, 2, 1];
-    let got = add_three(&input);
+    let got = add_roll(&input, 3);
     // Output should be
     // 4:1, 5:3, 6:6, 7:7, 8:6, 9:3, 10:1
     let expected = vec![0, 0, 0, 0, 1, 3, 6, 7, 6, 3, 1];
@@ -341,24 +412,153 @@ fn test_add_three_2() {
 #[test]
 fn test_roll_dirac_dice_1() {
     let start = 1;
-    let got = roll_dirac_dice_thre_times(start);
-    // index                    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10]
-    let expected: [usize; 11] = [0, 0, 0, 0, 1, 3, 6, 7, 6, 3, 1];
+    let got = DiceGame::DIRAC.positions_after_turn(start);
+    // index                         [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10]
+    let expected: Vec<usize> = vec![0, 0, 0, 0, 1, 3, 6, 7, 6, 3, 1];
     assert_eq!(expected, got);
 }
 
 #[test]
 fn test_roll_dirac_dice_2() {
     let start = 5;
-    let got = roll_dirac_dice_thre_times(start);
+    let got = DiceGame::DIRAC.positions_after_turn(start);
 
-    // index                    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10]
-    let expected: [usize; 11] = [0, 7, 6, 3, 1, 0, 0, 0, 1, 3, 6];
+    // index                         [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10]
+    let expected: Vec<usize> = vec![0, 7, 6, 3, 1, 0, 0, 0, 1, 3, 6];
     assert_eq!(expected, got);
 }
 
-// #[test]
-// fn test_part2() {
-// let got = part2(4, 8);
-//     assert_eq!(444356092776315, got);
-// }
+#[test]
+fn test_part2() {
+    let got = part2(&[4, 8]);
+    assert_eq!(444356092776315, got);
+}
+
+#[test]
+fn test_part2_actual() {
+    let input_str =
+        std::fs::read_to_string("input/day21.txt").expect("Failed to read day 21 input");
+    let starts = parse_input(&input_str);
+    let got = part2(&starts);
+    assert_eq!(306719685234774, got);
+}
+
+#[test]
+fn test_count_wins() {
+    let got = DiceGame::DIRAC.count_wins(&[4, 8]);
+    assert_eq!(vec![444356092776315, 341960390180808], got);
+
+    // A single roll of a 3-sided die always reaches a score of 1, so there are only 3
+    // universes, and player 1 wins all of them
+    let game = DiceGame {
+        board_size: 10,
+        winning_score: 1,
+        die_sides: 3,
+        rolls_per_turn: 1,
+        n_players: 3,
+    };
+    assert_eq!(vec![3, 0, 0], game.count_wins(&[1, 2, 3]));
+}
+
+#[test]
+fn test_count_wins_matches_deterministic() {
+    // A 1-sided die never splits the universe, so exactly one player wins the one game
+    let game = DiceGame {
+        board_size: 7,
+        winning_score: 30,
+        die_sides: 1,
+        rolls_per_turn: 2,
+        n_players: 3,
+    };
+    let starts = [1, 4, 6];
+    let (_, scores) = game.play_deterministic(&starts);
+    let winner = scores
+        .iter()
+        .position(|&score| score >= game.winning_score)
+        .unwrap();
+
+    let mut expected = vec![0; 3];
+    expected[winner] = 1;
+    assert_eq!(expected, game.count_wins(&starts));
+}
+
+#[test]
+fn test_analyse() {
+    let game = DiceGame::DIRAC;
+    let got = game.analyse(&[4, 8]);
+
+    // Someone always wins, and everyone always finishes with some score
+    let total: f64 = got.win_probability.iter().sum();
+    assert!((total - 1.0).abs() < 1e-9);
+    for scores in &got.final_scores {
+        let total: f64 = scores.iter().sum();
+        assert!((total - 1.0).abs() < 1e-9);
+    }
+
+    // The winner's score is always at least the winning score
+    let p1_wins: f64 = got.final_scores[0][game.winning_score..].iter().sum();
+    assert!((p1_wins - got.win_probability[0]).abs() < 1e-9);
+
+    // Each player needs at least 3 turns to reach 21, and the game can't go past 10 turns
+    // each
+    assert!(got.expected_turns > 5.0 && got.expected_turns < 20.0);
+}
+
+#[test]
+fn test_analyse_matches_deterministic() {
+    // A 1-sided die never splits the universe, so the result is certain
+    let game = DiceGame {
+        board_size: 7,
+        winning_score: 30,
+        die_sides: 1,
+        rolls_per_turn: 2,
+        n_players: 3,
+    };
+    let starts = [1, 4, 6];
+    let (n_die_rolls, scores) = game.play_deterministic(&starts);
+    let got = game.analyse(&starts);
+
+    assert_eq!((n_die_rolls / 2) as f64, got.expected_turns);
+    for (player, &score) in scores.iter().enumerate() {
+        let won = if score >= game.winning_score {
+            1.0
+        } else {
+            0.0
+        };
+        assert_eq!(won, got.win_probability[player]);
+        assert_eq!(1.0, got.final_scores[player][score]);
+    }
+}
+
+#[test]
+fn test_analysis_output() {
+    // Keep the game small, so that analysing every pair of starting positions is quick
+    let game = DiceGame {
+        board_size: 10,
+        winning_score: 8,
+        ..DiceGame::DIRAC
+    };
+
+    let table = win_probability_table(&game);
+    assert_eq!(11, table.lines().count());
+    assert!(table
+        .lines()
+        .skip(1)
+        .all(|line| line.split_whitespace().count() == 11));
+
+    let csv = analysis_csv(&game);
+    let mut lines = csv.lines();
+    let header = lines.next().unwrap();
+    assert!(header.starts_with("p1_start,p2_start,player,win_probability,expected_turns,"));
+    let n_cols = header.split(',').count();
+    assert_eq!(5 + 18, n_cols);
+    assert_eq!(200, lines.clone().count());
+    assert!(lines.all(|line| line.split(',').count() == n_cols));
+}
+
+#[test]
+fn test_examples() {
+    for check in puzzle::check_examples(EXAMPLES, PARTS) {
+        assert!(check.is_ok(), "{}", check);
+    }
+}
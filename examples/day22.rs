@@ -1,5 +1,8 @@
 use std::{collections::HashSet, ops::RangeInclusive};
 
+use aoc_2021_rs::crosscheck::{self, Crosscheck};
+use aoc_2021_rs::puzzle::{self, Example};
+
 fn parse_input(input: &str) -> Vec<(bool, PointRanges)> {
     input.lines().map(parse_line).collect()
 }
@@ -23,6 +26,54 @@ impl PointRanges {
         PointRanges { x, y, z }
     }
 
+    /// How many cubes there are in the ranges
+    fn volume(&self) -> u64 {
+        if self.is_empty() {
+            return 0;
+        }
+        [&self.x, &self.y, &self.z]
+            .iter()
+            .map(|r| (r.end() - r.start() + 1) as u64)
+            .product()
+    }
+
+    /// The cubes in `self` that are not in `other`, as ranges that do not overlap. The parts
+    /// outside `other` are sliced off along x, then y, then z, leaving the intersection out.
+    fn difference(&self, other: &PointRanges) -> Vec<PointRanges> {
+        if self.intersection(other).is_empty() {
+            return vec![self.clone()];
+        }
+
+        let mut result = Vec::new();
+        for x in range_difference(&self.x, &other.x) {
+            result.push(PointRanges {
+                x,
+                y: self.y.clone(),
+                z: self.z.clone(),
+            });
+        }
+        let x = range_intersection(&self.x, &other.x);
+        for y in range_difference(&self.y, &other.y) {
+            result.push(PointRanges {
+                x: x.clone(),
+                y,
+                z: self.z.clone(),
+            });
+        }
+        let y = range_intersection(&self.y, &other.y);
+        for z in range_difference(&self.z, &other.z) {
+            result.push(PointRanges {
+                x: x.clone(),
+                y: y.clone(),
+                z,
+            });
+        }
+
+        // `range_difference` can give empty ranges
+        result.retain(|pr| !pr.is_empty());
+        result
+    }
+
     /// union could/will end up with multiple PointRanges Based on the various cases
     ///  _____
     /// |     |
@@ -146,13 +197,19 @@ fn parse_line(line: &str) -> (bool, PointRanges) {
     (b, pr)
 }
 
-fn is_outside_range(p: &PointRanges, low: i64, hi: i64) -> bool {
-    (p.x.start() < &low)
-        | (p.x.end() > &hi)
-        | (p.y.start() < &low)
-        | (p.y.end() > &hi)
-        | (p.z.start() < &low)
-        | (p.z.end() > &hi)
+/// The steps of the initialization procedure, which only touch the cubes within 50 of the
+/// origin. Each step is cut down to the cubes in that region, and dropped if there are none.
+fn initialization_steps(cubes: &[(bool, PointRanges)]) -> Vec<(bool, PointRanges)> {
+    let region = PointRanges {
+        x: -50..=50,
+        y: -50..=50,
+        z: -50..=50,
+    };
+    cubes
+        .iter()
+        .map(|(b, pr)| (*b, pr.intersection(&region)))
+        .filter(|(_, pr)| !pr.is_empty())
+        .collect()
 }
 
 fn pointranges_to_hashset(pr: &PointRanges) -> HashSet<(i64, i64, i64)> {
@@ -170,30 +227,48 @@ fn pointranges_to_hashset(pr: &PointRanges) -> HashSet<(i64, i64, i64)> {
     result
 }
 
-fn part1(cubes: &[(bool, PointRanges)]) -> usize {
+/// Count the cubes that are on by keeping every one of them in a set. This is only any good
+/// for small regions, but is simple enough to check `count_on` against.
+fn count_on_by_cube(cubes: &[(bool, PointRanges)]) -> usize {
     cubes
         .iter()
-        // Filter out any that exist outside of -50..50 (inclusive)
-        .filter(|(_, pr)| !is_outside_range(pr, -50, 50))
-        // Create hashsets of each
         .map(|(b, pr)| (b, pointranges_to_hashset(pr)))
-        // Fold into a single hashset
-        .reduce(|(_, acc), (b, hs)| {
+        .fold(HashSet::new(), |mut acc, (b, hs)| {
             if *b {
-                // Get the union
-                let new_hs = acc.union(&hs).copied().collect::<HashSet<_>>();
-                (b, new_hs)
+                acc.extend(hs);
             } else {
-                // Get the difference
-                let new_hs = acc.difference(&hs).copied().collect::<HashSet<_>>();
-                (b, new_hs)
+                acc.retain(|p| !hs.contains(p));
             }
+            acc
         })
-        .expect("Apprently the iterator was empty")
-        .1
         .len()
 }
 
+/// Count the cubes that are on by keeping them as ranges that do not overlap. Each step
+/// takes its cubes out of the ranges that are on, then adds them back as one range if it
+/// turns them on.
+fn count_on(cubes: &[(bool, PointRanges)]) -> u64 {
+    let mut on: Vec<PointRanges> = Vec::new();
+    for (b, pr) in cubes {
+        on = on
+            .iter()
+            .flat_map(|existing| existing.difference(pr))
+            .collect();
+        if *b {
+            on.push(pr.clone());
+        }
+    }
+    on.iter().map(PointRanges::volume).sum()
+}
+
+fn part1(cubes: &[(bool, PointRanges)]) -> u64 {
+    count_on(&initialization_steps(cubes))
+}
+
+fn part2(cubes: &[(bool, PointRanges)]) -> u64 {
+    count_on(cubes)
+}
+
 /// range_union is commutative
 fn range_union(x: &RangeInclusive<i64>, y: &RangeInclusive<i64>) -> Vec<RangeInclusive<i64>> {
     // Following cases
@@ -243,9 +318,10 @@ fn range_intersection(x: &RangeInclusive<i64>, y: &RangeInclusive<i64>) -> Range
         return y.clone();
     }
 
-    // If there is no overlap, returns an empty range (make sure to check for it)
+    // If there is no overlap, returns an empty range (make sure to check for it). This can't
+    // be `y` backwards, as that isn't empty if `y` is a single point.
     if (x.end() < y.start()) || (y.end() < x.start()) {
-        return *y.end()..=*y.start();
+        return RangeInclusive::new(1, 0);
     }
 
     // Otherwise, get the start and end of the new range
@@ -340,18 +416,140 @@ fn reduce_ranges(ranges: &[(bool, RangeInclusive<i64>)]) -> Vec<RangeInclusive<i
     final_ranges
 }
 
-fn solve(
-    xs: &[(bool, RangeInclusive<i64>)],
-    ys: &[(bool, RangeInclusive<i64>)],
-    zs: &[(bool, RangeInclusive<i64>)],
-) {
-    // Reduce all of the xs, all of the ys, and all of the zs.
-    let final_xs = reduce_ranges(xs);
-    let final_ys = reduce_ranges(ys);
-    let final_zs = reduce_ranges(zs);
+/// The examples from the puzzle description
+const EXAMPLES: &[Example] = &[
+    Example {
+        input: "on x=10..12,y=10..12,z=10..12
+on x=11..13,y=11..13,z=11..13
+off x=9..11,y=9..11,z=9..11
+on x=10..10,y=10..10,z=10..10",
+        part1: Some("39"),
+        part2: None,
+    },
+    Example {
+        input: "on x=-20..26,y=-36..17,z=-47..7
+on x=-20..33,y=-21..23,z=-26..28
+on x=-22..28,y=-29..23,z=-38..16
+on x=-46..7,y=-6..46,z=-50..-1
+on x=-49..1,y=-3..46,z=-24..28
+on x=2..47,y=-22..22,z=-23..27
+on x=-27..23,y=-28..26,z=-21..29
+on x=-39..5,y=-6..47,z=-3..44
+on x=-30..21,y=-8..43,z=-13..34
+on x=-22..26,y=-27..20,z=-29..19
+off x=-48..-32,y=26..41,z=-47..-37
+on x=-12..35,y=6..50,z=-50..-2
+off x=-48..-32,y=-32..-16,z=-15..-5
+on x=-18..26,y=-33..15,z=-7..46
+off x=-40..-22,y=-38..-28,z=23..41
+on x=-16..35,y=-41..10,z=-47..6
+off x=-32..-23,y=11..30,z=-14..3
+on x=-49..-5,y=-3..45,z=-29..18
+off x=18..30,y=-20..-8,z=-3..13
+on x=-41..9,y=-7..43,z=-33..15
+on x=-54112..-39298,y=-85059..-49293,z=-27449..7877
+on x=967..23432,y=45373..81175,z=27513..53682",
+        part1: Some("590784"),
+        part2: Some("39769202357779"),
+    },
+    Example {
+        input: "on x=-5..47,y=-31..22,z=-19..33
+on x=-44..5,y=-27..21,z=-14..35
+on x=-49..-1,y=-11..42,z=-10..38
+on x=-20..34,y=-40..6,z=-44..1
+off x=26..39,y=40..50,z=-2..11
+on x=-41..5,y=-41..6,z=-36..8
+off x=-43..-33,y=-45..-28,z=7..25
+on x=-33..15,y=-32..19,z=-34..11
+off x=35..47,y=-46..-34,z=-11..5
+on x=-14..36,y=-6..44,z=-16..29
+on x=-57795..-6158,y=29564..72030,z=20435..90618
+on x=36731..105352,y=-21140..28532,z=16094..90401
+on x=30999..107136,y=-53464..15513,z=8553..71215
+on x=13528..83982,y=-99403..-27377,z=-24141..23996
+on x=-72682..-12347,y=18159..111354,z=7391..80950
+on x=-1060..80757,y=-65301..-20884,z=-103788..-16709
+on x=-83015..-9461,y=-72160..-8347,z=-81239..-26856
+on x=-52752..22273,y=-49450..9096,z=54442..119054
+on x=-29982..40483,y=-108474..-28371,z=-24328..38471
+on x=-4958..62750,y=40422..118853,z=-7672..65583
+on x=55694..108686,y=-43367..46958,z=-26781..48729
+on x=-98497..-18186,y=-63569..3412,z=1232..88485
+on x=-726..56291,y=-62629..13224,z=18033..85226
+on x=-110886..-34664,y=-81338..-8658,z=8914..63723
+on x=-55829..24974,y=-16897..54165,z=-121762..-28058
+on x=-65152..-11147,y=22489..91432,z=-58782..1780
+on x=-120100..-32970,y=-46592..27473,z=-11695..61039
+on x=-18631..37533,y=-124565..-50804,z=-35667..28308
+on x=-57817..18248,y=49321..117703,z=5745..55881
+on x=14781..98692,y=-1341..70827,z=15753..70151
+on x=-34419..55919,y=-19626..40991,z=39015..114138
+on x=-60785..11593,y=-56135..2999,z=-95368..-26915
+on x=-32178..58085,y=17647..101866,z=-91405..-8878
+on x=-53655..12091,y=50097..105568,z=-75335..-4862
+on x=-111166..-40997,y=-71714..2688,z=5609..50954
+on x=-16602..70118,y=-98693..-44401,z=5197..76897
+on x=16383..101554,y=4615..83635,z=-44907..18747
+off x=-95822..-15171,y=-19987..48940,z=10804..104439
+on x=-89813..-14614,y=16069..88491,z=-3297..45228
+on x=41075..99376,y=-20427..49978,z=-52012..13762
+on x=-21330..50085,y=-17944..62733,z=-112280..-30197
+on x=-16478..35915,y=36008..118594,z=-7885..47086
+off x=-98156..-27851,y=-49952..43171,z=-99005..-8456
+off x=2032..69770,y=-71013..4824,z=7471..94418
+on x=43670..120875,y=-42068..12382,z=-24787..38892
+off x=37514..111226,y=-45862..25743,z=-16714..54663
+off x=25699..97951,y=-30668..59918,z=-15349..69697
+off x=-44271..17935,y=-9516..60759,z=49131..112598
+on x=-61695..-5813,y=40978..94975,z=8655..80240
+off x=-101086..-9439,y=-7088..67543,z=33935..83858
+off x=18020..114017,y=-48931..32606,z=21474..89843
+off x=-77139..10506,y=-89994..-18797,z=-80..59318
+off x=8476..79288,y=-75520..11602,z=-96624..-24783
+on x=-47488..-1262,y=24338..100707,z=16292..72967
+off x=-84341..13987,y=2429..92914,z=-90671..-1318
+off x=-37810..49457,y=-71013..-7894,z=-105357..-13188
+off x=-27365..46395,y=31009..98017,z=15428..76570
+off x=-70369..-16548,y=22648..78696,z=-1892..86821
+on x=-53470..21291,y=-120233..-33476,z=-44150..38147
+off x=-93533..-4276,y=-16170..68771,z=-104985..-24507",
+        part1: Some("474140"),
+        part2: Some("2758514936282235"),
+    },
+];
+
+/// Each part, solved straight from the input
+const PARTS: [Option<puzzle::Part>; 2] = [Some(solve_part1), Some(solve_part2)];
+
+fn solve_part1(input: &str) -> Result<String, String> {
+    Ok(part1(&parse_input(input)).to_string())
+}
+
+fn solve_part2(input: &str) -> Result<String, String> {
+    Ok(part2(&parse_input(input)).to_string())
+}
+
+/// Counting with ranges, checked against counting every cube
+const CROSSCHECKS: &[Crosscheck] = &[Crosscheck {
+    name: "Part 1 by ranges",
+    reference: by_cube_part1,
+    optimised: solve_part1,
+}];
+
+fn by_cube_part1(input: &str) -> Result<String, String> {
+    Ok(count_on_by_cube(&initialization_steps(&parse_input(input))).to_string())
 }
 
 fn main() {
+    // Pass `--example` to check the solutions against the puzzle's examples instead
+    if std::env::args().any(|arg| arg == "--example") {
+        std::process::exit(puzzle::run_examples(EXAMPLES, PARTS));
+    }
+    // Pass `--crosscheck` to check the quick solutions against simple ones on random inputs
+    if std::env::args().any(|arg| arg == "--crosscheck") {
+        std::process::exit(crosscheck::run(22, CROSSCHECKS));
+    }
+
     let setup_time = std::time::Instant::now();
 
     let input_str =
@@ -365,13 +563,13 @@ fn main() {
     println!("Part 1 took {:.6} ms", part1_time.elapsed().as_millis());
 
     // Part 2
-    // let part2_time = std::time::Instant::now();
-    // let part2_result = solve(&input_image, &algo, 50);
-    // println!("Part 2 took {:.6} ms", part2_time.elapsed().as_millis());
+    let part2_time = std::time::Instant::now();
+    let part2_result = part2(&cubes);
+    println!("Part 2 took {:.6} ms", part2_time.elapsed().as_millis());
 
     println!();
     println!("Part 1 result: {}", part1_result);
-    // println!("Part 2 result: {}", part2_result);
+    println!("Part 2 result: {}", part2_result);
 }
 
 #[test]
@@ -431,6 +629,66 @@ on x=10..10,y=10..10,z=10..10";
     assert_eq!(39, got);
 }
 
+#[test]
+fn test_count_on() {
+    let input_str = "on x=-20..26,y=-36..17,z=-47..7
+on x=-20..33,y=-21..23,z=-26..28
+on x=-22..28,y=-29..23,z=-38..16
+off x=-48..-32,y=26..41,z=-47..-37
+on x=-12..35,y=6..50,z=-50..-2
+off x=-40..-22,y=-38..-28,z=23..41
+on x=-54112..-39298,y=-85059..-49293,z=-27449..7877
+on x=0..60,y=0..60,z=0..60";
+    let v = parse_input(input_str);
+    let steps = initialization_steps(&v);
+    // The far away step is dropped, and the last one is cut down to 0..=50 on each side
+    assert_eq!(7, steps.len());
+    assert_eq!(51 * 51 * 51, steps[6].1.volume());
+    assert_eq!(count_on_by_cube(&steps) as u64, part1(&v));
+
+    // An `off` step first turns nothing off
+    let v = parse_input("off x=0..1,y=0..1,z=0..1\non x=1..2,y=1..2,z=1..2");
+    assert_eq!(8, count_on_by_cube(&v));
+    assert_eq!(8, part2(&v));
+
+    let v = parse_input("on x=-100000..99999,y=0..9,z=0..0\noff x=5..5,y=5..5,z=0..0");
+    assert_eq!(2_000_000 - 1, part2(&v));
+}
+
+#[test]
+fn test_difference() {
+    let a = PointRanges {
+        x: 0..=9,
+        y: 0..=9,
+        z: 0..=9,
+    };
+    let b = PointRanges {
+        x: 3..=5,
+        y: -5..=5,
+        z: 8..=20,
+    };
+    let pieces = a.difference(&b);
+    let total: u64 = pieces.iter().map(PointRanges::volume).sum();
+    assert_eq!(1000 - 3 * 6 * 2, total);
+    for (idx, piece) in pieces.iter().enumerate() {
+        assert!(piece.intersection(&b).is_empty());
+        for other in &pieces[idx + 1..] {
+            assert!(piece.intersection(other).is_empty());
+        }
+    }
+    assert_eq!(
+        vec![a.clone()],
+        a.difference(&PointRanges { x: 10..=12, ..b })
+    );
+}
+
+#[test]
+fn test_crosschecks() {
+    for check in CROSSCHECKS {
+        assert_eq!(Ok(None), check.find_disagreement(22, 3, Some(30)));
+    }
+}
+
 #[test]
 fn test_range_union_some_overlap() {
     let x = 0..=10;
@@ -487,6 +745,14 @@ fn test_range_intersection_no_intersection_2() {
     assert!(got.is_empty());
 }
 
+#[test]
+fn test_range_intersection_no_intersection_single_point() {
+    let x = 0..=10;
+    let y = 12..=12;
+    let got = range_intersection(&x, &y);
+    assert!(got.is_empty());
+}
+
 #[test]
 fn test_range_intersection_some_intersection() {
     let x = 0..=10;
@@ -564,3 +830,10 @@ fn test_reduce_ranges_2() {
     let got = reduce_ranges(&ranges);
     assert_eq!(expected, got);
 }
+
+#[test]
+fn test_examples() {
+    for check in puzzle::check_examples(EXAMPLES, PARTS) {
+        assert!(check.is_ok(), "{}", check);
+    }
+}
@@ -0,0 +1,467 @@
+use std::collections::{BinaryHeap, HashMap};
+
+use aoc_2021_rs::puzzle::{self, Example};
+
+const HALLWAY_LEN: usize = 11;
+const N_ROOMS: usize = 4;
+const MAX_DEPTH: usize = 4;
+const EMPTY: u8 = b'.';
+
+/// Hallway positions just outside each room. Amphipods never stop on these
+const DOORS: [usize; N_ROOMS] = [2, 4, 6, 8];
+
+/// Energy used for each step by A, B, C and D
+const ENERGY: [usize; N_ROOMS] = [1, 10, 100, 1000];
+
+/// The extra rows folded into the middle of the diagram for part 2
+const UNFOLDED_ROWS: [[u8; N_ROOMS]; 2] = [*b"DCBA", *b"DBAC"];
+
+/// Where every amphipod is. Each amphipod is stored as its letter, and an empty space as `.`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct Burrow {
+    hallway: [u8; HALLWAY_LEN],
+    /// Each room from the top down. Only the first `depth` places are used
+    rooms: [[u8; MAX_DEPTH]; N_ROOMS],
+    depth: usize,
+}
+
+/// Which room an amphipod belongs in
+fn home(amphipod: u8) -> usize {
+    (amphipod - b'A') as usize
+}
+
+fn parse_input(input: &str) -> Result<Burrow, String> {
+    let lines: Vec<&str> = input.lines().collect();
+
+    let hallway_line = lines.get(1).ok_or("There is no hallway")?;
+    let hallway: Vec<u8> = hallway_line.trim().trim_matches('#').bytes().collect();
+    if hallway.len() != HALLWAY_LEN || hallway.iter().any(|&c| c != EMPTY) {
+        return Err(format!(
+            "The hallway should be {} empty spaces, but is {:?}",
+            HALLWAY_LEN, hallway_line
+        ));
+    }
+
+    let mut rooms = [[EMPTY; MAX_DEPTH]; N_ROOMS];
+    let mut depth = 0;
+    for line in lines.iter().skip(2) {
+        let row: Vec<u8> = line.bytes().filter(|c| !b"# ".contains(c)).collect();
+        if row.is_empty() {
+            continue;
+        }
+        if row.len() != N_ROOMS || row.iter().any(|c| !(b'A'..=b'D').contains(c)) {
+            return Err(format!("Could not read the amphipods in {:?}", line));
+        }
+        if depth == MAX_DEPTH {
+            return Err(format!("Rooms can be at most {} deep", MAX_DEPTH));
+        }
+
+        for (room, &amphipod) in rooms.iter_mut().zip(&row) {
+            room[depth] = amphipod;
+        }
+        depth += 1;
+    }
+
+    if depth == 0 {
+        return Err("There are no rooms".to_string());
+    }
+    for amphipod in b'A'..=b'D' {
+        let count = rooms.iter().flatten().filter(|&&c| c == amphipod).count();
+        if count != depth {
+            return Err(format!(
+                "There should be {} of amphipod {}, but there are {}",
+                depth, amphipod as char, count
+            ));
+        }
+    }
+
+    Ok(Burrow {
+        hallway: [EMPTY; HALLWAY_LEN],
+        rooms,
+        depth,
+    })
+}
+
+impl Burrow {
+    /// Add the two rows that were folded out of the diagram
+    fn unfold(&self) -> Result<Burrow, String> {
+        if self.depth != 2 {
+            return Err(format!(
+                "Only rooms 2 deep can be unfolded, not {} deep",
+                self.depth
+            ));
+        }
+
+        let mut unfolded = *self;
+        for (room_idx, room) in unfolded.rooms.iter_mut().enumerate() {
+            *room = [
+                self.rooms[room_idx][0],
+                UNFOLDED_ROWS[0][room_idx],
+                UNFOLDED_ROWS[1][room_idx],
+                self.rooms[room_idx][1],
+            ];
+        }
+        unfolded.depth = 4;
+        Ok(unfolded)
+    }
+
+    fn is_organised(&self) -> bool {
+        self.rooms.iter().enumerate().all(|(room_idx, room)| {
+            room[..self.depth]
+                .iter()
+                .all(|&c| c != EMPTY && home(c) == room_idx)
+        })
+    }
+
+    /// Whether a room only has amphipods that belong there, so they can move in
+    fn room_accepts(&self, room_idx: usize) -> bool {
+        self.rooms[room_idx][..self.depth]
+            .iter()
+            .all(|&c| c == EMPTY || home(c) == room_idx)
+    }
+
+    /// Whether every hallway space between `from` and `to` is empty, not counting `from`
+    fn hallway_clear(&self, from: usize, to: usize) -> bool {
+        let spaces = if from < to {
+            from + 1..to + 1
+        } else {
+            to..from
+        };
+        self.hallway[spaces].iter().all(|&c| c == EMPTY)
+    }
+
+    /// Every burrow that can be reached with one move, and the energy that move takes.
+    ///
+    /// Amphipods either go from the hallway straight into their own room, once it only has
+    /// their own kind in it, or leave a room that still has others in it and stop in the
+    /// hallway. Going straight from one room to another costs the same as stopping in the
+    /// hallway between them, so doesn't need to be a separate move.
+    fn moves(&self) -> Vec<(usize, Burrow)> {
+        let mut result = Vec::new();
+
+        for (hall_idx, &amphipod) in self.hallway.iter().enumerate() {
+            if amphipod == EMPTY {
+                continue;
+            }
+            let room_idx = home(amphipod);
+            if !self.room_accepts(room_idx) || !self.hallway_clear(hall_idx, DOORS[room_idx]) {
+                continue;
+            }
+
+            // Go as deep into the room as possible
+            let place = self.rooms[room_idx][..self.depth]
+                .iter()
+                .rposition(|&c| c == EMPTY)
+                .expect("An accepting room can't be full of the wrong amphipods");
+
+            let mut next = *self;
+            next.hallway[hall_idx] = EMPTY;
+            next.rooms[room_idx][place] = amphipod;
+            let steps = hall_idx.abs_diff(DOORS[room_idx]) + place + 1;
+            result.push((steps * ENERGY[room_idx], next));
+        }
+
+        for (room_idx, &door) in DOORS.iter().enumerate() {
+            if self.room_accepts(room_idx) {
+                continue;
+            }
+            let place = match self.rooms[room_idx][..self.depth]
+                .iter()
+                .position(|&c| c != EMPTY)
+            {
+                Some(place) => place,
+                None => continue,
+            };
+            let amphipod = self.rooms[room_idx][place];
+
+            for hall_idx in (0..HALLWAY_LEN).filter(|idx| !DOORS.contains(idx)) {
+                // The door itself is always empty, so it only matters what is past it
+                if !self.hallway_clear(door, hall_idx) {
+                    continue;
+                }
+
+                let mut next = *self;
+                next.rooms[room_idx][place] = EMPTY;
+                next.hallway[hall_idx] = amphipod;
+                let steps = place + 1 + hall_idx.abs_diff(door);
+                result.push((steps * ENERGY[home(amphipod)], next));
+            }
+        }
+
+        result
+    }
+
+    /// The least energy it could possibly take to organise the burrow, as if the amphipods
+    /// could walk through each other and only needed the top place in each room
+    fn estimate_remaining(&self) -> usize {
+        let in_hallway: usize = self
+            .hallway
+            .iter()
+            .enumerate()
+            .filter(|(_, &c)| c != EMPTY)
+            .map(|(hall_idx, &c)| (hall_idx.abs_diff(DOORS[home(c)]) + 1) * ENERGY[home(c)])
+            .sum();
+
+        let mut in_rooms = 0;
+        for (room_idx, room) in self.rooms.iter().enumerate() {
+            for (place, &c) in room[..self.depth].iter().enumerate() {
+                if c == EMPTY {
+                    continue;
+                }
+                let target = home(c);
+                let settled = target == room_idx
+                    && room[place + 1..self.depth].iter().all(|&below| below == c);
+
+                // Anything in the wrong place has to leave, walk to its room, and go in. If
+                // it is in its own room, it needs to step out of the way and back
+                let steps = if settled {
+                    0
+                } else if target == room_idx {
+                    place + 1 + 2 + 1
+                } else {
+                    place + 1 + DOORS[room_idx].abs_diff(DOORS[target]) + 1
+                };
+                in_rooms += steps * ENERGY[target];
+            }
+        }
+
+        in_hallway + in_rooms
+    }
+}
+
+impl std::fmt::Display for Burrow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "#############")?;
+        writeln!(f, "#{}#", String::from_utf8_lossy(&self.hallway))?;
+        for place in 0..self.depth {
+            let row: Vec<String> = self
+                .rooms
+                .iter()
+                .map(|room| (room[place] as char).to_string())
+                .collect();
+            if place == 0 {
+                writeln!(f, "###{}###", row.join("#"))?;
+            } else {
+                writeln!(f, "  #{}#", row.join("#"))?;
+            }
+        }
+        write!(f, "  #########")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct State {
+    /// Energy used so far, plus the least that could still be needed
+    estimate: usize,
+    energy: usize,
+    burrow: Burrow,
+}
+
+impl Ord for State {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        other
+            .estimate
+            .cmp(&self.estimate)
+            .then_with(|| self.burrow.cmp(&other.burrow))
+    }
+}
+
+impl PartialOrd for State {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// A* search for the least energy needed to organise the amphipods
+fn organise(start: Burrow) -> Result<usize, String> {
+    let mut frontier = BinaryHeap::new();
+    frontier.push(State {
+        estimate: start.estimate_remaining(),
+        energy: 0,
+        burrow: start,
+    });
+
+    let mut energies: HashMap<Burrow, usize> = HashMap::new();
+    energies.insert(start, 0);
+
+    while let Some(State { energy, burrow, .. }) = frontier.pop() {
+        if burrow.is_organised() {
+            return Ok(energy);
+        }
+        if energy > energies[&burrow] {
+            continue;
+        }
+
+        for (move_energy, next) in burrow.moves() {
+            let next_energy = energy + move_energy;
+            if energies.get(&next).is_none_or(|&known| next_energy < known) {
+                energies.insert(next, next_energy);
+                frontier.push(State {
+                    estimate: next_energy + next.estimate_remaining(),
+                    energy: next_energy,
+                    burrow: next,
+                });
+            }
+        }
+    }
+
+    Err("There is no way to organise the amphipods".to_string())
+}
+
+fn part1(burrow: &Burrow) -> Result<usize, String> {
+    organise(*burrow)
+}
+
+fn part2(burrow: &Burrow) -> Result<usize, String> {
+    organise(burrow.unfold()?)
+}
+
+/// The examples from the puzzle description
+const EXAMPLES: &[Example] = &[Example {
+    input: "#############
+#...........#
+###B#C#B#D###
+  #A#D#C#A#
+  #########",
+    part1: Some("12521"),
+    part2: Some("44169"),
+}];
+
+/// Each part, solved straight from the input
+const PARTS: [Option<puzzle::Part>; 2] = [Some(solve_part1), Some(solve_part2)];
+
+fn solve_part1(input: &str) -> Result<String, String> {
+    part1(&parse_input(input)?).map(|energy| energy.to_string())
+}
+
+fn solve_part2(input: &str) -> Result<String, String> {
+    part2(&parse_input(input)?).map(|energy| energy.to_string())
+}
+
+fn main() {
+    // Pass `--example` to check the solutions against the puzzle's examples instead
+    if std::env::args().any(|arg| arg == "--example") {
+        std::process::exit(puzzle::run_examples(EXAMPLES, PARTS));
+    }
+
+    let setup_time = std::time::Instant::now();
+
+    let input_str =
+        std::fs::read_to_string("input/day23.txt").expect("Failed to read day 23 input");
+    let burrow = match parse_input(&input_str) {
+        Ok(burrow) => burrow,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    println!("Setup took {:.6} µs", setup_time.elapsed().as_micros());
+
+    // Part 1
+    let part1_time = std::time::Instant::now();
+    let part1_result = part1(&burrow);
+    println!("Part 1 took {:.6} µs", part1_time.elapsed().as_micros());
+
+    // Part 2
+    let part2_time = std::time::Instant::now();
+    let part2_result = part2(&burrow);
+    println!("Part 2 took {:.6} µs", part2_time.elapsed().as_micros());
+
+    println!();
+    match part1_result {
+        Ok(result) => println!("Part 1 result: {}", result),
+        Err(e) => println!("Part 1 failed: {}", e),
+    }
+    match part2_result {
+        Ok(result) => println!("Part 2 result: {}", result),
+        Err(e) => println!("Part 2 failed: {}", e),
+    }
+}
+
+#[test]
+fn test_parse_input() {
+    let burrow = parse_input(EXAMPLES[0].input).unwrap();
+    assert_eq!(2, burrow.depth);
+    assert_eq!([*b"BA..", *b"CD..", *b"BC..", *b"DA.."], burrow.rooms);
+    assert_eq!(EXAMPLES[0].input, burrow.to_string());
+}
+
+#[test]
+fn test_parse_input_errors() {
+    assert_eq!(
+        Err("There should be 2 of amphipod A, but there are 3".to_string()),
+        parse_input(
+            "#############
+#...........#
+###B#C#B#D###
+  #A#D#A#A#
+  #########"
+        )
+    );
+    assert!(parse_input(
+        "#############
+#.....A.....#
+###B#C#B#D###
+  #A#D#C#A#
+  #########"
+    )
+    .is_err());
+}
+
+#[test]
+fn test_unfold() {
+    let expected = "#############
+#...........#
+###B#C#B#D###
+  #D#C#B#A#
+  #D#B#A#C#
+  #A#D#C#A#
+  #########";
+    let burrow = parse_input(EXAMPLES[0].input).unwrap().unfold().unwrap();
+    assert_eq!(expected, burrow.to_string());
+    assert_eq!(Ok(burrow), parse_input(expected));
+}
+
+#[test]
+fn test_organise_small() {
+    // Only the A and B need swapping. The B has to wait in the hallway so the A can get out
+    let burrow = parse_input(
+        "#############
+#...........#
+###B#A#C#D###
+  #A#B#C#D#
+  #########",
+    )
+    .unwrap();
+    assert_eq!(Ok(46), organise(burrow));
+
+    // Already organised
+    let burrow = parse_input(
+        "#############
+#...........#
+###A#B#C#D###
+  #A#B#C#D#
+  #########",
+    )
+    .unwrap();
+    assert_eq!(Ok(0), organise(burrow));
+}
+
+#[test]
+fn test_part1() {
+    let burrow = parse_input(EXAMPLES[0].input).unwrap();
+    assert_eq!(Ok(12521), part1(&burrow));
+}
+
+#[test]
+fn test_part2() {
+    let burrow = parse_input(EXAMPLES[0].input).unwrap();
+    assert_eq!(Ok(44169), part2(&burrow));
+}
+
+#[test]
+fn test_examples() {
+    for check in puzzle::check_examples(EXAMPLES, PARTS) {
+        assert!(check.is_ok(), "{}", check);
+    }
+}
@@ -1,5 +1,8 @@
 use std::collections::VecDeque;
 
+use aoc_2021_rs::parallel;
+use aoc_2021_rs::puzzle::{self, Example};
+
 #[derive(Debug, Clone, Copy, Default, PartialEq)]
 struct State(i64, i64, i64, i64);
 
@@ -514,34 +517,156 @@ fn digits_to_number(digits: &VecDeque<i64>) -> i64 {
         .sum()
 }
 
-fn part1(monad: &[Instruction]) -> usize {
-    // Need to run increasingly smaller numbers through the monad until receiving a 0 in
-    // the z space. Return that number
+/// What one digit's block of the MONAD does. z is used as a stack of base 26 digits: a block
+/// that doesn't pop pushes its digit plus `y_add`, and one that does pops the top, and only
+/// pushes if the popped value plus `x_add` isn't its digit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Block {
+    pops: bool,
+    x_add: i64,
+    y_add: i64,
+}
 
-    // Start at 99999999999999 and count downwards
-    let answer = (11111111111111..99999999999999)
-        .rev()
-        // Get the digits of each number
-        .inspect(|x| {
-            if x % 10_000_000 == 0 {
-                println!("{}", x)
-            }
-        })
-        .map(digits)
-        // Remove any that have 0 in them
-        .filter(|v| !v.contains(&0))
-        // Run the program
-        .map(|v| (v.clone(), run_program(monad, &v)))
-        // Find one with 0 in the z space
-        .find(|(_, state)| state.3 == 0)
-        .expect("Could not valid monad input number");
-
-    digits_to_number(&answer.0)
+/// How many instructions each digit's block has
+const BLOCK_LEN: usize = 18;
+
+/// Split the MONAD into the blocks for each digit, which must all be in the usual shape, only
+/// differing in whether they pop and what they add to x and y
+fn parse_blocks(monad: &[Instruction]) -> Result<Vec<Block>, String> {
+    if monad.is_empty() || !monad.len().is_multiple_of(BLOCK_LEN) {
+        return Err(format!(
+            "The MONAD should be made of blocks of {} instructions",
+            BLOCK_LEN
+        ));
+    }
+    monad
+        .chunks(BLOCK_LEN)
+        .enumerate()
+        .map(
+            |(idx, block)| match (block[0], block[4], block[5], block[15]) {
+                (
+                    Instruction::Inp { field_idx: 0, .. },
+                    Instruction::Div {
+                        field_idx: 3,
+                        scnd_arg: IdxOrVal::Val(divisor @ (1 | 26)),
+                    },
+                    Instruction::Add {
+                        field_idx: 1,
+                        scnd_arg: IdxOrVal::Val(x_add),
+                    },
+                    Instruction::Add {
+                        field_idx: 2,
+                        scnd_arg: IdxOrVal::Val(y_add),
+                    },
+                ) => Ok(Block {
+                    pops: divisor == 26,
+                    x_add,
+                    y_add,
+                }),
+                _ => Err(format!(
+                    "Block {} of the MONAD is not in the usual shape",
+                    idx
+                )),
+            },
+        )
+        .collect()
+}
+
+/// The largest model number that a MONAD in the usual shape accepts, as its digits. For z to end at 0, every
+/// push must be popped again, and a block that pops must not push, so its digit has to be the
+/// digit of the block that pushed plus that block's `y_add` and its own `x_add`. That ties
+/// the digits together in pairs, and the largest number makes the bigger of each pair a 9.
+fn largest_from_blocks(blocks: &[Block]) -> Option<Vec<i64>> {
+    let mut digits = vec![0; blocks.len()];
+    let mut pushed: Vec<(usize, i64)> = Vec::new();
+    for (idx, block) in blocks.iter().enumerate() {
+        if !block.pops {
+            pushed.push((idx, block.y_add));
+            continue;
+        }
+        let (pushed_idx, y_add) = pushed.pop()?;
+        // This digit is the pushed one plus `difference`
+        let difference = y_add + block.x_add;
+        if difference.abs() > 8 {
+            return None;
+        }
+        digits[pushed_idx] = 9 - difference.max(0);
+        digits[idx] = 9 + difference.min(0);
+    }
+    // Anything left would still be on the stack at the end
+    pushed.is_empty().then_some(digits)
+}
+
+/// The largest and smallest 14 digit model numbers
+const LARGEST: usize = 99_999_999_999_999;
+const SMALLEST: usize = 11_111_111_111_111;
+
+/// How many model numbers a thread checks before taking the next chunk of them
+const CHUNK_SIZE: usize = 1_000_000;
+
+/// The largest model number that the monad accepts, by running increasingly smaller numbers
+/// through it until one leaves a 0 in the z space. The numbers are split into chunks, counting
+/// down, which are shared out between `jobs` threads.
+fn search_largest(monad: &[Instruction], chunk_size: usize, jobs: usize) -> Option<usize> {
+    let n_chunks = (LARGEST - SMALLEST + 1).div_ceil(chunk_size);
+    parallel::find_first(n_chunks, jobs, |chunk_idx| {
+        // Start at 99999999999999 and count downwards
+        let high = LARGEST - chunk_idx * chunk_size;
+        let low = (high + 1).saturating_sub(chunk_size).max(SMALLEST);
+        (low..=high)
+            .rev()
+            // Get the digits of each number
+            .map(digits)
+            // Remove any that have 0 in them
+            .filter(|v| !v.contains(&0))
+            // Find one that leaves 0 in the z space
+            .find(|v| run_program(monad, v).3 == 0)
+            .map(|v| {
+                digits_to_number(&v)
+                    .try_into()
+                    .expect("Could not convert i64 to usize")
+            })
+    })
+}
+
+/// The largest model number the MONAD accepts. One in the usual shape is solved from its
+/// blocks, and anything else is searched, sharing the numbers out between `jobs` threads.
+fn part1(monad: &[Instruction], jobs: usize) -> Result<usize, String> {
+    let blocks = match parse_blocks(monad) {
+        Ok(blocks) => blocks,
+        Err(_) => {
+            return search_largest(monad, CHUNK_SIZE, jobs)
+                .ok_or_else(|| "The MONAD doesn't accept any model number".to_string())
+        }
+    };
+    let digits: VecDeque<i64> = largest_from_blocks(&blocks)
+        .ok_or("The MONAD doesn't accept any model number")?
+        .into();
+    // The blocks only work like a stack if every push doesn't match its digit, so make sure
+    if run_program(monad, &digits).3 != 0 {
+        return Err("The MONAD rejected the model number its blocks should accept".to_string());
+    }
+    digits_to_number(&digits)
         .try_into()
-        .expect("Could not convert i64 to usize")
+        .map_err(|_| "Could not convert i64 to usize".to_string())
+}
+
+/// The puzzle description has no example program with an answer to check
+const EXAMPLES: &[Example] = &[];
+
+/// Each part, solved straight from the input. There is no solution to the second part yet.
+const PARTS: [Option<puzzle::Part>; 2] = [Some(solve_part1), None];
+
+fn solve_part1(input: &str) -> Result<String, String> {
+    Ok(part1(&parse_program(input), parallel::jobs())?.to_string())
 }
 
 fn main() {
+    // Pass `--example` to check the solutions against the puzzle's examples instead
+    if std::env::args().any(|arg| arg == "--example") {
+        std::process::exit(puzzle::run_examples(EXAMPLES, PARTS));
+    }
+
     let setup_time = std::time::Instant::now();
 
     let input_str =
@@ -549,9 +674,11 @@ fn main() {
     let monad: Vec<Instruction> = parse_program(&input_str);
     println!("Setup took {:.6} µs", setup_time.elapsed().as_micros());
 
-    // Part 1
+    // Part 1. Pass `--jobs=N` to search with N threads, rather than one per core, if the
+    // MONAD has to be searched.
     let part1_time = std::time::Instant::now();
-    let part1_result = part1(&monad);
+    let part1_result =
+        part1(&monad, parallel::jobs()).expect("Could not find a valid model number");
     println!("Part 1 took {:.6} ms", part1_time.elapsed().as_millis());
 
     // Part 2
@@ -715,3 +842,89 @@ mod w 2";
     let got = run_program(&program, &args);
     assert_eq!(expected, got);
 }
+
+#[test]
+fn test_largest_from_blocks() {
+    // The same shape of block as the real MONADs
+    let block = |divisor: i64, x_add: i64, y_add: i64| {
+        format!(
+            "inp w\nmul x 0\nadd x z\nmod x 26\ndiv z {}\nadd x {}\neql x w\neql x 0\n\
+             mul y 0\nadd y 25\nmul y x\nadd y 1\nmul z y\nmul y 0\nadd y w\nadd y {}\n\
+             mul y x\nadd z y\n",
+            divisor, x_add, y_add
+        )
+    };
+    let monads = [
+        [
+            block(1, 12, 7),
+            block(1, 11, 3),
+            block(26, -5, 2),
+            block(26, -9, 4),
+        ],
+        [
+            block(1, 14, 0),
+            block(26, 6, 1),
+            block(1, 10, 15),
+            block(26, -12, 0),
+        ],
+        // The second pair can't be matched, as the digits would be 9 apart
+        [
+            block(1, 14, 0),
+            block(26, -3, 1),
+            block(1, 10, 15),
+            block(26, -6, 0),
+        ],
+    ];
+    for monad in monads {
+        let monad = parse_program(&monad.concat());
+        let blocks = parse_blocks(&monad).unwrap();
+
+        // Try every 4 digit number, from the top
+        let expected = (0..9_i64.pow(4)).rev().find_map(|n| {
+            let digits: VecDeque<i64> =
+                (0..4).rev().map(|pos| n / 9_i64.pow(pos) % 9 + 1).collect();
+            (run_program(&monad, &digits).3 == 0).then(|| digits.into_iter().collect())
+        });
+        assert_eq!(expected, largest_from_blocks(&blocks));
+    }
+}
+
+#[test]
+fn test_search_largest() {
+    // Only accepts numbers whose tenth digit is 5, which is 40,000 numbers down from the top
+    let monad = parse_program(
+        "inp w\ninp w\ninp w\ninp w\ninp w\ninp w\ninp w\ninp w\ninp w\ninp z\nadd z -5",
+    );
+    for jobs in [1, 2, 8] {
+        assert_eq!(Some(99_999_999_959_999), search_largest(&monad, 1000, jobs));
+    }
+    // It isn't in the usual shape, so part 1 searches too
+    assert_eq!(Ok(99_999_999_959_999), part1(&monad, 2));
+}
+
+#[test]
+fn test_parse_blocks_unusual_shape() {
+    assert!(parse_blocks(&parse_program("inp w\nadd z w")).is_err());
+}
+
+#[test]
+fn test_part1_generated() {
+    for seed in 0..10 {
+        let monad = parse_program(&aoc_2021_rs::generate::generate(24, None, seed).unwrap());
+        assert!(part1(&monad, 1).is_ok());
+    }
+}
+
+#[test]
+fn test_part1_actual() {
+    let input_str =
+        std::fs::read_to_string("input/day24.txt").expect("Failed to read day 24 input");
+    assert_eq!(Ok(97919997299495), part1(&parse_program(&input_str), 1));
+}
+
+#[test]
+fn test_examples() {
+    for check in puzzle::check_examples(EXAMPLES, PARTS) {
+        assert!(check.is_ok(), "{}", check);
+    }
+}
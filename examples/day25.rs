@@ -1,3 +1,4 @@
+use aoc_2021_rs::puzzle::{self, Example};
 use ndarray::{Array2, ArrayView2};
 
 fn parse_input(input: &str) -> Array2<u8> {
@@ -18,76 +19,130 @@ fn parse_input(input: &str) -> Array2<u8> {
     result
 }
 
-fn step_in_direction(inp_arr: ArrayView2<u8>, herd_to_step: u8) -> (Array2<u8>, usize) {
+/// Move every member of `herd_to_step` that has an open spot in front of it, reading from
+/// `src` and writing the new positions into `dst`. `dst` is overwritten, so the same pair of
+/// buffers can be reused on every step without allocating.
+fn step_in_direction(src: ArrayView2<u8>, dst: &mut Array2<u8>, herd_to_step: u8) -> usize {
     assert!(
         [1, 2].contains(&herd_to_step),
         "herd to step was not 1 or 2"
     );
-    let dir = if herd_to_step == 1 { (0, 1) } else { (1, 0) };
-
-    let mut result = inp_arr.to_owned();
+    let (nrows, ncols) = src.dim();
+    dst.assign(&src);
 
     let mut num_changed: usize = 0;
 
-    // For each slug in the herd (denoted by `herd_to_step`)
-    inp_arr
-        .indexed_iter()
-        // Is this item one of the herd we're interested in?
-        .filter(|(_, item)| **item == herd_to_step)
-        // Which ones have open neighbors?
-        .filter(|(idx, _)| {
+    for row_idx in 0..nrows {
+        for col_idx in 0..ncols {
+            // Is this item one of the herd we're interested in?
+            if src[[row_idx, col_idx]] != herd_to_step {
+                continue;
+            }
+
             // Make sure that anything over get's properly wrapped back around
-            let new_idx = (
-                (idx.0 + dir.0) % inp_arr.nrows(),
-                (idx.1 + dir.1) % inp_arr.ncols(),
-            );
-            if inp_arr[new_idx] == 0 {
-                true
+            let new_idx = if herd_to_step == 1 {
+                [row_idx, (col_idx + 1) % ncols]
             } else {
-                false
+                [(row_idx + 1) % nrows, col_idx]
+            };
+
+            // Move the ones that are open
+            if src[new_idx] == 0 {
+                num_changed += 1;
+                dst[[row_idx, col_idx]] = 0;
+                dst[new_idx] = herd_to_step;
             }
-        })
-        // Move the ones that are open
-        .for_each(|(idx, _)| {
-            // Update the count of number changed
-            num_changed += 1;
+        }
+    }
 
-            // Get the new index
-            let new_idx = (
-                (idx.0 + dir.0) % inp_arr.nrows(),
-                (idx.1 + dir.1) % inp_arr.ncols(),
-            );
-            // Make the item at current idx 0
-            result[idx] = 0;
+    num_changed
+}
 
-            // Make the item at new idx the new slug
-            result[new_idx] = herd_to_step;
-        });
+/// Step the east herd and then the south herd. The east herd is written into `scratch`, and
+/// the south herd back into `cuces`, so the result always ends up in `cuces`.
+fn step(cuces: &mut Array2<u8>, scratch: &mut Array2<u8>) -> usize {
+    let east_changed = step_in_direction(cuces.view(), scratch, 1);
+    let south_changed = step_in_direction(scratch.view(), cuces, 2);
+    east_changed + south_changed
+}
 
-    (result, num_changed)
+#[derive(Debug, PartialEq, Eq)]
+enum Outcome {
+    /// No sea cucumbers moved on this step
+    Settled(usize),
+    /// The sea cucumbers never stop moving, and repeat their positions every `period` steps.
+    /// The repeat was noticed on step `detected_at`.
+    Cycle { detected_at: usize, period: usize },
 }
 
-fn part1(arr: ArrayView2<u8>) -> usize {
+fn part1(arr: ArrayView2<u8>) -> Outcome {
     let mut cuces = arr.to_owned();
+    let mut scratch = Array2::zeros(arr.dim());
 
-    let mut num_changed = 100;
+    // Brent's cycle detection: compare against a saved state, which is moved up to the
+    // current state every time the number of steps since it was saved hits a power of two
+    let mut saved = arr.to_owned();
+    let mut power = 1;
+    let mut period = 0;
     let mut iter_num = 0;
 
-    // Step through until no sea cucumbers move
-    while num_changed > 0 {
-        let r = step_in_direction(cuces.view(), 1);
-        let arr2 = r.0;
-        let east_changed = r.1;
-        let r = step_in_direction(arr2.view(), 2);
-        cuces = r.0;
+    // Step through until no sea cucumbers move, or we find that they never will
+    loop {
+        let num_changed = step(&mut cuces, &mut scratch);
         iter_num += 1;
-        num_changed = east_changed + r.1;
+        if num_changed == 0 {
+            return Outcome::Settled(iter_num);
+        }
+
+        period += 1;
+        if cuces == saved {
+            return Outcome::Cycle {
+                detected_at: iter_num,
+                period,
+            };
+        }
+        if period == power {
+            saved.assign(&cuces);
+            power *= 2;
+            period = 0;
+        }
     }
+}
 
-    iter_num
+/// The examples from the puzzle description
+const EXAMPLES: &[Example] = &[Example {
+    input: "v...>>.vv>
+.vv>>.vv..
+>>.>v>...v
+>>v>>.>.v.
+v>v.vv.v..
+>.>>..v...
+.vv..>.>v.
+v.v..>>v.v
+....v..v.>",
+    part1: Some("58"),
+    part2: None,
+}];
+
+/// The first part, solved straight from the input. There is no second part on the last day.
+const PARTS: [Option<puzzle::Part>; 2] = [Some(solve_part1), None];
+
+fn solve_part1(input: &str) -> Result<String, String> {
+    match part1(parse_input(input).view()) {
+        Outcome::Settled(iter_num) => Ok(iter_num.to_string()),
+        Outcome::Cycle { period, .. } => Err(format!(
+            "The sea cucumbers never settle, and repeat every {} steps",
+            period
+        )),
+    }
 }
 
 fn main() {
+    // Pass `--example` to check the solutions against the puzzle's examples instead
+    if std::env::args().any(|arg| arg == "--example") {
+        std::process::exit(puzzle::run_examples(EXAMPLES, PARTS));
+    }
+
     let setup_time = std::time::Instant::now();
 
     let input_str =
@@ -101,7 +156,16 @@ fn main() {
     println!("Part 1 took {:.6} ms", part1_time.elapsed().as_millis());
 
     println!();
-    println!("Part 1 result: {}", part1_result);
+    match part1_result {
+        Outcome::Settled(iter_num) => println!("Part 1 result: {}", iter_num),
+        Outcome::Cycle {
+            detected_at,
+            period,
+        } => println!(
+            "Part 1 result: never settles, repeats every {} steps (found on step {})",
+            period, detected_at
+        ),
+    }
 }
 
 #[test]
@@ -141,7 +205,8 @@ v.v..>>v.v
 #[test]
 fn test_step_in_direction_1() {
     let arr = ndarray::arr2(&[[0, 0, 0, 1, 1, 1, 1, 1, 0, 0, 0]]);
-    let (got, _) = step_in_direction(arr.view(), 1);
+    let mut got = Array2::zeros(arr.dim());
+    step_in_direction(arr.view(), &mut got, 1);
     let expected = ndarray::arr2(&[[0, 0, 0, 1, 1, 1, 1, 0, 1, 0, 0]]);
     assert_eq!(expected, got);
 }
@@ -149,7 +214,8 @@ fn test_step_in_direction_1() {
 #[test]
 fn test_step_in_direction_2() {
     let arr = ndarray::arr2(&[[0, 0, 0, 1, 1, 1, 1, 1, 0, 0, 0]]);
-    let (got, _) = step_in_direction(arr.view(), 2);
+    let mut got = Array2::zeros(arr.dim());
+    step_in_direction(arr.view(), &mut got, 2);
     let expected = ndarray::arr2(&[[0, 0, 0, 1, 1, 1, 1, 1, 0, 0, 0]]);
     assert_eq!(expected, got);
 }
@@ -157,7 +223,8 @@ fn test_step_in_direction_2() {
 #[test]
 fn test_step_in_direction_3() {
     let arr = ndarray::arr2(&[[0, 0, 0, 1, 1, 1, 1, 0, 1, 0, 0]]);
-    let (got, _) = step_in_direction(arr.view(), 1);
+    let mut got = Array2::zeros(arr.dim());
+    step_in_direction(arr.view(), &mut got, 1);
     let expected = ndarray::arr2(&[[0, 0, 0, 1, 1, 1, 0, 1, 0, 1, 0]]);
     assert_eq!(expected, got);
 }
@@ -168,17 +235,17 @@ fn test_can_do_two_steps_correctly() {
 .>v....v..
 .......>..
 ..........";
-    let arr = parse_input(input_str);
+    let mut arr = parse_input(input_str);
     let expected = ndarray::arr2(&[
         [0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
         [0, 1, 0, 0, 0, 0, 0, 0, 0, 0],
         [0, 0, 2, 0, 0, 0, 0, 2, 1, 0],
         [0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
     ]);
-    let (got1, _) = step_in_direction(arr.view(), 1);
-    let (got2, _) = step_in_direction(got1.view(), 2);
+    let mut scratch = Array2::zeros(arr.dim());
+    step(&mut arr, &mut scratch);
 
-    assert_eq!(expected, got2);
+    assert_eq!(expected, arr);
 }
 
 #[test]
@@ -190,9 +257,9 @@ v.....>
 ......>
 .......
 ..vvv..";
-    let arr = parse_input(input_str);
-    let (got1, _) = step_in_direction(arr.view(), 1);
-    let (got2, _) = step_in_direction(got1.view(), 2);
+    let mut arr = parse_input(input_str);
+    let mut scratch = Array2::zeros(arr.dim());
+    step(&mut arr, &mut scratch);
 
     let expected = ndarray::arr2(&[
         [0, 0, 2, 2, 1, 0, 0],
@@ -204,7 +271,7 @@ v.....>
         [0, 0, 0, 0, 2, 0, 0],
     ]);
 
-    assert_eq!(expected, got2);
+    assert_eq!(expected, arr);
 }
 
 #[test]
@@ -220,7 +287,7 @@ fn test_part1() {
         [2, 0, 2, 0, 0, 1, 1, 2, 0, 2],
         [0, 0, 0, 0, 2, 0, 0, 2, 0, 1],
     ]);
-    let expected = 58;
+    let expected = Outcome::Settled(58);
     let got = part1(arr.view());
     assert_eq!(expected, got);
 }
@@ -230,7 +297,35 @@ fn test_part1_actual() {
     let input_str =
         std::fs::read_to_string("input/day25.txt").expect("Failed to read day 25 input");
     let arr = parse_input(&input_str);
-    let expected = 482;
+    let expected = Outcome::Settled(482);
     let got = part1(arr.view());
     assert_eq!(expected, got);
 }
+
+#[test]
+fn test_part1_cycle() {
+    // A lone sea cucumber on a ring never stops, and comes back around every 4 steps
+    let arr = parse_input(">...\n");
+    let got = part1(arr.view());
+    assert_eq!(
+        Outcome::Cycle {
+            detected_at: 7,
+            period: 4
+        },
+        got
+    );
+
+    // Both herds keep chasing each other around a loop
+    let arr = parse_input(">.v\n...\n...\n");
+    match part1(arr.view()) {
+        Outcome::Cycle { period, .. } => assert_eq!(3, period),
+        o => panic!("Expected a cycle, got {:?}", o),
+    }
+}
+
+#[test]
+fn test_examples() {
+    for check in puzzle::check_examples(EXAMPLES, PARTS) {
+        assert!(check.is_ok(), "{}", check);
+    }
+}
@@ -0,0 +1,207 @@
+//! Checking a day's quick solutions against reference ones, which are too slow for the real
+//! inputs but simple enough to trust, on lots of generated inputs. When they disagree, the
+//! input is cut down to as little as still disagrees, so that the bug is easy to find.
+
+use crate::generate;
+use crate::puzzle::Part;
+
+/// How many generated inputs to check, if `--runs` is not given
+pub const DEFAULT_RUNS: u64 = 100;
+
+/// A quick solution, and the reference solution it should always agree with
+#[derive(Debug, Clone, Copy)]
+pub struct Crosscheck {
+    pub name: &'static str,
+    pub reference: Part,
+    pub optimised: Part,
+}
+
+/// The first input that a reference and quick solution disagree on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Disagreement {
+    pub name: &'static str,
+    /// The seed of the generated input that first disagreed
+    pub seed: u64,
+    /// As little of the generated input as still disagrees
+    pub input: String,
+    pub reference: Result<String, String>,
+    pub optimised: Result<String, String>,
+}
+
+impl std::fmt::Display for Disagreement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let describe = |answer: &Result<String, String>| match answer {
+            Ok(answer) => answer.clone(),
+            Err(e) => format!("an error ({})", e),
+        };
+        writeln!(
+            f,
+            "{}: DISAGREE on seed {}, which cuts down to",
+            self.name, self.seed
+        )?;
+        writeln!(f, "{}", self.input.trim_end())?;
+        write!(
+            f,
+            "The reference gives {}, but the quick solution gives {}",
+            describe(&self.reference),
+            describe(&self.optimised)
+        )
+    }
+}
+
+/// Run a solution, treating a panic like any other error
+fn answer(part: Part, input: &str) -> Result<String, String> {
+    std::panic::catch_unwind(|| part(input)).unwrap_or_else(|_| Err("it panicked".to_string()))
+}
+
+impl Crosscheck {
+    /// Both answers, if they differ
+    fn disagreement(
+        &self,
+        input: &str,
+    ) -> Option<(Result<String, String>, Result<String, String>)> {
+        let reference = answer(self.reference, input);
+        let optimised = answer(self.optimised, input);
+        (reference != optimised).then_some((reference, optimised))
+    }
+
+    /// Remove as much of `input` as possible while the solutions still disagree, and the
+    /// reference still succeeds or fails as it did on all of `input`. An input of one line is
+    /// cut down by its comma separated items, and anything else by its lines.
+    fn minimise(&self, input: &str, reference_ok: bool) -> String {
+        let (separator, mut units): (&str, Vec<&str>) = match input.trim().lines().count() {
+            1 => (",", input.trim().split(',').collect()),
+            _ => ("\n", input.lines().collect()),
+        };
+        let still_disagrees = |units: &[&str]| {
+            matches!(
+                self.disagreement(&units.join(separator)),
+                Some((reference, _)) if reference.is_ok() == reference_ok
+            )
+        };
+
+        // Try removing big chunks first, then smaller and smaller ones
+        let mut chunk = (units.len() / 2).max(1);
+        while chunk > 0 && units.len() > 1 {
+            let mut start = 0;
+            while start < units.len() {
+                let end = (start + chunk).min(units.len());
+                let candidate: Vec<&str> = units[..start]
+                    .iter()
+                    .chain(&units[end..])
+                    .copied()
+                    .collect();
+                if !candidate.is_empty() && still_disagrees(&candidate) {
+                    units = candidate;
+                } else {
+                    start = end;
+                }
+            }
+            chunk /= 2;
+        }
+
+        let mut minimised = units.join(separator);
+        minimised.push('\n');
+        minimised
+    }
+
+    /// Compare the solutions on the inputs generated from seeds `0..runs`, giving the first
+    /// disagreement
+    pub fn find_disagreement(
+        &self,
+        day: u8,
+        runs: u64,
+        size: Option<usize>,
+    ) -> Result<Option<Disagreement>, String> {
+        for seed in 0..runs {
+            let input = generate::generate(day, size, seed)?;
+            if let Some((reference, _)) = self.disagreement(&input) {
+                let input = self.minimise(&input, reference.is_ok());
+                let (reference, optimised) = self
+                    .disagreement(&input)
+                    .expect("The minimised input should still disagree");
+                return Ok(Some(Disagreement {
+                    name: self.name,
+                    seed,
+                    input,
+                    reference,
+                    optimised,
+                }));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Run every crosscheck for a day and print how each went, giving the exit code for the day.
+/// Pass `--runs=N` to check N generated inputs, and `--size=N` to change how big they are.
+pub fn run(day: u8, checks: &[Crosscheck]) -> i32 {
+    let mut runs = DEFAULT_RUNS;
+    let mut size = None;
+    for arg in std::env::args() {
+        if let Some(n) = arg.strip_prefix("--runs=") {
+            runs = n.parse().expect("Could not parse the number of runs");
+        }
+        if let Some(n) = arg.strip_prefix("--size=") {
+            size = Some(n.parse().expect("Could not parse the size"));
+        }
+    }
+
+    // The solutions panicking is reported as a disagreement, so don't print every panic too
+    std::panic::set_hook(Box::new(|_| {}));
+
+    let mut exit_code = 0;
+    for check in checks {
+        match check.find_disagreement(day, runs, size) {
+            Ok(None) => println!("{}: agreed on {} inputs", check.name, runs),
+            Ok(Some(disagreement)) => {
+                println!("{}", disagreement);
+                exit_code = 1;
+            }
+            Err(e) => {
+                println!("{}: {}", check.name, e);
+                exit_code = 2;
+            }
+        }
+    }
+    exit_code
+}
+
+#[test]
+fn test_find_disagreement() {
+    fn fuel(input: &str) -> Result<String, String> {
+        let positions = crate::parsing::numbers::<i64>(input)?;
+        Ok(positions.iter().sum::<i64>().to_string())
+    }
+    // Wrong whenever there is a crab past 50
+    fn fuel_ignoring_far_crabs(input: &str) -> Result<String, String> {
+        let positions = crate::parsing::numbers::<i64>(input)?;
+        Ok(positions
+            .iter()
+            .filter(|&&p| p <= 50)
+            .sum::<i64>()
+            .to_string())
+    }
+
+    let agrees = Crosscheck {
+        name: "same",
+        reference: fuel,
+        optimised: fuel,
+    };
+    assert_eq!(Ok(None), agrees.find_disagreement(7, 5, Some(50)));
+
+    let disagrees = Crosscheck {
+        name: "far crabs",
+        reference: fuel,
+        optimised: fuel_ignoring_far_crabs,
+    };
+    let disagreement = disagrees
+        .find_disagreement(7, 5, Some(50))
+        .unwrap()
+        .expect("Some crab should be past 50");
+    // Cut down to the single crab that is too far away
+    let crab: i64 = disagreement.input.trim().parse().unwrap();
+    assert!(crab > 50);
+    assert_eq!(Ok(crab.to_string()), disagreement.reference);
+    assert_eq!(Ok("0".to_string()), disagreement.optimised);
+}
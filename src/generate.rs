@@ -0,0 +1,990 @@
+//! Random puzzle inputs, in the same format as the real ones, for stress testing and
+//! benchmarking the solutions on inputs of any size.
+//!
+//! Every input comes from a seed, so the same day, size and seed always give the same input.
+//! What the size counts depends on the day, and is listed in [`GENERATORS`].
+
+use std::collections::{HashSet, VecDeque};
+
+use crate::font::{FONT, GLYPH_HEIGHT, GLYPH_WIDTH};
+use crate::rng::Rng;
+
+/// Makes the input for one day
+pub struct Generator {
+    /// What the size counts
+    pub size: &'static str,
+    /// A size like the real input's
+    pub default_size: usize,
+    generate: fn(usize, &mut Rng) -> String,
+}
+
+/// The generator for each day, starting from day 1
+pub const GENERATORS: [Generator; 25] = [
+    Generator {
+        size: "depth measurements",
+        default_size: 2000,
+        generate: depths,
+    },
+    Generator {
+        size: "commands",
+        default_size: 1000,
+        generate: commands,
+    },
+    Generator {
+        size: "readings",
+        default_size: 1000,
+        generate: diagnostic_report,
+    },
+    Generator {
+        size: "boards",
+        default_size: 100,
+        generate: bingo,
+    },
+    Generator {
+        size: "lines of vents",
+        default_size: 500,
+        generate: vents,
+    },
+    Generator {
+        size: "fish",
+        default_size: 300,
+        generate: lanternfish,
+    },
+    Generator {
+        size: "crabs",
+        default_size: 1000,
+        generate: crabs,
+    },
+    Generator {
+        size: "displays",
+        default_size: 200,
+        generate: displays,
+    },
+    Generator {
+        size: "rows and columns",
+        default_size: 100,
+        generate: heightmap,
+    },
+    Generator {
+        size: "lines",
+        default_size: 100,
+        generate: navigation_subsystem,
+    },
+    Generator {
+        size: "rows and columns, of octopuses that might never all flash at once",
+        default_size: 10,
+        generate: octopuses,
+    },
+    Generator {
+        size: "small caves",
+        default_size: 12,
+        generate: caves,
+    },
+    Generator {
+        size: "dots",
+        default_size: 900,
+        generate: transparent_paper,
+    },
+    Generator {
+        size: "elements in the template",
+        default_size: 20,
+        generate: polymer,
+    },
+    Generator {
+        size: "rows and columns",
+        default_size: 100,
+        generate: risk_levels,
+    },
+    Generator {
+        size: "packets",
+        default_size: 100,
+        generate: transmission,
+    },
+    Generator {
+        size: "rough distance to the target",
+        default_size: 150,
+        generate: target_area,
+    },
+    Generator {
+        size: "snailfish numbers",
+        default_size: 100,
+        generate: snailfish_numbers,
+    },
+    Generator {
+        size: "scanners",
+        default_size: 30,
+        generate: scanners,
+    },
+    Generator {
+        size: "rows and columns of the image",
+        default_size: 100,
+        generate: trench_map,
+    },
+    Generator {
+        size: "nothing, the input is always two starting positions",
+        default_size: 1,
+        generate: starting_positions,
+    },
+    Generator {
+        size: "reboot steps",
+        default_size: 420,
+        generate: reboot_steps,
+    },
+    Generator {
+        size: "nothing, the burrow is always the same size",
+        default_size: 1,
+        generate: burrow,
+    },
+    Generator {
+        size: "nothing, the program always checks 14 digits",
+        default_size: 1,
+        generate: monad,
+    },
+    Generator {
+        size: "rows and columns",
+        default_size: 137,
+        generate: sea_cucumbers,
+    },
+];
+
+impl Generator {
+    pub fn generate(&self, size: usize, seed: u64) -> String {
+        (self.generate)(size, &mut Rng::new(seed))
+    }
+}
+
+/// The generator for `day`
+pub fn generator(day: u8) -> Result<&'static Generator, String> {
+    match day {
+        1..=25 => Ok(&GENERATORS[day as usize - 1]),
+        _ => Err(format!("There is no day {}", day)),
+    }
+}
+
+/// Make an input for `day`, of the default size if `size` is `None`
+pub fn generate(day: u8, size: Option<usize>, seed: u64) -> Result<String, String> {
+    let generator = generator(day)?;
+    match size.unwrap_or(generator.default_size) {
+        0 => Err("The size must be at least 1".to_string()),
+        size => Ok(generator.generate(size, seed)),
+    }
+}
+
+fn join_lines<I: IntoIterator<Item = String>>(lines: I) -> String {
+    let mut input: String = lines.into_iter().map(|line| line + "\n").collect();
+    if input.is_empty() {
+        input.push('\n');
+    }
+    input
+}
+
+/// Rows of single characters, each picked by `cell`
+fn grid<F: FnMut(&mut Rng) -> char>(
+    nrows: usize,
+    ncols: usize,
+    rng: &mut Rng,
+    mut cell: F,
+) -> String {
+    join_lines((0..nrows).map(|_| (0..ncols).map(|_| cell(rng)).collect::<String>()))
+}
+
+fn digit(rng: &mut Rng, low: u32, high: u32) -> char {
+    char::from_digit(rng.range(low as i64, high as i64) as u32, 10).unwrap()
+}
+
+/// Day 1: a sonar sweep that mostly gets deeper
+fn depths(size: usize, rng: &mut Rng) -> String {
+    let mut depth = rng.range(100, 200);
+    join_lines((0..size).map(|_| {
+        depth = (depth + rng.range(-20, 30)).max(0);
+        depth.to_string()
+    }))
+}
+
+/// Day 2: commands that never take the submarine above the surface
+fn commands(size: usize, rng: &mut Rng) -> String {
+    let mut depth = 0;
+    join_lines((0..size).map(|_| {
+        let units = rng.range(1, 9);
+        let direction = match rng.below(3) {
+            0 if depth >= units => {
+                depth -= units;
+                "up"
+            }
+            0 | 1 => {
+                depth += units;
+                "down"
+            }
+            _ => "forward",
+        };
+        format!("{} {}", direction, units)
+    }))
+}
+
+/// Day 3: distinct binary readings, which are 12 bits wide unless there are too many to fit
+fn diagnostic_report(size: usize, rng: &mut Rng) -> String {
+    let width = (12..=63)
+        .find(|&width| 1_u64 << width >= 2 * size as u64)
+        .unwrap_or(63);
+    let mut seen = HashSet::new();
+    let mut readings = Vec::with_capacity(size);
+    while readings.len() < size {
+        let reading = rng.below(1 << width);
+        if seen.insert(reading) {
+            readings.push(reading);
+        }
+    }
+    join_lines(
+        readings
+            .iter()
+            .map(|reading| format!("{:0width$b}", reading, width = width)),
+    )
+}
+
+/// Day 4: boards of distinct numbers below 100, with every number drawn once
+fn bingo(size: usize, rng: &mut Rng) -> String {
+    let mut numbers: Vec<u16> = (0..100).collect();
+    rng.shuffle(&mut numbers);
+    let draws: Vec<String> = numbers.iter().map(u16::to_string).collect();
+
+    let mut input = draws.join(",") + "\n";
+    for _ in 0..size {
+        rng.shuffle(&mut numbers);
+        input.push('\n');
+        for row in numbers[..25].chunks(5) {
+            let row: Vec<String> = row.iter().map(|n| format!("{:>2}", n)).collect();
+            input += &row.join(" ");
+            input.push('\n');
+        }
+    }
+    input
+}
+
+/// Day 5: horizontal, vertical and diagonal lines, on a grid that grows with the number of
+/// lines once there are more than 500
+fn vents(size: usize, rng: &mut Rng) -> String {
+    let extent = 1000.max(2 * size as i64);
+    join_lines((0..size).map(|_| loop {
+        let (x1, y1) = (rng.range(0, extent - 1), rng.range(0, extent - 1));
+        let (dx, dy) = *rng.choose(&[
+            (1, 0),
+            (-1, 0),
+            (0, 1),
+            (0, -1),
+            (1, 1),
+            (1, -1),
+            (-1, 1),
+            (-1, -1),
+        ]);
+        // How far the line can go before it leaves the grid
+        let room = |pos: i64, step: i64| match step {
+            1 => extent - 1 - pos,
+            -1 => pos,
+            _ => i64::MAX,
+        };
+        let max_len = room(x1, dx).min(room(y1, dy)).min(extent / 3);
+        if max_len > 0 {
+            let len = rng.range(1, max_len);
+            break format!("{},{} -> {},{}", x1, y1, x1 + dx * len, y1 + dy * len);
+        }
+    }))
+}
+
+/// Day 6: timers between 1 and 5, like the real input
+fn lanternfish(size: usize, rng: &mut Rng) -> String {
+    let timers: Vec<String> = (0..size).map(|_| rng.range(1, 5).to_string()).collect();
+    timers.join(",") + "\n"
+}
+
+/// Day 7: positions that bunch up towards 0, like the real input
+fn crabs(size: usize, rng: &mut Rng) -> String {
+    let extent = 2 * size as u64;
+    let positions: Vec<String> = (0..size)
+        .map(|_| (rng.below(extent) * rng.below(extent) / extent).to_string())
+        .collect();
+    positions.join(",") + "\n"
+}
+
+/// The segments lit for each digit on a seven-segment display
+const DIGIT_SEGMENTS: [&str; 10] = [
+    "abcefg", "cf", "acdeg", "acdfg", "bcdf", "abdfg", "abdefg", "acf", "abcdefg", "abcdfg",
+];
+
+/// Day 8: displays with their own random wiring, each showing four random digits
+fn displays(size: usize, rng: &mut Rng) -> String {
+    join_lines((0..size).map(|_| {
+        let mut wiring: Vec<char> = ('a'..='g').collect();
+        rng.shuffle(&mut wiring);
+        let pattern = |rng: &mut Rng, digit: usize| {
+            let mut wires: Vec<char> = DIGIT_SEGMENTS[digit]
+                .chars()
+                .map(|segment| wiring[segment as usize - 'a' as usize])
+                .collect();
+            rng.shuffle(&mut wires);
+            wires.into_iter().collect::<String>()
+        };
+
+        let mut digits: Vec<usize> = (0..10).collect();
+        rng.shuffle(&mut digits);
+        let patterns: Vec<String> = digits.iter().map(|&digit| pattern(rng, digit)).collect();
+        let output: Vec<String> = (0..4)
+            .map(|_| {
+                let digit = rng.index(10);
+                pattern(rng, digit)
+            })
+            .collect();
+        format!("{} | {}", patterns.join(" "), output.join(" "))
+    }))
+}
+
+/// Day 9: basins that get higher away from their low points. Only the cells furthest from
+/// any low point reach 9, so neighbouring basins often touch without a wall between them.
+fn heightmap(size: usize, rng: &mut Rng) -> String {
+    // Spread out from random low points, so each cell's height is its distance from the
+    // nearest one, with some noise
+    let mut distances = vec![vec![usize::MAX; size]; size];
+    let mut queue = VecDeque::new();
+    for _ in 0..(size * size / 20).max(1) {
+        let (row, col) = (rng.index(size), rng.index(size));
+        distances[row][col] = 0;
+        queue.push_back((row, col));
+    }
+    while let Some((row, col)) = queue.pop_front() {
+        let next = distances[row][col] + 1;
+        let neighbours = [
+            (row.wrapping_sub(1), col),
+            (row + 1, col),
+            (row, col.wrapping_sub(1)),
+            (row, col + 1),
+        ];
+        for (r, c) in neighbours {
+            if r < size && c < size && distances[r][c] == usize::MAX {
+                distances[r][c] = next;
+                queue.push_back((r, c));
+            }
+        }
+    }
+
+    join_lines(distances.iter().map(|row| {
+        row.iter()
+            .map(|&distance| {
+                let height = (2 * distance as u64 + rng.below(3)).min(9);
+                char::from_digit(height as u32, 10).unwrap()
+            })
+            .collect::<String>()
+    }))
+}
+
+/// Day 10: lines that are each either corrupted or incomplete, with an odd number of
+/// incomplete lines so that there is a middle score
+fn navigation_subsystem(size: usize, rng: &mut Rng) -> String {
+    const PAIRS: [(char, char); 4] = [('(', ')'), ('[', ']'), ('{', '}'), ('<', '>')];
+
+    let mut corrupted: Vec<bool> = (0..size).map(|_| rng.chance(1, 2)).collect();
+    if corrupted.iter().filter(|&&c| !c).count() % 2 == 0 {
+        let idx = rng.index(size);
+        corrupted[idx] = !corrupted[idx];
+    }
+
+    join_lines(corrupted.into_iter().map(|corrupted| {
+        let len = rng.range(20, 110) as usize;
+        let corrupt_from = rng.index(len);
+        let mut is_corrupt = false;
+        let mut line = String::with_capacity(len);
+        let mut open: Vec<usize> = Vec::new();
+
+        while line.len() < len {
+            if corrupted && !is_corrupt && line.len() >= corrupt_from && !open.is_empty() {
+                // Close with any bracket but the right one, then carry on as if it were right
+                let expected = open.pop().unwrap();
+                let wrong = (expected + 1 + rng.index(PAIRS.len() - 1)) % PAIRS.len();
+                line.push(PAIRS[wrong].1);
+                is_corrupt = true;
+            } else if open.is_empty() || rng.chance(11, 20) {
+                let pair = rng.index(PAIRS.len());
+                open.push(pair);
+                line.push(PAIRS[pair].0);
+            } else {
+                line.push(PAIRS[open.pop().unwrap()].1);
+            }
+        }
+        // A corrupted line whose wrong bracket never came needs one on the end, and an
+        // incomplete line that happens to be complete needs a bracket left open
+        if corrupted && !is_corrupt {
+            let pair = rng.index(PAIRS.len());
+            line.push(PAIRS[pair].0);
+            line.push(PAIRS[(pair + 1) % PAIRS.len()].1);
+        } else if !corrupted && open.is_empty() {
+            line.push(PAIRS[rng.index(PAIRS.len())].0);
+        }
+        line
+    }))
+}
+
+/// Day 11: energy levels, on a square grid of any size. Unlike the real inputs, the octopuses
+/// might never all flash at once, in which case day 11 says so instead of giving part 2.
+fn octopuses(size: usize, rng: &mut Rng) -> String {
+    grid(size, size, rng, |rng| digit(rng, 0, 9))
+}
+
+/// A name of `len` random letters from `letters`, that is not already in `names`
+fn unique_name(letters: &[char], len: usize, names: &mut HashSet<String>, rng: &mut Rng) -> String {
+    loop {
+        let name: String = (0..len).map(|_| *rng.choose(letters)).collect();
+        if names.insert(name.clone()) {
+            return name;
+        }
+    }
+}
+
+/// Day 12: small caves, with a third as many big caves, where big caves only connect to
+/// small caves so that the number of paths is finite
+fn caves(size: usize, rng: &mut Rng) -> String {
+    let n_big = (size / 3).max(1);
+    // Names are two letters long, unless there are too many caves for that
+    let name_len = (2..)
+        .find(|&len| 26_usize.pow(len as u32) >= 2 * size)
+        .unwrap();
+    let lower: Vec<char> = ('a'..='z').collect();
+    let upper: Vec<char> = ('A'..='Z').collect();
+    let mut names = HashSet::from(["start".to_string(), "end".to_string()]);
+
+    // Start and end, then the small caves, then the big ones
+    let mut caves = vec!["start".to_string(), "end".to_string()];
+    for _ in 0..size {
+        caves.push(unique_name(&lower, name_len, &mut names, rng));
+    }
+    for _ in 0..n_big {
+        caves.push(unique_name(&upper, name_len, &mut names, rng));
+    }
+    let neighbour = |rng: &mut Rng, big_one_in: u64| {
+        if rng.chance(1, big_one_in) {
+            2 + size + rng.index(n_big)
+        } else {
+            2 + rng.index(size)
+        }
+    };
+
+    let mut edges: HashSet<(usize, usize)> = HashSet::new();
+    let mut lines = Vec::new();
+    let mut connect = |a: usize, b: usize| {
+        if a != b && edges.insert((a.min(b), a.max(b))) {
+            lines.push(format!("{}-{}", caves[a], caves[b]));
+        }
+    };
+    for end in 0..2 {
+        for _ in 0..rng.range(2, 3) {
+            connect(end, neighbour(rng, 3));
+        }
+    }
+    for cave in 2..2 + size {
+        for _ in 0..rng.range(1, 2) {
+            connect(cave, neighbour(rng, 2));
+        }
+    }
+    rng.shuffle(&mut lines);
+    join_lines(lines)
+}
+
+/// Day 13: dots that fold up into eight random letters. Every dot is one of the letters'
+/// dots, unfolded a random way across each fold.
+fn transparent_paper(size: usize, rng: &mut Rng) -> String {
+    const N_LETTERS: usize = 8;
+
+    // The dots of the letters, on the folded paper
+    let letter_dots: Vec<(usize, usize)> = (0..N_LETTERS)
+        .flat_map(|letter_idx| {
+            // Every letter but the blank one
+            let (_, rows) = &FONT[rng.index(FONT.len() - 1)];
+            rows.iter().enumerate().flat_map(move |(row_idx, row)| {
+                row.chars()
+                    .enumerate()
+                    .filter(|&(_, c)| c == '#')
+                    .map(move |(col_idx, _)| (letter_idx * (GLYPH_WIDTH + 1) + col_idx, row_idx))
+            })
+        })
+        .collect();
+
+    // Unfold the paper until there is room for twice as many dots as were asked for
+    let (mut width, mut height) = (N_LETTERS * (GLYPH_WIDTH + 1) - 1, GLYPH_HEIGHT);
+    // The folds, from the last one made to the first
+    let mut folds: Vec<(char, usize)> = Vec::new();
+    let mut n_copies = 1;
+    while folds.len() < 2 || letter_dots.len() * n_copies < 2 * size {
+        // Unfold each way once, then the shorter side, so the paper stays roughly square
+        let unfold_y = match folds.len() {
+            0 => true,
+            1 => false,
+            _ => height < width,
+        };
+        if unfold_y {
+            folds.push(('y', height));
+            height = 2 * height + 1;
+        } else {
+            folds.push(('x', width));
+            width = 2 * width + 1;
+        }
+        n_copies *= 2;
+    }
+
+    // Reflect a dot across each fold, from the last one made, if `mirror` says so for that
+    // fold
+    let unfold = |(mut x, mut y): (usize, usize), mirror: &mut dyn FnMut() -> bool| {
+        for &(axis, line) in &folds {
+            if mirror() {
+                match axis {
+                    'x' => x = 2 * line - x,
+                    _ => y = 2 * line - y,
+                }
+            }
+        }
+        (x, y)
+    };
+
+    let mut dots: Vec<(usize, usize)> = Vec::with_capacity(size.max(letter_dots.len()));
+    let mut seen = HashSet::new();
+    // Every letter needs all of its dots, and one dot past every fold makes the paper big
+    // enough to fold
+    for (idx, &dot) in letter_dots.iter().enumerate() {
+        let dot = unfold(dot, &mut || idx == 0 || rng.chance(1, 2));
+        if seen.insert(dot) {
+            dots.push(dot);
+        }
+    }
+    while dots.len() < size {
+        let dot = unfold(*rng.choose(&letter_dots), &mut || rng.chance(1, 2));
+        if seen.insert(dot) {
+            dots.push(dot);
+        }
+    }
+    rng.shuffle(&mut dots);
+
+    let mut input = join_lines(dots.iter().map(|(x, y)| format!("{},{}", x, y)));
+    input.push('\n');
+    input
+        + &join_lines(
+            folds
+                .iter()
+                .rev()
+                .map(|(axis, line)| format!("fold along {}={}", axis, line)),
+        )
+}
+
+/// Day 14: a template of ten elements, with a rule for every pair of them
+fn polymer(size: usize, rng: &mut Rng) -> String {
+    let mut letters: Vec<char> = ('A'..='Z').collect();
+    rng.shuffle(&mut letters);
+    let elements = &letters[..10];
+
+    let template: String = (0..size.max(2)).map(|_| *rng.choose(elements)).collect();
+    let mut rules = Vec::with_capacity(elements.len() * elements.len());
+    for &first in elements {
+        for &second in elements {
+            rules.push(format!("{}{} -> {}", first, second, rng.choose(elements)));
+        }
+    }
+    rng.shuffle(&mut rules);
+    format!("{}\n\n{}", template, join_lines(rules))
+}
+
+/// Day 15: risk levels on a square grid of any size
+fn risk_levels(size: usize, rng: &mut Rng) -> String {
+    grid(size, size, rng, |rng| digit(rng, 1, 9))
+}
+
+/// The bits of a random packet, which has about `budget` packets in it
+fn packet(budget: usize, rng: &mut Rng) -> String {
+    let version = format!("{:03b}", rng.below(8));
+    if budget <= 1 {
+        // A literal value, in groups of four bits
+        let n_groups = rng.range(1, 3) as usize;
+        let value = rng.below(1 << (4 * n_groups));
+        let mut bits = version + "100";
+        for group in (0..n_groups).rev() {
+            let more = if group > 0 { '1' } else { '0' };
+            bits.push(more);
+            bits += &format!("{:04b}", (value >> (4 * group)) & 0xf);
+        }
+        return bits;
+    }
+
+    // Comparisons have exactly two sub-packets, and the rest have at least one. Products
+    // only multiply a few literal values, so that the answer fits in 64 bits.
+    let type_id = if budget <= 4 {
+        *rng.choose(&[0, 1, 2, 3, 5, 6, 7])
+    } else {
+        *rng.choose(&[0, 0, 2, 3, 5, 6, 7])
+    };
+    let n_children = match type_id {
+        1 => budget - 1,
+        5..=7 => 2,
+        _ => rng.range(1, (budget - 1).min(5) as i64) as usize,
+    };
+    // Share out the rest of the packets between the sub-packets
+    let mut child_budgets = vec![1; n_children];
+    for _ in n_children..budget - 1 {
+        child_budgets[rng.index(n_children)] += 1;
+    }
+    let children: String = child_budgets
+        .into_iter()
+        .map(|child_budget| packet(child_budget, rng))
+        .collect();
+
+    let mut bits = version + &format!("{:03b}", type_id);
+    if children.len() < 1 << 15 && rng.chance(1, 2) {
+        bits += &format!("0{:015b}", children.len());
+    } else {
+        bits += &format!("1{:011b}", n_children);
+    }
+    bits + &children
+}
+
+/// Day 16: a single transmission in hexadecimal
+fn transmission(size: usize, rng: &mut Rng) -> String {
+    let mut bits = packet(size, rng);
+    while !bits.len().is_multiple_of(4) {
+        bits.push('0');
+    }
+    let hex: String = bits
+        .as_bytes()
+        .chunks(4)
+        .map(|nibble| {
+            let nibble = u32::from_str_radix(std::str::from_utf8(nibble).unwrap(), 2).unwrap();
+            char::from_digit(nibble, 16).unwrap().to_ascii_uppercase()
+        })
+        .collect();
+    hex + "\n"
+}
+
+/// Day 17: a target ahead of and below the probe, wide enough that the probe can stop above
+/// it
+fn target_area(size: usize, rng: &mut Rng) -> String {
+    let size = size as i64;
+    let x_low = size + rng.range(0, size);
+    let mut x_high = x_low + size / 4 + rng.range(0, size / 4);
+    // There must be an x velocity that stops the probe inside the target
+    while !(1..=x_high).any(|vel| (x_low..=x_high).contains(&(vel * (vel + 1) / 2))) {
+        x_high += 1;
+    }
+    let y_high = -(size / 2 + rng.range(1, size / 2 + 1));
+    let y_low = y_high - size / 4 - rng.range(1, size / 4 + 1);
+    format!(
+        "target area: x={}..{}, y={}..{}\n",
+        x_low, x_high, y_low, y_high
+    )
+}
+
+/// A reduced snailfish number, with no pair nested inside four others
+fn snailfish_number(depth: usize, rng: &mut Rng) -> String {
+    let element = |rng: &mut Rng| {
+        if depth < 3 && rng.chance(3, 5) {
+            snailfish_number(depth + 1, rng)
+        } else {
+            rng.range(0, 9).to_string()
+        }
+    };
+    let left = element(rng);
+    let right = element(rng);
+    format!("[{},{}]", left, right)
+}
+
+/// Day 18: reduced snailfish numbers
+fn snailfish_numbers(size: usize, rng: &mut Rng) -> String {
+    join_lines((0..size).map(|_| snailfish_number(0, rng)))
+}
+
+type Point = (i64, i64, i64);
+
+/// The 24 rotations of a scanner, as the matrices that turn a direction into the scanner's
+/// point of view
+fn rotations() -> Vec<[[i64; 3]; 3]> {
+    let mut rotations = Vec::with_capacity(24);
+    for axes in [
+        [0, 1, 2],
+        [0, 2, 1],
+        [1, 0, 2],
+        [1, 2, 0],
+        [2, 0, 1],
+        [2, 1, 0],
+    ] {
+        for signs in 0..8 {
+            let mut matrix = [[0; 3]; 3];
+            for (row, &axis) in axes.iter().enumerate() {
+                matrix[row][axis] = if signs & (1 << row) == 0 { 1 } else { -1 };
+            }
+            let det = matrix[0][0] * (matrix[1][1] * matrix[2][2] - matrix[1][2] * matrix[2][1])
+                - matrix[0][1] * (matrix[1][0] * matrix[2][2] - matrix[1][2] * matrix[2][0])
+                + matrix[0][2] * (matrix[1][0] * matrix[2][1] - matrix[1][1] * matrix[2][0]);
+            if det == 1 {
+                rotations.push(matrix);
+            }
+        }
+    }
+    rotations
+}
+
+fn in_range(scanner: Point, beacon: Point) -> bool {
+    (scanner.0 - beacon.0).abs() <= 1000
+        && (scanner.1 - beacon.1).abs() <= 1000
+        && (scanner.2 - beacon.2).abs() <= 1000
+}
+
+/// Day 19: scanners that each share at least 12 beacons with one scanner before them, and
+/// report what they see in their own orientation. Scanner 0 is at the origin, facing the
+/// same way as the answer.
+fn scanners(size: usize, rng: &mut Rng) -> String {
+    let mut positions: Vec<Point> = vec![(0, 0, 0)];
+    let mut beacons: HashSet<Point> = HashSet::new();
+    let mut random_beacons = |low: Point, high: Point, n: usize, rng: &mut Rng| {
+        for _ in 0..n {
+            beacons.insert((
+                rng.range(low.0, high.0),
+                rng.range(low.1, high.1),
+                rng.range(low.2, high.2),
+            ));
+        }
+    };
+    random_beacons((-1000, -1000, -1000), (1000, 1000, 1000), 12, rng);
+
+    for _ in 1..size {
+        let parent = *rng.choose(&positions);
+        let position = (
+            parent.0 + rng.range(-1100, 1100),
+            parent.1 + rng.range(-1100, 1100),
+            parent.2 + rng.range(-1100, 1100),
+        );
+        // Beacons both scanners can see, and some only the new one can
+        let low = (
+            parent.0.max(position.0) - 1000,
+            parent.1.max(position.1) - 1000,
+            parent.2.max(position.2) - 1000,
+        );
+        let high = (
+            parent.0.min(position.0) + 1000,
+            parent.1.min(position.1) + 1000,
+            parent.2.min(position.2) + 1000,
+        );
+        random_beacons(low, high, 12, rng);
+        let own_low = (position.0 - 1000, position.1 - 1000, position.2 - 1000);
+        let own_high = (position.0 + 1000, position.1 + 1000, position.2 + 1000);
+        random_beacons(own_low, own_high, rng.range(4, 12) as usize, rng);
+        positions.push(position);
+    }
+
+    // Sort the beacons so that the order doesn't depend on the hashing
+    let mut beacons: Vec<Point> = beacons.into_iter().collect();
+    beacons.sort_unstable();
+    let rotations = rotations();
+    let mut reports = Vec::with_capacity(size);
+    for (idx, &scanner) in positions.iter().enumerate() {
+        let rotation = if idx == 0 {
+            rotations[0]
+        } else {
+            *rng.choose(&rotations)
+        };
+        let mut seen: Vec<String> = beacons
+            .iter()
+            .filter(|&&beacon| in_range(scanner, beacon))
+            .map(|&beacon| {
+                let relative = [
+                    beacon.0 - scanner.0,
+                    beacon.1 - scanner.1,
+                    beacon.2 - scanner.2,
+                ];
+                let [x, y, z] =
+                    rotation.map(|row| (0..3).map(|i| row[i] * relative[i]).sum::<i64>());
+                format!("{},{},{}", x, y, z)
+            })
+            .collect();
+        rng.shuffle(&mut seen);
+        reports.push(format!("--- scanner {} ---\n{}", idx, join_lines(seen)));
+    }
+    reports.join("\n")
+}
+
+/// Day 20: a random algorithm, which never lights the whole infinite image for good, and a
+/// square image
+fn trench_map(size: usize, rng: &mut Rng) -> String {
+    let pixel = |rng: &mut Rng| if rng.chance(1, 2) { '#' } else { '.' };
+    let mut algorithm: Vec<char> = (0..512).map(|_| pixel(rng)).collect();
+    if algorithm[0] == '#' {
+        algorithm[511] = '.';
+    }
+    let algorithm: String = algorithm.into_iter().collect();
+    format!("{}\n\n{}", algorithm, grid(size, size, rng, pixel))
+}
+
+/// Day 21: both players' starting positions
+fn starting_positions(_size: usize, rng: &mut Rng) -> String {
+    format!(
+        "Player 1 starting position: {}\nPlayer 2 starting position: {}\n",
+        rng.range(1, 10),
+        rng.range(1, 10)
+    )
+}
+
+/// Day 22: twenty steps inside the initialisation region, like the real input, and the rest
+/// spread out over a much larger one
+fn reboot_steps(size: usize, rng: &mut Rng) -> String {
+    join_lines((0..size).map(|idx| {
+        let (extent, max_side) = if idx < 20 {
+            (50, 50)
+        } else {
+            (100_000, 50_000)
+        };
+        let range = |rng: &mut Rng| {
+            let low = rng.range(-extent, extent);
+            let high = (low + rng.range(0, max_side)).min(extent);
+            format!("{}..{}", low, high)
+        };
+        let on = idx == 0 || rng.chance(3, 5);
+        format!(
+            "{} x={},y={},z={}",
+            if on { "on" } else { "off" },
+            range(rng),
+            range(rng),
+            range(rng)
+        )
+    }))
+}
+
+/// Day 23: two amphipods of each type, in random rooms
+fn burrow(_size: usize, rng: &mut Rng) -> String {
+    let mut amphipods: Vec<char> = "AABBCCDD".chars().collect();
+    rng.shuffle(&mut amphipods);
+    format!(
+        "#############\n#...........#\n###{}#{}#{}#{}###\n  #{}#{}#{}#{}#\n  #########\n",
+        amphipods[0],
+        amphipods[1],
+        amphipods[2],
+        amphipods[3],
+        amphipods[4],
+        amphipods[5],
+        amphipods[6],
+        amphipods[7]
+    )
+}
+
+/// Day 24: a MONAD in the same shape as the real ones. Each digit's block either pushes
+/// the digit plus an offset onto z as a base-26 stack, or pops the top and checks it against
+/// the digit. Every push is paired with a later pop, with offsets that leave at least one
+/// valid model number.
+fn monad(_size: usize, rng: &mut Rng) -> String {
+    const N_DIGITS: usize = 14;
+
+    // Pick the order of the pushes and pops, which must balance like brackets
+    let mut pushed: Vec<i64> = Vec::new();
+    let mut pushes_left = N_DIGITS / 2;
+    let mut blocks = Vec::with_capacity(N_DIGITS);
+    for _ in 0..N_DIGITS {
+        let push = pushes_left > 0 && (pushed.is_empty() || rng.chance(1, 2));
+        let (divisor, x_add, y_add) = if push {
+            pushes_left -= 1;
+            let y_add = rng.range(0, 16);
+            pushed.push(y_add);
+            (1, rng.range(10, 16), y_add)
+        } else {
+            // The popped digit must be this one minus `difference`
+            let y_add_pushed = pushed.pop().unwrap();
+            let difference = rng.range(-8, 8);
+            (26, difference - y_add_pushed, rng.range(0, 16))
+        };
+        blocks.push(format!(
+            "inp w\nmul x 0\nadd x z\nmod x 26\ndiv z {}\nadd x {}\neql x w\neql x 0\n\
+             mul y 0\nadd y 25\nmul y x\nadd y 1\nmul z y\nmul y 0\nadd y w\nadd y {}\n\
+             mul y x\nadd z y\n",
+            divisor, x_add, y_add
+        ));
+    }
+    blocks.concat()
+}
+
+/// Day 25: a square of east- and south-facing sea cucumbers
+fn sea_cucumbers(size: usize, rng: &mut Rng) -> String {
+    grid(size, size, rng, |rng| match rng.below(10) {
+        0..=2 => '>',
+        3..=5 => 'v',
+        _ => '.',
+    })
+}
+
+#[test]
+fn test_generate_is_repeatable() {
+    for day in 1..=25 {
+        let size = Some(generator(day).unwrap().default_size.min(20));
+        let input = generate(day, size, 7).unwrap();
+        assert!(!input.trim().is_empty(), "Day {} made an empty input", day);
+        assert!(
+            input.ends_with('\n'),
+            "Day {} has no newline at the end",
+            day
+        );
+        assert_eq!(input, generate(day, size, 7).unwrap());
+    }
+    assert!(generate(26, None, 0).is_err());
+    assert!(generate(1, Some(0), 0).is_err());
+}
+
+#[test]
+fn test_navigation_subsystem_has_a_middle_score() {
+    for seed in 0..20 {
+        for size in [1, 2, 5] {
+            let input = generate(10, Some(size), seed).unwrap();
+            // Incomplete lines have no closing bracket that doesn't match
+            let n_incomplete = input
+                .lines()
+                .filter(|line| {
+                    let mut open = Vec::new();
+                    line.chars().all(|c| match c {
+                        '(' | '[' | '{' | '<' => {
+                            open.push(c);
+                            true
+                        }
+                        _ => matches!(
+                            (open.pop(), c),
+                            (Some('('), ')')
+                                | (Some('['), ']')
+                                | (Some('{'), '}')
+                                | (Some('<'), '>')
+                        ),
+                    }) && !open.is_empty()
+                })
+                .count();
+            assert_eq!(
+                1,
+                n_incomplete % 2,
+                "Seed {} size {}:\n{}",
+                seed,
+                size,
+                input
+            );
+        }
+    }
+}
+
+#[test]
+fn test_scanners_overlap() {
+    let input = generate(19, Some(6), 3).unwrap();
+    let reports: Vec<&str> = input.split("\n\n").collect();
+    assert_eq!(6, reports.len());
+    assert_eq!("--- scanner 5 ---", reports[5].lines().next().unwrap());
+    // Every scanner sees the beacons it shares with another, and some of its own
+    for report in reports {
+        assert!(report.lines().count() > 12);
+    }
+    assert_eq!(24, rotations().len());
+}
+
+#[test]
+fn test_monad_balances() {
+    let input = generate(24, None, 11).unwrap();
+    assert_eq!(14 * 18, input.lines().count());
+    assert_eq!(7, input.lines().filter(|&line| line == "div z 26").count());
+    // Every pop must be able to match a digit from 1 to 9
+    let x_adds = input
+        .lines()
+        .filter_map(|line| line.strip_prefix("add x ")?.parse::<i64>().ok());
+    for x_add in x_adds {
+        assert!(x_add >= -24 && (x_add <= 8 || x_add >= 10), "{}", x_add);
+    }
+}
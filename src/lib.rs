@@ -0,0 +1,10 @@
+//! Code shared between the days, which are each an example
+
+pub mod crosscheck;
+pub mod font;
+pub mod generate;
+pub mod matrix;
+pub mod parallel;
+pub mod parsing;
+pub mod puzzle;
+pub mod rng;
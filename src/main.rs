@@ -1,3 +1,457 @@
+//! Runs several days at once. Each day is its own example, so this builds them all and then
+//! runs the ones asked for, several at a time. Each day's output is printed in order once it
+//! has finished, along with how long it took.
+//!
+//! ```shell
+//! cargo run --release                   # every day that has an input in `input/`
+//! cargo run --release -- 5 12           # just days 5 and 12
+//! cargo run --release -- --example      # every day, checked against the puzzle examples
+//! cargo run --release -- --jobs 1       # one day at a time, each using one thread
+//! ```
+//!
+//! It can also make random inputs, of any size, for stress testing the days
+//!
+//! ```shell
+//! cargo run --release -- generate 15 --size 1000 --seed 7 > big.txt
+//! ```
+//!
+//! and check the days that have a simple reference solution against their quick ones on lots
+//! of those inputs
+//!
+//! ```shell
+//! cargo run --release -- crosscheck                    # every day with a reference solution
+//! cargo run --release -- crosscheck 22 --runs 1000 --size 50
+//! ```
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::Command;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use aoc_2021_rs::{generate, parallel};
+
+const N_DAYS: u8 = 25;
+
+/// The days that have reference solutions to check their quick ones against
+const CROSSCHECK_DAYS: [u8; 4] = [6, 7, 14, 22];
+
+#[derive(Debug, PartialEq, Eq)]
+struct Options {
+    /// Check against the puzzle examples rather than solving the real inputs
+    example: bool,
+    days: Vec<u8>,
+    /// How many days to run at once, and how many threads each day may use
+    jobs: Option<usize>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Task {
+    Run(Options),
+    /// Print a random input for a day, of the day's default size if `size` is `None`
+    Generate {
+        day: u8,
+        size: Option<usize>,
+        seed: u64,
+    },
+    /// Compare the reference and quick solutions of some days on random inputs
+    Crosscheck {
+        days: Vec<u8>,
+        runs: Option<u64>,
+        size: Option<usize>,
+        jobs: Option<usize>,
+    },
+}
+
+/// Parse a day like `5` or `day05`
+fn parse_day(arg: &str) -> Result<u8, String> {
+    let day: u8 = arg
+        .trim_start_matches("day")
+        .parse()
+        .map_err(|_| format!("Expected a day, not {:?}", arg))?;
+    if (1..=N_DAYS).contains(&day) {
+        Ok(day)
+    } else {
+        Err(format!("There is no day {}", day))
+    }
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Task, String> {
+    let mut example = false;
+    let mut days = Vec::new();
+    let mut jobs = None;
+
+    while let Some(arg) = args.next() {
+        let first = days.is_empty() && !example && jobs.is_none();
+        match arg.as_str() {
+            "generate" if first => return parse_generate(args),
+            "crosscheck" if first => return parse_crosscheck(args),
+            "--example" => example = true,
+            "--jobs" => jobs = Some(parse_jobs(&arg, args.next())?),
+            _ => days.push(parse_day(&arg)?),
+        }
+    }
+    if days.is_empty() {
+        days = (1..=N_DAYS).collect();
+    }
+
+    Ok(Task::Run(Options {
+        example,
+        days,
+        jobs,
+    }))
+}
+
+/// Parse the number given after an option like `--size`
+fn parse_number<T: FromStr>(option: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("Expected a number after {}", option))?;
+    value
+        .parse()
+        .map_err(|_| format!("Expected a number after {}, not {:?}", option, value))
+}
+
+/// Parse the number of jobs given after `--jobs`, which must be at least one
+fn parse_jobs(option: &str, value: Option<String>) -> Result<usize, String> {
+    match parse_number(option, value)? {
+        0 => Err(format!("Expected at least one job after {}", option)),
+        jobs => Ok(jobs),
+    }
+}
+
+/// Parse the arguments after `generate`, which are the day then `--size N` and `--seed S`
+fn parse_generate<I: Iterator<Item = String>>(mut args: I) -> Result<Task, String> {
+    let day = parse_day(&args.next().ok_or("Expected the day to make an input for")?)?;
+    let mut size = None;
+    let mut seed = 0;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--size" => size = Some(parse_number(&arg, args.next())?),
+            "--seed" => seed = parse_number(&arg, args.next())?,
+            _ => return Err(format!("Expected `--size` or `--seed`, not {:?}", arg)),
+        }
+    }
+
+    Ok(Task::Generate { day, size, seed })
+}
+
+/// Parse the arguments after `crosscheck`, which are the days then `--runs N`, `--size N` and
+/// `--jobs N`
+fn parse_crosscheck<I: Iterator<Item = String>>(mut args: I) -> Result<Task, String> {
+    let mut days = Vec::new();
+    let mut runs = None;
+    let mut size = None;
+    let mut jobs = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--runs" => runs = Some(parse_number(&arg, args.next())?),
+            "--size" => size = Some(parse_number(&arg, args.next())?),
+            "--jobs" => jobs = Some(parse_jobs(&arg, args.next())?),
+            _ => {
+                let day = parse_day(&arg)?;
+                if !CROSSCHECK_DAYS.contains(&day) {
+                    return Err(format!("Day {} has no reference solution to check", day));
+                }
+                days.push(day);
+            }
+        }
+    }
+    if days.is_empty() {
+        days = CROSSCHECK_DAYS.to_vec();
+    }
+
+    Ok(Task::Crosscheck {
+        days,
+        runs,
+        size,
+        jobs,
+    })
+}
+
+/// Build every day with the same profile as this binary, so that they are up to date
+fn build_days() -> Result<(), String> {
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let mut command = Command::new(cargo);
+    command
+        .args(["build", "--quiet", "--examples"])
+        .current_dir(env!("CARGO_MANIFEST_DIR"));
+    if !cfg!(debug_assertions) {
+        command.arg("--release");
+    }
+
+    let status = command
+        .status()
+        .map_err(|e| format!("Could not run cargo: {}", e))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err("Could not build the days".to_string())
+    }
+}
+
+/// Whether a day's real input is in `input/`, which isn't kept in the repository for every day
+fn has_input(day: u8) -> bool {
+    std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("input")
+        .join(format!("day{:02}.txt", day))
+        .is_file()
+}
+
+/// Where cargo puts the binary for a day, next to this one
+fn day_path(day: u8) -> Result<PathBuf, String> {
+    let exe = std::env::current_exe().map_err(|e| format!("Could not find the runner: {}", e))?;
+    let dir = exe.parent().ok_or("The runner is not in a directory")?;
+    Ok(dir.join("examples").join(format!("day{:02}", day)))
+}
+
+/// What a day printed, and how long it took
+struct DayOutput {
+    success: bool,
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+    elapsed: Duration,
+}
+
+/// Run a day from the top of the repository, where it looks for its input, keeping what it
+/// prints to show once it has finished
+fn run_day(day: u8, args: &[String]) -> Result<DayOutput, String> {
+    let mut command = Command::new(day_path(day)?);
+    command.current_dir(env!("CARGO_MANIFEST_DIR")).args(args);
+
+    let start = Instant::now();
+    let output = command
+        .output()
+        .map_err(|e| format!("Could not run day {}: {}", day, e))?;
+    Ok(DayOutput {
+        success: output.status.success(),
+        stdout: output.stdout,
+        stderr: output.stderr,
+        elapsed: start.elapsed(),
+    })
+}
+
+/// Print what a day printed, and how long it took, and say whether it succeeded
+fn report(day: u8, output: Result<DayOutput, String>) -> bool {
+    let output = match output {
+        Ok(output) => output,
+        Err(e) => {
+            println!("Day {:02}", day);
+            eprintln!("{}", e);
+            println!();
+            return false;
+        }
+    };
+
+    println!("Day {:02} ({:.3} s)", day, output.elapsed.as_secs_f64());
+    // If the output can't be shown there is nowhere to say so, so carry on with the next day
+    let _ = std::io::stdout().write_all(&output.stdout);
+    let _ = std::io::stderr().write_all(&output.stderr);
+    println!();
+    output.success
+}
+
+/// Run each day with `args`, up to `jobs` at once, giving the days that failed. The days are
+/// started in order, and each is printed as soon as it and every day before it have finished,
+/// so the output is always in the same order.
+fn run(days: &[u8], args: &[String], jobs: usize) -> Result<Vec<u8>, String> {
+    build_days()?;
+
+    let start = Instant::now();
+    let next_idx = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+    let mut failed = Vec::new();
+    std::thread::scope(|scope| {
+        for _ in 0..jobs.min(days.len()) {
+            let sender = sender.clone();
+            let next_idx = &next_idx;
+            scope.spawn(move || loop {
+                let idx = next_idx.fetch_add(1, Ordering::Relaxed);
+                let day = match days.get(idx) {
+                    Some(&day) => day,
+                    None => break,
+                };
+                if sender.send((idx, run_day(day, args))).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(sender);
+
+        let mut finished: Vec<Option<Result<DayOutput, String>>> =
+            days.iter().map(|_| None).collect();
+        let mut next_to_print = 0;
+        for (idx, output) in receiver {
+            finished[idx] = Some(output);
+            while let Some(output) = finished.get_mut(next_to_print).and_then(Option::take) {
+                let day = days[next_to_print];
+                if !report(day, output) {
+                    failed.push(day);
+                }
+                next_to_print += 1;
+            }
+        }
+    });
+    println!(
+        "Ran {} days in {:.3} s",
+        days.len(),
+        start.elapsed().as_secs_f64()
+    );
+
+    Ok(failed)
+}
+
 fn main() {
-    println!("Hello, world!");
+    let (days, mut args, jobs) = match parse_args(std::env::args().skip(1)) {
+        Ok(Task::Run(options)) if options.example => {
+            (options.days, vec!["--example".to_string()], options.jobs)
+        }
+        Ok(Task::Run(options)) => {
+            // A day can't be solved without its input, so say so rather than fail
+            let (days, skipped): (Vec<u8>, Vec<u8>) =
+                options.days.into_iter().partition(|&day| has_input(day));
+            for day in skipped {
+                println!(
+                    "Skipping day {:02}, which has no input/day{:02}.txt",
+                    day, day
+                );
+            }
+            (days, vec![], options.jobs)
+        }
+        Ok(Task::Crosscheck {
+            days,
+            runs,
+            size,
+            jobs,
+        }) => {
+            let mut args = vec!["--crosscheck".to_string()];
+            args.extend(runs.map(|runs| format!("--runs={}", runs)));
+            args.extend(size.map(|size| format!("--size={}", size)));
+            (days, args, jobs)
+        }
+        Ok(Task::Generate { day, size, seed }) => match generate::generate(day, size, seed) {
+            Ok(input) => {
+                print!("{}", input);
+                return;
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(2);
+            }
+        },
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+
+    // The days share out their own searches between threads, so only limit those too if
+    // asked to
+    args.extend(jobs.map(|jobs| format!("--jobs={}", jobs)));
+    let jobs = jobs.unwrap_or_else(parallel::default_jobs);
+
+    match run(&days, &args, jobs) {
+        Ok(failed) if failed.is_empty() => {}
+        Ok(failed) => {
+            eprintln!("These days failed: {:?}", failed);
+            std::process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+#[test]
+fn test_parse_args() {
+    let args = |args: &[&str]| parse_args(args.iter().map(|arg| arg.to_string()));
+
+    assert_eq!(
+        Ok(Task::Run(Options {
+            example: false,
+            days: (1..=25).collect(),
+            jobs: None
+        })),
+        args(&[])
+    );
+    assert_eq!(
+        Ok(Task::Run(Options {
+            example: true,
+            days: vec![5, 12],
+            jobs: None
+        })),
+        args(&["5", "--example", "day12"])
+    );
+    assert_eq!(
+        Ok(Task::Run(Options {
+            example: false,
+            days: vec![17, 19],
+            jobs: Some(3)
+        })),
+        args(&["--jobs", "3", "17", "19"])
+    );
+    assert_eq!(
+        Err("Expected at least one job after --jobs".to_string()),
+        args(&["--jobs", "0"])
+    );
+    assert!(args(&["--jobs", "2", "generate"]).is_err());
+    assert_eq!(Err("There is no day 26".to_string()), args(&["26"]));
+    assert!(args(&["--examples"]).is_err());
+
+    assert_eq!(
+        Ok(Task::Generate {
+            day: 15,
+            size: Some(1000),
+            seed: 7
+        }),
+        args(&["generate", "day15", "--seed", "7", "--size", "1000"])
+    );
+    assert_eq!(
+        Ok(Task::Generate {
+            day: 4,
+            size: None,
+            seed: 0
+        }),
+        args(&["generate", "4"])
+    );
+    assert!(args(&["generate"]).is_err());
+    assert!(args(&["generate", "4", "--size"]).is_err());
+    assert!(args(&["generate", "4", "--size", "big"]).is_err());
+    assert!(args(&["5", "generate"]).is_err());
+
+    assert_eq!(
+        Ok(Task::Crosscheck {
+            days: vec![6, 7, 14, 22],
+            runs: None,
+            size: None,
+            jobs: None
+        }),
+        args(&["crosscheck"])
+    );
+    assert_eq!(
+        Ok(Task::Crosscheck {
+            days: vec![22],
+            runs: Some(1000),
+            size: Some(50),
+            jobs: Some(2)
+        }),
+        args(&[
+            "crosscheck",
+            "22",
+            "--runs",
+            "1000",
+            "--size",
+            "50",
+            "--jobs",
+            "2"
+        ])
+    );
+    assert_eq!(
+        Err("Day 5 has no reference solution to check".to_string()),
+        args(&["crosscheck", "5"])
+    );
+    assert!(args(&["crosscheck", "--runs", "-1"]).is_err());
 }
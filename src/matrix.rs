@@ -0,0 +1,112 @@
+//! Arithmetic on square matrices of counts, for the days that project how things grow by
+//! raising a transition matrix to a power
+
+use ndarray::{Array2, ArrayView2};
+
+/// Multiply two square matrices, or `None` if any element overflows
+pub fn checked_matmul(a: ArrayView2<u128>, b: ArrayView2<u128>) -> Option<Array2<u128>> {
+    let n = a.nrows();
+    let mut result = Array2::zeros((n, n));
+    for row in 0..n {
+        for col in 0..n {
+            let mut total: u128 = 0;
+            for k in 0..n {
+                total = total.checked_add(a[[row, k]].checked_mul(b[[k, col]])?)?;
+            }
+            result[[row, col]] = total;
+        }
+    }
+    Some(result)
+}
+
+/// Raise a square matrix to the power `exp` by repeated squaring, or `None` if any element
+/// overflows
+pub fn checked_matrix_power(m: ArrayView2<u128>, mut exp: usize) -> Option<Array2<u128>> {
+    let mut result = Array2::eye(m.nrows());
+    let mut base = m.to_owned();
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = checked_matmul(result.view(), base.view())?;
+        }
+        exp >>= 1;
+        if exp > 0 {
+            base = checked_matmul(base.view(), base.view())?;
+        }
+    }
+    Some(result)
+}
+
+/// Multiply two square matrices whose elements are all less than `modulus`, reducing every
+/// element of the result modulo `modulus`
+pub fn matmul_mod(a: ArrayView2<u128>, b: ArrayView2<u128>, modulus: u64) -> Array2<u128> {
+    let modulus = modulus as u128;
+    let n = a.nrows();
+    let mut result = Array2::zeros((n, n));
+    for row in 0..n {
+        for col in 0..n {
+            let mut total: u128 = 0;
+            for k in 0..n {
+                // Both factors are below 2^64, so neither the product nor the sum can overflow
+                total = (total + a[[row, k]] * b[[k, col]]) % modulus;
+            }
+            result[[row, col]] = total;
+        }
+    }
+    result
+}
+
+/// Raise a square matrix to the power `exp` by repeated squaring, with every element reduced
+/// modulo `modulus`. This never overflows, so it can go as many steps as you like.
+pub fn matrix_power_mod(m: ArrayView2<u128>, mut exp: usize, modulus: u64) -> Array2<u128> {
+    let mut result = Array2::eye(m.nrows()).mapv(|x: u128| x % modulus as u128);
+    let mut base = m.mapv(|x| x % modulus as u128);
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = matmul_mod(result.view(), base.view(), modulus);
+        }
+        exp >>= 1;
+        if exp > 0 {
+            base = matmul_mod(base.view(), base.view(), modulus);
+        }
+    }
+    result
+}
+
+#[test]
+fn test_checked_matrix_power() {
+    // Fibonacci numbers
+    let m = ndarray::arr2(&[[1, 1], [1, 0]]);
+    assert_eq!(Some(Array2::eye(2)), checked_matrix_power(m.view(), 0));
+    assert_eq!(
+        Some(ndarray::arr2(&[[89, 55], [55, 34]])),
+        checked_matrix_power(m.view(), 10)
+    );
+    // F(186) is the largest that fits in a u128
+    assert!(checked_matrix_power(m.view(), 185).is_some());
+    assert_eq!(None, checked_matrix_power(m.view(), 200));
+}
+
+#[test]
+fn test_matrix_power_mod() {
+    // Fibonacci numbers, which overflow a u128 long before this
+    let m = ndarray::arr2(&[[1, 1], [1, 0]]);
+    assert_eq!(
+        ndarray::arr2(&[[89, 55], [55, 34]]),
+        matrix_power_mod(m.view(), 10, 1000)
+    );
+    assert_eq!(
+        checked_matrix_power(m.view(), 185)
+            .unwrap()
+            .mapv(|x| x % 1_000_000_007),
+        matrix_power_mod(m.view(), 185, 1_000_000_007)
+    );
+    // F(1000) mod 1,000,000,007
+    assert_eq!(
+        517691607,
+        matrix_power_mod(m.view(), 1000, 1_000_000_007)[[0, 1]]
+    );
+    assert_eq!(
+        Array2::<u128>::zeros((2, 2)),
+        matrix_power_mod(m.view(), 0, 1)
+    );
+}
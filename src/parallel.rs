@@ -0,0 +1,116 @@
+//! Sharing out independent pieces of work between threads, for the searches that check lots
+//! of candidates. However many threads are used, the answers come out the same, and in the
+//! same order, as doing the work on one thread.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// One thread per core, or one if the number of cores can't be found
+pub fn default_jobs() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+}
+
+/// How many threads a day should use, which is `--jobs=N` if it was passed, or one per core
+pub fn jobs() -> usize {
+    std::env::args()
+        .find_map(|arg| {
+            arg.strip_prefix("--jobs=")
+                .map(|n| n.parse().expect("Could not parse the number of jobs"))
+        })
+        .unwrap_or_else(default_jobs)
+        .max(1)
+}
+
+/// Apply `f` to every item, splitting the items between up to `jobs` threads, and give the
+/// results in the same order as the items
+pub fn map<T, R, F>(items: &[T], jobs: usize, f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    if jobs <= 1 || items.len() <= 1 {
+        return items.iter().map(f).collect();
+    }
+
+    let chunk_size = items.len().div_ceil(jobs);
+    let f = &f;
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = items
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(move || chunk.iter().map(f).collect::<Vec<R>>()))
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|e| std::panic::resume_unwind(e))
+            })
+            .collect()
+    })
+}
+
+/// The result of `f` for the lowest index in `0..n` that it gives one for, trying up to `jobs`
+/// indices at once. The indices are handed out in order, and none are started after one has
+/// a result, so this finds the same one as trying each in turn would.
+pub fn find_first<R, F>(n: usize, jobs: usize, f: F) -> Option<R>
+where
+    R: Send,
+    F: Fn(usize) -> Option<R> + Sync,
+{
+    if jobs <= 1 {
+        return (0..n).find_map(f);
+    }
+
+    let next_idx = AtomicUsize::new(0);
+    let found: Mutex<Option<(usize, R)>> = Mutex::new(None);
+    std::thread::scope(|scope| {
+        for _ in 0..jobs.min(n) {
+            scope.spawn(|| loop {
+                let idx = next_idx.fetch_add(1, Ordering::Relaxed);
+                let found_earlier = |found: &Option<(usize, R)>| {
+                    matches!(found, Some((found_idx, _)) if *found_idx < idx)
+                };
+                if idx >= n || found_earlier(&found.lock().unwrap()) {
+                    break;
+                }
+                if let Some(result) = f(idx) {
+                    let mut found = found.lock().unwrap();
+                    if !found_earlier(&found) {
+                        *found = Some((idx, result));
+                    }
+                    break;
+                }
+            });
+        }
+    });
+
+    found.into_inner().unwrap().map(|(_, result)| result)
+}
+
+#[test]
+fn test_map() {
+    let items: Vec<u64> = (0..1000).collect();
+    let expected: Vec<u64> = items.iter().map(|n| n * n).collect();
+    for jobs in [1, 2, 3, 8, 2000] {
+        assert_eq!(expected, map(&items, jobs, |n| n * n));
+    }
+    assert_eq!(Vec::<u64>::new(), map(&[], 4, |n: &u64| *n));
+}
+
+#[test]
+fn test_find_first() {
+    // Later indices are quicker, so they finish first, but the earliest still wins
+    let slow_multiple_of_7 = |idx: usize| {
+        std::thread::sleep(std::time::Duration::from_micros(100 - idx as u64));
+        (idx > 0 && idx.is_multiple_of(7)).then_some(idx * 10)
+    };
+    for jobs in [1, 2, 4, 16] {
+        assert_eq!(Some(70), find_first(100, jobs, slow_multiple_of_7));
+        assert_eq!(None, find_first(5, jobs, slow_multiple_of_7));
+    }
+    assert_eq!(None, find_first(0, 4, Some));
+}
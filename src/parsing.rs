@@ -0,0 +1,148 @@
+//! Helpers for reading puzzle inputs.
+//!
+//! Everything here accepts both `\n` and `\r\n` line endings, and ignores whitespace at the
+//! end of lines, so inputs saved on any platform parse the same way.
+
+use std::str::FromStr;
+
+use ndarray::Array2;
+
+/// Every line of the input, without line endings or trailing whitespace
+pub fn lines(input: &str) -> impl Iterator<Item = &str> {
+    input.lines().map(str::trim_end)
+}
+
+/// Split the input into sections separated by blank lines, giving the lines in each.
+///
+/// Any number of blank lines can separate sections, and blank lines at the start and end are
+/// ignored.
+pub fn sections(input: &str) -> Vec<Vec<&str>> {
+    let mut result: Vec<Vec<&str>> = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+
+    for line in lines(input) {
+        if line.trim().is_empty() {
+            if !current.is_empty() {
+                result.push(std::mem::take(&mut current));
+            }
+        } else {
+            current.push(line);
+        }
+    }
+    if !current.is_empty() {
+        result.push(current);
+    }
+
+    result
+}
+
+/// Parse every number in a line, separated by commas, whitespace, or both
+pub fn numbers<T: FromStr>(line: &str) -> Result<Vec<T>, String> {
+    line.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            s.parse()
+                .map_err(|_| format!("Could not parse {:?} as a number", s))
+        })
+        .collect()
+}
+
+/// Parse an `x,y,z` triple
+pub fn triple<T: FromStr>(line: &str) -> Result<(T, T, T), String> {
+    let mut values = numbers::<T>(line)?.into_iter();
+    match (values.next(), values.next(), values.next(), values.next()) {
+        (Some(x), Some(y), Some(z), None) => Ok((x, y, z)),
+        _ => Err(format!("Expected three numbers in {:?}", line)),
+    }
+}
+
+/// Split a `key -> value` rule into its key and value
+pub fn rule(line: &str) -> Result<(&str, &str), String> {
+    line.split_once("->")
+        .map(|(key, value)| (key.trim(), value.trim()))
+        .filter(|(key, value)| !key.is_empty() && !value.is_empty())
+        .ok_or_else(|| format!("Expected a rule like `key -> value`, not {:?}", line))
+}
+
+/// Read lines of single digits into a grid, with a row for each line
+pub fn digit_grid(lines: &[&str]) -> Result<Array2<u8>, String> {
+    let ncols = lines.first().map_or(0, |line| line.trim().chars().count());
+    let mut grid = Array2::<u8>::zeros((lines.len(), ncols));
+
+    for (row_idx, line) in lines.iter().enumerate() {
+        let line = line.trim();
+        if line.chars().count() != ncols {
+            return Err(format!(
+                "Row {} has {} digits, but the first row has {}",
+                row_idx + 1,
+                line.chars().count(),
+                ncols
+            ));
+        }
+        for (col_idx, c) in line.chars().enumerate() {
+            grid[(row_idx, col_idx)] = c
+                .to_digit(10)
+                .ok_or_else(|| format!("{:?} in row {} is not a digit", c, row_idx + 1))?
+                as u8;
+        }
+    }
+
+    Ok(grid)
+}
+
+#[test]
+fn test_sections() {
+    let expected = vec![vec!["a", "b"], vec!["c"], vec!["d  e"]];
+    assert_eq!(expected, sections("a\nb\n\nc\n\n\nd  e\n"));
+
+    // Windows line endings, trailing whitespace, and blank lines that aren't quite empty
+    assert_eq!(
+        expected,
+        sections("\r\na  \r\nb\r\n  \r\nc\t\r\n\r\n\r\nd  e\r\n\r\n")
+    );
+    assert!(sections("\n \n").is_empty());
+}
+
+#[test]
+fn test_numbers() {
+    assert_eq!(Ok(vec![7, 4, 9, 5]), numbers::<u16>("7,4,9,5"));
+    assert_eq!(Ok(vec![22, 13, 0]), numbers::<u16>("22 13  0"));
+    assert_eq!(Ok(vec![-1, 2, 3]), numbers::<i64>(" -1, 2,3 "));
+    assert_eq!(
+        Err("Could not parse \"x\" as a number".to_string()),
+        numbers::<u16>("1,x")
+    );
+}
+
+#[test]
+fn test_triple() {
+    assert_eq!(Ok((404, -588, -901)), triple::<i64>("404,-588,-901"));
+    assert!(triple::<i64>("1,2").is_err());
+    assert!(triple::<i64>("1,2,3,4").is_err());
+}
+
+#[test]
+fn test_rule() {
+    assert_eq!(Ok(("CH", "B")), rule("CH -> B"));
+    assert_eq!(Ok(("CH", "B")), rule("CH->B  "));
+    assert!(rule("CH B").is_err());
+    assert!(rule("CH -> ").is_err());
+}
+
+#[test]
+fn test_digit_grid() {
+    use ndarray::arr2;
+
+    assert_eq!(
+        Ok(arr2(&[[2, 1, 9], [3, 9, 8]])),
+        digit_grid(&["219", "398"])
+    );
+    assert_eq!(
+        Err("Row 2 has 2 digits, but the first row has 3".to_string()),
+        digit_grid(&["219", "39"])
+    );
+    assert_eq!(
+        Err("'x' in row 1 is not a digit".to_string()),
+        digit_grid(&["2x9"])
+    );
+}
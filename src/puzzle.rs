@@ -0,0 +1,140 @@
+//! Checking a day's solutions against the examples in its puzzle description, so that changes
+//! can be checked without the real inputs.
+
+/// An example from a puzzle description
+#[derive(Debug, Clone, Copy)]
+pub struct Example {
+    pub input: &'static str,
+    /// The answers the description gives, if it gives one for that part
+    pub part1: Option<&'static str>,
+    pub part2: Option<&'static str>,
+}
+
+/// Solves one part of a puzzle, straight from its input
+pub type Part = fn(&str) -> Result<String, String>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Passed(String),
+    Failed {
+        expected: String,
+        got: String,
+    },
+    Errored(String),
+    /// There is an answer to check, but no solution for that part yet
+    Unsolved,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    /// Which example, counting from 1
+    pub example: usize,
+    pub part: usize,
+    pub outcome: Outcome,
+}
+
+impl Check {
+    pub fn is_ok(&self) -> bool {
+        matches!(self.outcome, Outcome::Passed(_) | Outcome::Unsolved)
+    }
+}
+
+impl std::fmt::Display for Check {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Example {} part {}: ", self.example, self.part)?;
+        match &self.outcome {
+            Outcome::Passed(answer) => write!(f, "passed ({})", answer),
+            Outcome::Failed { expected, got } => {
+                write!(f, "FAILED, expected {} but got {}", expected, got)
+            }
+            Outcome::Errored(e) => write!(f, "FAILED, {}", e),
+            Outcome::Unsolved => write!(f, "no solution yet"),
+        }
+    }
+}
+
+/// Run each part on every example that has an answer for it
+pub fn check_examples(examples: &[Example], parts: [Option<Part>; 2]) -> Vec<Check> {
+    let mut checks = Vec::new();
+
+    for (example_idx, example) in examples.iter().enumerate() {
+        for (part_idx, (expected, part)) in [example.part1, example.part2]
+            .into_iter()
+            .zip(parts)
+            .enumerate()
+        {
+            let expected = match expected {
+                Some(expected) => expected,
+                None => continue,
+            };
+            let outcome = match part.map(|part| part(example.input)) {
+                None => Outcome::Unsolved,
+                Some(Ok(got)) if got == expected => Outcome::Passed(got),
+                Some(Ok(got)) => Outcome::Failed {
+                    expected: expected.to_string(),
+                    got,
+                },
+                Some(Err(e)) => Outcome::Errored(e),
+            };
+            checks.push(Check {
+                example: example_idx + 1,
+                part: part_idx + 1,
+                outcome,
+            });
+        }
+    }
+
+    checks
+}
+
+/// Check the examples and print how each went, giving the exit code for the day
+pub fn run_examples(examples: &[Example], parts: [Option<Part>; 2]) -> i32 {
+    let checks = check_examples(examples, parts);
+    if checks.is_empty() {
+        println!("There are no examples to check");
+    }
+    for check in &checks {
+        println!("{}", check);
+    }
+
+    if checks.iter().all(Check::is_ok) {
+        0
+    } else {
+        1
+    }
+}
+
+#[test]
+fn test_check_examples() {
+    const EXAMPLES: &[Example] = &[
+        Example {
+            input: "1,2,3",
+            part1: Some("6"),
+            part2: Some("3"),
+        },
+        Example {
+            input: "1,x",
+            part1: Some("1"),
+            part2: None,
+        },
+    ];
+    fn sum(input: &str) -> Result<String, String> {
+        crate::parsing::numbers::<i64>(input).map(|nums| nums.iter().sum::<i64>().to_string())
+    }
+
+    let checks = check_examples(EXAMPLES, [Some(sum), None]);
+    let outcomes: Vec<Outcome> = checks.iter().map(|check| check.outcome.clone()).collect();
+    assert_eq!(
+        vec![
+            Outcome::Passed("6".to_string()),
+            Outcome::Unsolved,
+            Outcome::Errored("Could not parse \"x\" as a number".to_string()),
+        ],
+        outcomes
+    );
+    assert_eq!(
+        "Example 2 part 1: FAILED, Could not parse \"x\" as a number",
+        checks[2].to_string()
+    );
+    assert!(!checks[2].is_ok());
+}
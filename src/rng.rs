@@ -0,0 +1,90 @@
+//! A small seedable random number generator, so that generated inputs can be made again from
+//! their seed.
+//!
+//! This is SplitMix64, which is quick and random enough for making puzzle inputs, but is not
+//! suitable for anything that needs to be unpredictable.
+
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..n`, with every number equally likely
+    pub fn below(&mut self, n: u64) -> u64 {
+        assert!(n > 0, "Cannot pick a number below 0");
+        // Reject the numbers at the top that would make some remainders more likely
+        let limit = u64::MAX - u64::MAX % n;
+        loop {
+            let x = self.next_u64();
+            if x < limit {
+                return x % n;
+            }
+        }
+    }
+
+    /// An index into something of length `len`
+    pub fn index(&mut self, len: usize) -> usize {
+        self.below(len as u64) as usize
+    }
+
+    /// A number in `low..=high`
+    pub fn range(&mut self, low: i64, high: i64) -> i64 {
+        assert!(low <= high, "The range {}..={} is empty", low, high);
+        let width = high.abs_diff(low);
+        if width == u64::MAX {
+            self.next_u64() as i64
+        } else {
+            low.wrapping_add(self.below(width + 1) as i64)
+        }
+    }
+
+    /// True with probability `numerator / denominator`
+    pub fn chance(&mut self, numerator: u64, denominator: u64) -> bool {
+        self.below(denominator) < numerator
+    }
+
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.index(items.len())]
+    }
+
+    /// Shuffle `items` in place, with every order equally likely
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for idx in (1..items.len()).rev() {
+            items.swap(idx, self.index(idx + 1));
+        }
+    }
+}
+
+#[test]
+fn test_rng() {
+    // The first outputs of SplitMix64 seeded with 0
+    let mut rng = Rng::new(0);
+    assert_eq!(0xE220_A839_7B1D_CDAF, rng.next_u64());
+    assert_eq!(0x6E78_9E6A_A1B9_65F4, rng.next_u64());
+
+    let mut rng = Rng::new(2021);
+    for _ in 0..1000 {
+        assert!((-3..=3).contains(&rng.range(-3, 3)));
+        assert!(rng.below(7) < 7);
+    }
+    assert_eq!(i64::MIN, Rng::new(1).range(i64::MIN, i64::MIN));
+
+    let mut items: Vec<u32> = (0..50).collect();
+    rng.shuffle(&mut items);
+    assert_ne!((0..50).collect::<Vec<u32>>(), items);
+    items.sort_unstable();
+    assert_eq!((0..50).collect::<Vec<u32>>(), items);
+}